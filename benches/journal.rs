@@ -0,0 +1,62 @@
+// Comment: criterion isn't a dependency, so this is a plain `harness = false` benchmark reporting
+// the median of a few runs. It exits with a failure when listing 100k tasks or counting 1k tasks breaks
+// the time budgets documented on `Task::list_to` and `Task::count`
+use std::io;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use rusty_journal_clap::task::Task;
+use rusty_journal_clap::test_util::TempJournal;
+
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+const RUNS: usize = 7;
+const LIST_BUDGET: Duration = Duration::from_millis(250);
+// counting stays under a millisecond for a journal of 1k tasks, since the count ends up in shell prompts
+const COUNT_BUDGET: Duration = Duration::from_millis(1);
+
+fn journal(size: usize) -> TempJournal {
+    let tasks = (0..size).map(|i| (format!("Task number {}", i),
+                                   if i % 3 == 0 { vec!["work".to_string()] } else { Vec::new() },
+                                   i % 2 == 0))
+                         .collect();
+    TempJournal::with_tasks(tasks).unwrap()
+}
+
+/// Times the operation against a fresh journal of the given size for every run, returning the median
+fn median(size: usize, operation: impl Fn(&TempJournal)) -> Duration {
+    let mut timings = (0..RUNS).map(|_| {
+                                   let journal = journal(size);
+                                   let start = Instant::now();
+                                   operation(&journal);
+                                   start.elapsed()
+                               })
+                               .collect::<Vec<_>>();
+    timings.sort();
+    timings[RUNS / 2]
+}
+
+fn main() -> ExitCode {
+    let mut within_budget = true;
+    println!("{:<8} {:>8} {:>12}", "", "tasks", "median");
+    for size in SIZES {
+        let add = median(size, |journal| Task::add(journal.path(), "benchmark".to_string(), None).unwrap());
+        let list = median(size, |journal| Task::list_to(journal.path(), None, io::sink()).unwrap());
+        let remove = median(size, |journal| Task::remove(journal.path(), 1).unwrap());
+        let count = median(size, |journal| { Task::count(journal.path(), None).unwrap(); });
+
+        println!("{:<8} {:>8} {:>12?}", "add", size, add);
+        println!("{:<8} {:>8} {:>12?}", "list", size, list);
+        println!("{:<8} {:>8} {:>12?}", "remove", size, remove);
+        println!("{:<8} {:>8} {:>12?}", "count", size, count);
+
+        if size == 100_000 && list > LIST_BUDGET {
+            println!("listing {} tasks took {:?}, over the budget of {:?}", size, list, LIST_BUDGET);
+            within_budget = false;
+        }
+        if size == 1_000 && count > COUNT_BUDGET {
+            println!("counting {} tasks took {:?}, over the budget of {:?}", size, count, COUNT_BUDGET);
+            within_budget = false;
+        }
+    }
+
+    if within_budget { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
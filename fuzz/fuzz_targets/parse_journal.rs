@@ -0,0 +1,13 @@
+#![no_main]
+// run with `cargo +nightly fuzz run parse_journal`
+use libfuzzer_sys::fuzz_target;
+use rusty_journal_clap::task::parse_journal;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(tasks) = parse_journal(data) {
+        // whatever parses gets listed, so printing the tasks must not panic either
+        for task in tasks {
+            let _ = task.to_string();
+        }
+    }
+});
@@ -0,0 +1,259 @@
+# Deutsche Meldungen
+
+## Listing
+empty-list = Leere Aufgabenliste
+label-task = Aufgabe:
+label-created = Erstellt am:
+label-updated = Geändert am:
+label-due = Fällig:
+label-waiting = Wartet bis:
+label-done = Erledigt am:
+nothing-to-do = Nichts zu tun
+
+## Details of `show`
+task-heading = Aufgabe { $index }: { $name }
+label-id = Id:
+label-state = Status:
+label-tags = Tags:
+label-priority = Priorität:
+label-estimate = Schätzung:
+label-delegated = Delegiert:
+label-goal = Ziel:
+label-related = Verwandt:
+label-parent = Übergeordnet:
+label-repeats = Wiederholt:
+label-link = Link:
+label-owner = Besitzer:
+label-assignee = Zuständig:
+state-active = offen
+state-waiting-until = wartet bis { $date }
+label-waiting-for = Wartet auf:
+state-complete-since = erledigt seit { $date }
+history = Verlauf
+
+## Plain reports
+report-title = { $period ->
+    [daily] Tagesbericht
+   *[weekly] Wochenbericht
+}, { $since } bis { $until }
+section-added = Hinzugefügt ({ $count })
+section-completed = Erledigt ({ $count })
+section-overdue = Überfällig ({ $count })
+section-pending = Offen ({ $count })
+
+## Plans
+plan-title = Plan für { $days ->
+    [one] 1 Tag
+   *[other] { $days } Tage
+}, { $capacity } pro Tag
+plan-day = { $date }  { $planned } / { $capacity }
+plan-overcommitted = um { $over } überbucht
+plan-no-estimate = keine Schätzung
+plan-unscheduled = Ohne Termin: { $count ->
+    [one] 1 Aufgabe
+   *[other] { $count } Aufgaben
+}, { $estimate }
+
+## Goals
+no-goals = Keine Ziele
+goal-progress = { $done }/{ $total } Aufgaben erledigt
+
+## Trash
+trash-empty = Der Papierkorb ist leer
+trashed-task = { $id }: { $name } (entfernt am { $date })
+undone = `{ $command }` vom { $time } rückgängig gemacht
+undone-change = Änderung vom { $time } rückgängig gemacht
+undo-empty = Es gibt nichts rückgängig zu machen
+ticked = { $count ->
+    [0] Keine wiederkehrende Aufgabe steht wieder an
+    [one] Nächstes Vorkommen von 1 wiederkehrenden Aufgabe hinzugefügt
+   *[other] Nächste Vorkommen von { $count } wiederkehrenden Aufgaben hinzugefügt
+}
+error-undo-nothing = es gibt nichts rückgängig zu machen
+error-undo-steps = { $count ->
+    [one] nur 1 Änderung kann rückgängig gemacht werden
+   *[other] nur { $count } Änderungen können rückgängig gemacht werden
+}
+error-undo-stdio = ein von stdin gelesenes Journal hat keine Sicherungen, um seine Änderungen rückgängig zu machen
+task-restored = { $name } ist wieder Aufgabe { $index }
+
+## Stale tasks
+stale-banner = { $count ->
+    [one] 1 offene Aufgabe
+   *[other] { $count } offene Aufgaben
+} seit { days } oder länger unverändert, siehe `list --stale { $span }`
+stats-states = { $active } offen, { $waiting } wartend, { $complete } erledigt
+stats-stale = { $count } veraltet, seit { days } oder länger unverändert
+stats-oldest = Älteste offene Aufgaben
+info-journal = Journal:
+info-format = Format:
+info-schema = Schema:
+info-size = Größe:
+info-tasks = Aufgaben:
+info-oldest = Älteste:
+info-newest = Neueste:
+info-lock = Sperre:
+info-stdin = Standardeingabe
+info-bytes = { $bytes } Bytes
+info-missing = existiert noch nicht
+info-counts = { $total } ({ $active } offen, { $waiting } wartend, { $complete } erledigt)
+info-daemon = vom Daemon auf { $path } gehalten
+info-unlocked = keine
+info-writing = von einem anderen Prozess gehalten, der das Journal schreibt
+untouched-for = seit { days } unverändert
+
+## Bulk changes
+tasks-completed = { $count ->
+    [one] 1 Aufgabe erledigt
+   *[other] { $count } Aufgaben erledigt
+}
+tasks-would-complete = { $count ->
+    [one] 1 Aufgabe würde erledigt
+   *[other] { $count } Aufgaben würden erledigt
+}
+tasks-removed = { $count ->
+    [one] 1 Aufgabe entfernt
+   *[other] { $count } Aufgaben entfernt
+}
+tasks-would-remove = { $count ->
+    [one] 1 Aufgabe würde entfernt
+   *[other] { $count } Aufgaben würden entfernt
+}
+tasks-updated = { $count ->
+    [one] 1 Aufgabe geändert
+   *[other] { $count } Aufgaben geändert
+}
+tasks-would-update = { $count ->
+    [one] 1 Aufgabe würde geändert
+   *[other] { $count } Aufgaben würden geändert
+}
+tasks-imported = { $count ->
+    [one] 1 Aufgabe importiert
+   *[other] { $count } Aufgaben importiert
+}
+tasks-would-import = { $count ->
+    [one] 1 Aufgabe würde importiert
+   *[other] { $count } Aufgaben würden importiert
+}
+import-skipped = , { $count } schon im Journal
+tasks-appended = { $count ->
+    [one] 1 Aufgabe angehängt
+   *[other] { $count } Aufgaben angehängt
+}
+nothing-to-complete = Keine Aufgabe zu erledigen
+nothing-to-remove = Keine Aufgabe zu entfernen
+confirm-complete = { $count ->
+    [one] 1 Aufgabe erledigen?
+   *[other] { $count } Aufgaben erledigen?
+}
+confirm-remove = { $count ->
+    [one] 1 Aufgabe entfernen?
+   *[other] { $count } Aufgaben entfernen?
+}
+confirm-answers = [j/N]
+# the English answers are taken as well, for the habit of typing them
+confirm-yes = j ja y yes
+aborted = Abgebrochen
+
+## Notifications
+notify-overdue = Aufgabe überfällig
+notify-completed = Aufgabe mit hoher Priorität erledigt
+
+## Relative times
+# the German phrases need the days in the dative, so each phrase selects on its own
+days = { $days ->
+    [0] weniger als ein Tag
+    [one] 1 Tag
+   *[other] { $days } Tage
+}
+overdue-by = { $days ->
+    [0] seit heute überfällig
+    [one] seit 1 Tag überfällig
+   *[other] seit { $days } Tagen überfällig
+}
+due-in = { $days ->
+    [0] heute fällig
+    [one] fällig in 1 Tag
+   *[other] fällig in { $days } Tagen
+}
+age = { days } alt
+days-left = noch { days }
+tag-count = { $count ->
+    [one] 1 Schlagwort
+   *[other] { $count } Schlagwörter
+}
+
+## Errors
+error-index-out-of-range = { $max ->
+    [one] Ungültige Aufgaben-ID { $given }, das Journal hat 1 Aufgabe
+   *[other] Ungültige Aufgaben-ID { $given }, das Journal hat { $max } Aufgaben
+}
+error-index-empty = Ungültige Aufgaben-ID { $given }, das Journal ist leer
+error-parse = { $error } (die Datei ist beschädigt oder nicht im erwarteten Format)
+error-overdue = { $count ->
+    [one] 1 Aufgabe ist überfällig
+   *[other] { $count } Aufgaben sind überfällig
+}
+error-in-command = Fehler in `{ $command }`: { $error }
+error-while = beim { $action }: { $error }
+error-while-path = beim { $action } '{ $path }': { $error }
+error-read-only = das Journal ist schreibgeschützt
+error-read-only-command = `{ $command }` würde das schreibgeschützte Journal ändern
+error-rpc-stdio = rpc liest seine Anfragen von stdin und braucht deshalb eine Journaldatei statt -
+error-relate-itself = eine Aufgabe kann nicht mit sich selbst verwandt sein
+error-empty-name = der Name einer Aufgabe darf nicht leer sein
+error-rename-several = es kann nur eine Aufgabe auf einmal umbenannt werden
+error-confirm-terminal = { $question } Die Bestätigung braucht ein Terminal, mit --yes geht es ohne Nachfrage weiter
+error-no-url = Aufgabe { $index } hat keine URL
+error-macro-nested = Das Makro '{ $name }' kann das Makro '{ $step }' nicht ausführen
+error-macro-option = Der Schritt '{ $step }' des Makros '{ $name }' kann { $option } nicht setzen, das für den ganzen Aufruf gilt
+error-macro-step = Das Makro '{ $name }' kann `{ $command }` nicht ausführen, das die Dateien neben dem Journal ändert
+error-unknown-subcommand = Unbekannter Unterbefehl '{ $name }'
+error-clipboard-empty = Die Zwischenablage ist leer
+error-daemon-unsupported = Der Daemon braucht Unix-Domain-Sockets
+error-redact-script = die Zeilen eines Skripts können nicht unkenntlich gemacht werden, ohne --script auflisten oder redact in der Konfiguration abschalten
+error-not-in-trash = keine Aufgabe mit der ID { $id } im Papierkorb
+error-goals-stdio = Ziele liegen neben der Journaldatei, die ein von stdin gelesenes Journal nicht hat
+error-missing-directory = das Verzeichnis '{ $path }' existiert nicht
+error-not-a-directory = '{ $path }' ist kein Verzeichnis
+error-no-current-user = es gibt keinen aktuellen Benutzer, setze "user" in der Konfiguration oder gib den Zuständigen an
+error-not-a-file = '{ $path }' ist kein Dateipfad
+error-invalid-position = Ungültige Position
+error-move-after-itself = Eine Aufgabe kann nicht hinter sich selbst verschoben werden
+error-invalid-index = Ungültiger Index '{ $spec }': { $error }
+error-index-zero = Indizes beginnen bei 1
+error-not-an-index = '{ $part }' ist kein Index
+error-range-backwards = der Bereich { $start }-{ $end } endet vor seinem Anfang
+error-range-too-large = der Bereich { $start }-{ $end } ist zu groß
+error-invalid-filter = Ungültiger Filter '{ $expression }': { $error }
+error-filter-term = <Feld>:<Wert> erwartet, aber '{ $word }' gefunden
+error-filter-task-id = ungültige Aufgaben-ID '{ $id }'
+error-filter-state = unbekannter Zustand '{ $state }', erwartet active, waiting oder complete
+error-filter-field = unbekanntes Feld '{ $field }', erwartet tag, name, regex, owner, assignee, delegated, goal, related, stale, created-after, created-before oder state
+error-column-mapping = Ungültige Spaltenzuordnung '{ $pair }', erwartet <Feld>=<Spalte>
+error-mapping-field = Unbekanntes Feld '{ $field }' in der Spaltenzuordnung, erwartet name, tags oder state
+error-missing-column = Keine Spalte '{ $column }' in der CSV-Kopfzeile, vorhanden sind: { $columns }
+error-config = Ungültige Konfigurationsdatei { $path }: { $error }
+error-config-setting = Ungültiges { $setting } in der Konfiguration: { $error }
+error-config-locale = Unbekannte Sprache '{ $locale }' in der Konfiguration, erwartet en oder de
+error-unterminated-quote = Nicht geschlossenes Anführungszeichen in '{ $line }'
+error-daemon-running = ein Daemon bedient dieses Journal bereits
+error-daemon-closed = der Daemon hat die Verbindung geschlossen
+error-search-terminal = Die interaktive Suche braucht ein Terminal
+error-tool-exited = { $tool } wurde mit { $status } beendet
+error-open-unsupported = Das Öffnen von URLs wird auf dieser Plattform nicht unterstützt
+error-opener-exited = Das Programm zum Öffnen wurde mit { $status } beendet
+error-notify-unsupported = Desktop-Benachrichtigungen werden auf dieser Plattform nicht unterstützt
+error-notifier-exited = Das Benachrichtigungsprogramm wurde mit { $status } beendet
+error-clipboard-not-text = Die Zwischenablage enthält keinen Text
+error-no-clipboard-tool = Kein Programm für die Zwischenablage gefunden, installiere eines von: { $tools }
+error-invalid-date = ungültiges Datum '{ $date }', erwartet z.B. tomorrow, next week, friday, 2024-07-01, 2024-07-01 14:30 oder 3d
+error-invalid-span = ungültige Zeitspanne '{ $span }', erwartet z.B. 45m, 1h30m oder 7d
+error-invalid-repetition = ungültige Wiederholung '{ $rule }', erwartet daily, weekly, monthly oder z.B. 'every 3 days'
+error-unknown-period = unbekannter Zeitraum '{ $period }', erwartet daily oder weekly
+action-opening-journal = Öffnen des Journals
+action-reading-journal = Lesen des Journals
+action-writing-journal = Schreiben des Journals
+action-locking-journal = Sperren des Journals
+action-keeping-undo-backup = Sichern des Journals für undo
+action-undoing = Rückgängigmachen
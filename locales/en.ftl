@@ -0,0 +1,246 @@
+# English messages, the reference every other locale is checked against.
+# The syntax is the subset of Fluent understood by `i18n`: `key = pattern`, indented continuation
+# lines, `{ $variable }` placeables, `{ other-message }` references and `{ $variable -> [key] ... *[other] ... }` selections.
+
+## Listing
+empty-list = Empty to-do list
+label-task = Task:
+label-created = Created at:
+label-updated = Updated at:
+label-due = Due:
+label-waiting = Waiting until:
+label-done = Done at:
+nothing-to-do = Nothing to do
+
+## Details of `show`
+task-heading = Task { $index }: { $name }
+label-id = Id:
+label-state = State:
+label-tags = Tags:
+label-priority = Priority:
+label-estimate = Estimate:
+label-delegated = Delegated:
+label-goal = Goal:
+label-related = Related:
+label-parent = Parent:
+label-repeats = Repeats:
+label-link = Link:
+label-owner = Owner:
+label-assignee = Assignee:
+state-active = active
+state-waiting-until = waiting until { $date }
+label-waiting-for = Waiting for:
+state-complete-since = complete since { $date }
+history = History
+
+## Plain reports
+report-title = { $period ->
+    [daily] Daily report
+   *[weekly] Weekly report
+}, { $since } to { $until }
+section-added = Added ({ $count })
+section-completed = Completed ({ $count })
+section-overdue = Overdue ({ $count })
+section-pending = Pending ({ $count })
+
+## Plans
+plan-title = Plan for { $days ->
+    [one] 1 day
+   *[other] { $days } days
+}, { $capacity } a day
+plan-day = { $date }  { $planned } / { $capacity }
+plan-overcommitted = overcommitted by { $over }
+plan-no-estimate = no estimate
+plan-unscheduled = Unscheduled: { $count ->
+    [one] 1 task
+   *[other] { $count } tasks
+}, { $estimate }
+
+## Goals
+no-goals = No goals
+goal-progress = { $done }/{ $total } tasks done
+
+## Trash
+trash-empty = The trash is empty
+trashed-task = { $id }: { $name } (removed { $date })
+task-restored = Restored { $name } as task { $index }
+undone = Undid `{ $command }` of { $time }
+undone-change = Undid the change of { $time }
+undo-empty = There is nothing to undo
+ticked = { $count ->
+    [0] No recurring task is due to come back
+    [one] Added the next occurrence of 1 recurring task
+   *[other] Added the next occurrences of { $count } recurring tasks
+}
+error-undo-nothing = there is nothing to undo
+error-undo-steps = { $count ->
+    [one] only 1 change can be undone
+   *[other] only { $count } changes can be undone
+}
+error-undo-stdio = a journal read from stdin has no backups to undo its changes with
+
+## Stale tasks
+stale-banner = { $count ->
+    [one] 1 active task
+   *[other] { $count } active tasks
+} untouched for { days } or more, see `list --stale { $span }`
+stats-states = { $active } active, { $waiting } waiting, { $complete } complete
+stats-stale = { $count } stale, untouched for { days } or more
+stats-oldest = Oldest open tasks
+info-journal = Journal:
+info-format = Format:
+info-schema = Schema:
+info-size = Size:
+info-tasks = Tasks:
+info-oldest = Oldest:
+info-newest = Newest:
+info-lock = Lock:
+info-stdin = standard input
+info-bytes = { $bytes } bytes
+info-missing = doesn't exist yet
+info-counts = { $total } ({ $active } active, { $waiting } waiting, { $complete } complete)
+info-daemon = held by the daemon on { $path }
+info-unlocked = none
+info-writing = held by another process writing the journal
+untouched-for = untouched for { days }
+
+## Bulk changes
+tasks-completed = { $count ->
+    [one] 1 task completed
+   *[other] { $count } tasks completed
+}
+tasks-would-complete = { $count ->
+    [one] 1 task would be completed
+   *[other] { $count } tasks would be completed
+}
+tasks-removed = { $count ->
+    [one] 1 task removed
+   *[other] { $count } tasks removed
+}
+tasks-would-remove = { $count ->
+    [one] 1 task would be removed
+   *[other] { $count } tasks would be removed
+}
+tasks-updated = { $count ->
+    [one] 1 task updated
+   *[other] { $count } tasks updated
+}
+tasks-would-update = { $count ->
+    [one] 1 task would be updated
+   *[other] { $count } tasks would be updated
+}
+tasks-imported = { $count ->
+    [one] 1 task imported
+   *[other] { $count } tasks imported
+}
+tasks-would-import = { $count ->
+    [one] 1 task would be imported
+   *[other] { $count } tasks would be imported
+}
+import-skipped = , { $count } already in the journal
+tasks-appended = { $count ->
+    [one] 1 task appended
+   *[other] { $count } tasks appended
+}
+nothing-to-complete = No task to complete
+nothing-to-remove = No task to remove
+confirm-complete = { $count ->
+    [one] Complete 1 task?
+   *[other] Complete { $count } tasks?
+}
+confirm-remove = { $count ->
+    [one] Remove 1 task?
+   *[other] Remove { $count } tasks?
+}
+confirm-answers = [y/N]
+# the answers taken for yes, separated by spaces, anything else is no
+confirm-yes = y yes
+aborted = Aborted
+
+## Notifications
+notify-overdue = Task overdue
+notify-completed = High priority task completed
+
+## Relative times
+days = { $days ->
+    [0] less than a day
+    [one] 1 day
+   *[other] { $days } days
+}
+overdue-by = overdue by { days }
+due-in = due in { days }
+age = { days } old
+days-left = { days } left
+tag-count = { $count ->
+    [one] 1 tag
+   *[other] { $count } tags
+}
+
+## Errors
+error-index-out-of-range = { $max ->
+    [one] Invalid Task ID { $given }, the journal has 1 task
+   *[other] Invalid Task ID { $given }, the journal has { $max } tasks
+}
+error-index-empty = Invalid Task ID { $given }, the journal is empty
+error-parse = { $error } (the file is damaged or not in the expected format)
+error-overdue = { $count ->
+    [one] 1 task is overdue
+   *[other] { $count } tasks are overdue
+}
+error-in-command = error in `{ $command }`: { $error }
+error-while = while { $action }: { $error }
+error-while-path = while { $action } '{ $path }': { $error }
+error-read-only = the journal is read-only
+error-read-only-command = `{ $command }` would change the journal, which is read-only
+error-rpc-stdio = rpc reads its requests from stdin, so it needs a journal file rather than -
+error-relate-itself = a task can't be related to itself
+error-empty-name = a task name can't be empty
+error-rename-several = only a single task can be renamed at a time
+error-confirm-terminal = { $question } Confirmation needs a terminal, pass --yes to proceed without asking
+error-no-url = Task { $index } has no URL
+error-macro-nested = Macro '{ $name }' cannot run macro '{ $step }'
+error-macro-option = Macro '{ $name }' step '{ $step }' can't set { $option }, which applies to the whole run
+error-macro-step = Macro '{ $name }' cannot run `{ $command }`, which changes the files next to the journal
+error-unknown-subcommand = Unrecognized subcommand '{ $name }'
+error-clipboard-empty = The clipboard is empty
+error-daemon-unsupported = The daemon needs Unix domain sockets
+error-redact-script = the lines of a script can't be redacted, list without --script or turn off redact in the config
+error-not-in-trash = no task with id { $id } in the trash
+error-goals-stdio = goals are kept next to the journal file, which a journal read from stdin does not have
+error-missing-directory = the directory '{ $path }' doesn't exist
+error-not-a-directory = '{ $path }' is not a directory
+error-not-a-file = '{ $path }' is not a file path
+error-no-current-user = there is no current user, set "user" in the config or give the assignee
+error-invalid-position = Invalid position
+error-move-after-itself = Cannot move a task after itself
+error-invalid-index = Invalid index '{ $spec }': { $error }
+error-index-zero = indices start at 1
+error-not-an-index = '{ $part }' is not an index
+error-range-backwards = the range { $start }-{ $end } ends before it starts
+error-range-too-large = the range { $start }-{ $end } is too large
+error-invalid-filter = Invalid filter '{ $expression }': { $error }
+error-filter-term = expected <field>:<value> but found '{ $word }'
+error-filter-task-id = invalid task id '{ $id }'
+error-filter-state = unknown state '{ $state }', expected active, waiting or complete
+error-filter-field = unknown field '{ $field }', expected tag, name, regex, owner, assignee, delegated, goal, related, stale, created-after, created-before or state
+error-column-mapping = Invalid column mapping '{ $pair }', expected <field>=<column>
+error-mapping-field = Unknown field '{ $field }' in column mapping, expected name, tags or state
+error-missing-column = No column '{ $column }' in the CSV header, available columns are: { $columns }
+error-config = Invalid config file { $path }: { $error }
+error-config-setting = Invalid { $setting } in the config: { $error }
+error-config-locale = Unknown locale '{ $locale }' in the config, expected en or de
+error-unterminated-quote = Unterminated quote in '{ $line }'
+error-daemon-running = a daemon is already serving this journal
+error-daemon-closed = the daemon closed the connection
+error-search-terminal = The interactive search needs a terminal
+error-tool-exited = { $tool } exited with { $status }
+error-open-unsupported = Opening URLs is not supported on this platform
+error-opener-exited = The opener exited with { $status }
+error-notify-unsupported = Desktop notifications are not supported on this platform
+error-notifier-exited = The notifier exited with { $status }
+error-clipboard-not-text = The clipboard doesn't hold text
+error-no-clipboard-tool = No clipboard tool found, install one of: { $tools }
+error-invalid-date = invalid date '{ $date }', expected e.g. tomorrow, next week, friday, 2024-07-01, 2024-07-01 14:30 or 3d
+error-invalid-span = invalid span of time '{ $span }', expected e.g. 45m, 1h30m or 7d
+error-invalid-repetition = invalid repetition '{ $rule }', expected daily, weekly, monthly or e.g. 'every 3 days'
+error-unknown-period = unknown period '{ $period }', expected daily or weekly
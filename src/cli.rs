@@ -1,13 +1,51 @@
 use std::path::PathBuf;
-use clap::{Parser, Subcommand};
-
+use chrono::{DateTime, Duration, Utc};
+use clap::{ArgGroup, Parser, Subcommand};
+use crate::{color, completions, date, export, filter, import, index_spec, output, recur, span, storage, summary, task};
 
 #[derive(Parser)]
-#[command(version = "0.1.24", author = "Reggie Mantle", about = "Does really amazing things for great people")]
+#[command(name = "My Program", version = "1.0.2", author = "Me, me@mail.com", about = "Explains in brief what the program does")]
+#[command(after_help = "Longer explanation to appear after the options when \
+                        displaying the help information from --help or -h")]
 pub struct Cli {
-    #[arg(short, long, value_name = "FILE")]
+    /// The journal, kept in TOML when named .toml, gzip or zstd compressed when named .json.gz or .json.zst, or - to read it from stdin and write the changed journal to stdout [default: $RUSTY_JOURNAL_FILE, else journal in the config, else todo.json]
+    // Comment: left at None when not given rather than defaulted, so that a macro step can tell a journal of its own
+    // from the one of the macro invocation
+    #[arg(long = "journal_file", value_name = "FILE")]
     pub journal_file: Option<PathBuf>,
-    
+
+    /// json or toml, the format of the journal whatever its extension
+    #[arg(long, value_parser = storage::Backend::parse)]
+    pub journal_format: Option<storage::Backend>,
+
+    /// Don't show desktop notifications, even if enabled in the config
+    #[arg(long)]
+    pub no_notify: bool,
+
+    /// Report the timings of the journal operations on stderr
+    #[arg(long)]
+    pub trace: bool,
+
+    /// Append the timings of the journal operations as JSON lines to the file
+    #[arg(long, value_name = "FILE")]
+    pub log_file: Option<PathBuf>,
+
+    /// How list and search print the tasks: human, json for an array of the tasks with their index, or porcelain for one line of tab separated fields per task, which stay the same across versions
+    #[arg(long, value_parser = output::OutputFormat::parse, default_value = "human")]
+    pub output: output::OutputFormat,
+
+    /// How failures are reported on stderr, json gives an object with code, message, command, path and index
+    #[arg(long, value_parser = ["text", "json"], default_value = "text")]
+    pub error_format: String,
+
+    /// Refuse any subcommand changing the journal, and never create the journal file
+    #[arg(long)]
+    pub read_only: bool,
+
+    /// Output that is the same on every machine, for snapshot tests: UTC times and no color
+    #[arg(long)]
+    pub deterministic: bool,
+
     #[command(subcommand)]
     pub action: Action,
 }
@@ -16,12 +54,450 @@ pub struct Cli {
 pub enum Action {
     /// Write tasks to the journal file.
     Add {
-        task: String,
+        #[cfg_attr(feature = "clipboard", arg(long, required_unless_present_any = ["from_file", "clipboard"]))]
+        #[cfg_attr(not(feature = "clipboard"), arg(long, required_unless_present = "from_file"))]
+        task: Option<String>,
+        #[arg(long)]
+        tag: Option<Vec<String>>,
+        /// high, medium or low
+        #[arg(long, value_parser = task::Priority::parse)]
+        priority: Option<task::Priority>,
+        /// When the task is due, e.g. today, tomorrow, next week, friday or 2024-07-01
+        #[arg(long, value_parser = date::parse)]
+        due: Option<DateTime<Utc>>,
+        /// Assign the task to a user of a shared journal
+        #[arg(long, value_name = "USER")]
+        assign: Option<String>,
+        /// The goal the task contributes to, c.f. the goal subcommand
+        #[arg(long, value_name = "NAME")]
+        goal: Option<String>,
+        /// How long doing the task should take, e.g. 45m or 1h30m
+        #[arg(long, value_parser = span::parse)]
+        estimate: Option<Duration>,
+        /// Bring the task back once completed: daily, weekly, monthly or e.g. 'every 3 days'
+        #[arg(long, value_name = "RULE", value_parser = recur::Recurrence::parse)]
+        repeat: Option<recur::Recurrence>,
+        /// Add the task as a subtask of the task at the index
+        #[arg(long, value_name = "INDEX")]
+        parent: Option<usize>,
+        /// Add one task per line of the file, normalized according to the config
+        #[arg(long, value_name = "FILE", conflicts_with = "task")]
+        from_file: Option<PathBuf>,
+        // reading the system clipboard is only available with the clipboard feature
+        /// Add a task named after the text of the system clipboard, e.g. a copied URL
+        #[cfg(feature = "clipboard")]
+        #[arg(long, conflicts_with_all = ["task", "from_file"])]
+        clipboard: bool,
     },
-    /// Remove an entry from the journal file by position.
+    /// Move tasks to the trash, by index or every task matching a filter
+    #[command(group(ArgGroup::new("selection").args(["index", "where", "tag"]).required(true)))]
+    #[command(group(ArgGroup::new("bulk").args(["where", "tag"])))]
     Remove {
-        index: usize,
+        /// Tasks to remove, e.g. 3 or 1-5,8
+        #[arg(value_parser = index_spec::IndexSpec::parse)]
+        index: Option<index_spec::IndexSpec>,
+        /// Remove every task matching the filter, e.g. 'tag:scratch state:complete'
+        #[arg(long, value_name = "FILTER", value_parser = filter::TaskFilter::parse)]
+        r#where: Option<filter::TaskFilter>,
+        /// Remove every task with the tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only show the tasks that would be removed
+        #[arg(long, requires = "bulk")]
+        dry_run: bool,
+        /// Don't ask for confirmation
+        #[arg(short, long, requires = "bulk")]
+        yes: bool,
     },
     /// List all tasks in the journal file.
+    List {
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only the tasks assigned to the user, the current user when no user is given
+        #[arg(long, value_name = "USER", num_args = 0..=1, default_missing_value = "")]
+        assignee: Option<String>,
+        /// Only the tasks delegated to the person, or to anyone when no person is given
+        #[arg(long, value_name = "PERSON", num_args = 0..=1, default_missing_value = "")]
+        delegated: Option<String>,
+        /// Only the active or the complete tasks
+        #[arg(long, value_parser = ["active", "complete", "all"], default_value = "all")]
+        state: String,
+        /// Only the tasks related to the task with the id, c.f. relate
+        #[arg(long, value_name = "ID")]
+        related_to: Option<u64>,
+        /// Only the active tasks untouched for at least that long, e.g. 30d
+        #[arg(long, value_name = "SPAN", value_parser = span::parse)]
+        stale: Option<Duration>,
+        /// Only the tasks matching the filter, e.g. 'tag:work created-after:2024-07-01'
+        #[arg(long, value_name = "FILTER", value_parser = filter::TaskFilter::parse)]
+        r#where: Option<filter::TaskFilter>,
+        /// index, urgency, due or priority
+        #[arg(long, value_parser = task::SortBy::parse)]
+        sort: Option<task::SortBy>,
+        /// Print the urgency score in front of every task
+        #[arg(long)]
+        show_urgency: bool,
+        /// Print the subtasks indented under their parent task
+        #[arg(long)]
+        tree: bool,
+        /// List the waiting tasks, which are left out otherwise
+        #[arg(long)]
+        waiting: bool,
+        /// Keep listing, refreshing whenever the journal file changes
+        #[arg(long)]
+        watch: bool,
+        /// Mask the task names, showing a placeholder with the tags and dates instead
+        #[arg(long)]
+        redact: bool,
+        // the script based filtering / reporting is only available with the scripting feature
+        /// Filter or report on the tasks through a script file
+        #[cfg(feature = "scripting")]
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["sort", "show_urgency", "redact"])]
+        script: Option<PathBuf>,
+    },
+    /// Mark tasks as complete, by index or every active task with a tag
+    #[command(group(ArgGroup::new("selection").args(["index", "tag"]).required(true)))]
+    Done {
+        /// Tasks to complete, e.g. 3 or 1-5,8
+        #[arg(value_parser = index_spec::IndexSpec::parse)]
+        index: Option<index_spec::IndexSpec>,
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only show the tasks that would be completed
+        #[arg(long, requires = "tag")]
+        dry_run: bool,
+        /// Don't ask for confirmation
+        #[arg(short, long, requires = "tag")]
+        yes: bool,
+    },
+    /// Move a task to another position in the journal
+    MoveTo {
+        index: usize,
+        #[arg(required_unless_present = "after")]
+        position: Option<usize>,
+        /// Move the task right after the task at this index
+        #[arg(long, value_name = "INDEX", conflicts_with = "position")]
+        after: Option<usize>,
+    },
+    /// Move tasks to the top of the list
+    Bump {
+        /// Tasks to move, e.g. 3 or 1-5,8
+        #[arg(value_parser = index_spec::IndexSpec::parse)]
+        index: index_spec::IndexSpec,
+    },
+    /// Move tasks to the bottom of the list
+    Sink {
+        /// Tasks to move, e.g. 3 or 1-5,8
+        #[arg(value_parser = index_spec::IndexSpec::parse)]
+        index: index_spec::IndexSpec,
+    },
+    /// Rewrite the journal in a canonical order (active first, then oldest first) and fill in missing ids
+    Renumber,
+    /// Add a timestamped note to the history of tasks
+    Annotate {
+        /// Tasks to annotate, e.g. 3 or 1-5,8
+        #[arg(value_parser = index_spec::IndexSpec::parse)]
+        index: index_spec::IndexSpec,
+        note: String,
+    },
+    /// Print all the details of tasks and their history
+    Show {
+        /// Tasks to show, e.g. 3 or 1-5,8
+        #[arg(value_parser = index_spec::IndexSpec::parse)]
+        index: index_spec::IndexSpec,
+    },
+    /// Print the single active task to do now, scored by priority, due date and age
+    Next {
+        /// Show how the score of the task adds up
+        #[arg(long)]
+        explain: bool,
+    },
+    /// Put tasks on hold until a date, they become active again by themselves then
+    Wait {
+        /// Tasks to put on hold, e.g. 3 or 1-5,8
+        #[arg(value_parser = index_spec::IndexSpec::parse)]
+        index: index_spec::IndexSpec,
+        /// e.g. tomorrow, friday, 2024-07-01, 2024-07-01 14:30 or 3d
+        #[arg(long, value_name = "DATE", value_parser = date::parse)]
+        until: DateTime<Utc>,
+        /// What the task waits for, e.g. "Bob's reply"
+        #[arg(long = "for", value_name = "REASON")]
+        reason: Option<String>,
+    },
+    /// Record that tasks were handed to someone else, to follow up on them
+    Delegate {
+        /// Tasks to delegate, e.g. 3 or 1-5,8
+        #[arg(value_parser = index_spec::IndexSpec::parse)]
+        index: index_spec::IndexSpec,
+        person: String,
+    },
+    /// Link two tasks as related, so each can be found from the other with list --related-to
+    Relate {
+        index: usize,
+        #[arg(long, value_name = "INDEX")]
+        to: usize,
+    },
+    /// Create a goal or set its target date, and make tasks contribute to it
+    Goal {
+        name: String,
+        /// The target date, e.g. 2024-07-01 or 2w
+        #[arg(long, value_name = "DATE", value_parser = date::parse)]
+        by: Option<DateTime<Utc>>,
+        /// Tasks contributing to the goal, e.g. 3 or 1-5,8, can be given several times
+        #[arg(long, value_name = "INDEX", value_parser = index_spec::IndexSpec::parse)]
+        task: Vec<index_spec::IndexSpec>,
+    },
+    /// Show every goal with its progress and the days left
+    Goals,
+    /// Find tasks by typing a few letters of their name in order, e.g. qrep for the quarterly report
+    #[command(group(ArgGroup::new("search_filters")
+                        .args(["exact", "regex", "tag", "created_after", "created_before"])
+                        .multiple(true)
+                        .conflicts_with("interactive")))]
+    Search {
+        /// Matched against the task names, all tasks when left out
+        query: Option<String>,
+        /// Match the query as a substring of the name, ignoring case
+        #[arg(long, requires = "query")]
+        exact: bool,
+        /// Match the query as a regular expression, (?i) at its start ignores case
+        #[arg(long, requires = "query", conflicts_with = "exact")]
+        regex: bool,
+        /// Only the tasks with the tag, can be given several times
+        #[arg(long)]
+        tag: Vec<String>,
+        /// Match the tasks with any of the tags given with --tag, rather than all of them
+        #[arg(long, requires = "tag")]
+        any_tag: bool,
+        /// Only the tasks created on or after the date, e.g. 2024-07-01
+        #[arg(long, value_name = "DATE", value_parser = date::parse)]
+        created_after: Option<DateTime<Utc>>,
+        /// Only the tasks created before the date
+        #[arg(long, value_name = "DATE", value_parser = date::parse)]
+        created_before: Option<DateTime<Utc>>,
+        /// Refilter the tasks on every key, then print the task picked with Enter
+        #[arg(short, long)]
+        interactive: bool,
+        /// Complete the task picked, rather than print it
+        #[arg(long, requires = "interactive")]
+        done: bool,
+    },
+    /// Look into the trash, which keeps the removed tasks until trash_retention in the config is up
+    Trash {
+        #[command(subcommand)]
+        action: TrashAction,
+    },
+    /// Move a removed task from the trash back to the end of the journal
+    Restore {
+        /// The id of the task, as shown by trash list
+        id: u64,
+    },
+    /// Put back the journal as it was before the last changes, up to undo_depth in the config
+    Undo {
+        /// How many changes to undo
+        #[arg(default_value = "1")]
+        count: usize,
+        /// Show the changes that can be undone, most recent first, rather than undo them
+        #[arg(long, conflicts_with = "count")]
+        list: bool,
+    },
+    /// Add the next occurrence of the completed recurring tasks that have none yet
+    #[command(visible_alias = "refresh")]
+    Tick,
+    /// Count the tasks by state and show the open tasks left untouched the longest
+    Stats,
+    /// Show where the journal is, how it is stored and what it holds, e.g. for a bug report
+    Info,
+    /// Set how long doing tasks should take, e.g. 1h30m
+    Estimate {
+        /// Tasks to estimate, e.g. 3 or 1-5,8
+        #[arg(value_parser = index_spec::IndexSpec::parse)]
+        index: index_spec::IndexSpec,
+        #[arg(value_parser = span::parse)]
+        estimate: Duration,
+    },
+    /// Sum up the estimates of the tasks due every day against the daily capacity
+    Plan {
+        /// How far to plan ahead, e.g. 7d or 2w
+        #[arg(long, value_parser = span::parse, default_value = "7d")]
+        horizon: Duration,
+        /// The time available every day, daily_capacity in the config by default
+        #[arg(long, value_parser = span::parse)]
+        capacity: Option<Duration>,
+    },
+    /// Open the first URL of tasks, or their link, with the system opener
+    Open {
+        /// Tasks to open the URL of, e.g. 3 or 1-5,8
+        #[arg(value_parser = index_spec::IndexSpec::parse)]
+        index: index_spec::IndexSpec,
+    },
+    /// Exchange the positions of two tasks
+    Swap {
+        index: usize,
+        other: usize,
+    },
+    /// Rename a task, change its tags or due date, or add and remove tags on every task matching a filter
+    #[command(group(ArgGroup::new("selection").args(["index", "where"]).required(true)))]
+    #[command(group(ArgGroup::new("changes").args(["name", "add_tag", "remove_tag", "due", "no_due"]).multiple(true).required(true)))]
+    Edit {
+        /// Tasks to edit, e.g. 3 or 1-5,8
+        #[arg(value_parser = index_spec::IndexSpec::parse)]
+        index: Option<index_spec::IndexSpec>,
+        /// Tasks to edit, e.g. 'tag:old-project -state:complete'
+        #[arg(long, value_name = "FILTER", value_parser = filter::TaskFilter::parse)]
+        r#where: Option<filter::TaskFilter>,
+        /// The new name of the task, for a single task only
+        #[arg(long, requires = "index")]
+        name: Option<String>,
+        #[arg(long)]
+        add_tag: Vec<String>,
+        #[arg(long)]
+        remove_tag: Vec<String>,
+        /// The new due date, e.g. friday or 2024-07-01
+        #[arg(long, requires = "index", value_parser = date::parse)]
+        due: Option<DateTime<Utc>>,
+        /// Clear the due date
+        #[arg(long, requires = "index", conflicts_with = "due")]
+        no_due: bool,
+        /// Only show the tasks that would be edited
+        #[arg(long, requires = "where")]
+        dry_run: bool,
+    },
+    /// Append all the tasks of another journal file to this journal
+    Append {
+        #[arg(value_name = "OTHER_JOURNAL")]
+        other: PathBuf,
+    },
+    /// Import tasks from another format into the journal
+    Import {
+        /// csv, ics, apple-reminders (the JSON of reminders-cli) or ms-todo (the JSON of the Graph API, lists with their tasks)
+        #[arg(long, value_name = "FORMAT", value_parser = ["csv", "ics", "apple-reminders", "ms-todo"])]
+        from: String,
+        file: PathBuf,
+        /// Columns holding the task fields, e.g. 'name=Title,tags=Labels,state=Status'
+        #[arg(long, value_name = "MAPPING", value_parser = import::ColumnMap::parse)]
+        map: Option<import::ColumnMap>,
+        /// Only show the tasks that would be imported
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Render all the tasks through a template, e.g. to generate an HTML dashboard, or summarize them as plain text
+    #[command(group(ArgGroup::new("format").args(["template", "plain"]).required(true)))]
+    Report {
+        #[arg(long, value_name = "FILE")]
+        template: Option<PathBuf>,
+        /// Plain text summary of the added, completed, overdue and pending tasks, e.g. to mail from cron, exiting with 5 when any task is overdue
+        #[arg(long)]
+        plain: bool,
+        /// daily or weekly, the span of time the plain summary looks back on
+        #[arg(long, requires = "plain", value_parser = summary::Period::parse, default_value = "daily")]
+        period: summary::Period,
+    },
+    /// Export the tasks to a file, e.g. a filterable single page HTML view grouped by tag or state, or a journal of some of them
+    #[command(group(ArgGroup::new("destination").args(["out", "out_file"]).required(true)))]
+    Export {
+        /// html, json for a standalone journal, csv, or markdown for a table
+        #[arg(long, value_parser = export::Format::parse, default_value = "html")]
+        format: export::Format,
+        /// Only export the tasks matching the filter, e.g. 'tag:clientA'
+        #[arg(long, value_name = "FILTER", value_parser = filter::TaskFilter::parse)]
+        r#where: Option<filter::TaskFilter>,
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+        /// The file to export to, the same as --out
+        #[arg(value_name = "FILE")]
+        out_file: Option<PathBuf>,
+        /// tag or state, how the tasks of the HTML view are split into sections
+        #[arg(long, value_parser = export::GroupBy::parse, default_value = "tag")]
+        group_by: export::GroupBy,
+    },
+    /// Print the number of active tasks, fast enough for a shell prompt
+    Count {
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Print the pending and overdue counts compactly for a shell prompt, e.g. '⚑3 ⚠1' for a starship custom module
+    PromptSegment {
+        #[arg(long)]
+        tag: Option<String>,
+        /// never, ansi (e.g. for starship), or escape codes marked for zsh or bash prompts
+        #[arg(long, value_parser = color::ColorMode::parse, default_value = "never")]
+        color: color::ColorMode,
+    },
+    /// Print the JSON Schema of the journal file, for validating hand-edited journals
+    Schema,
+    /// Serve JSON-RPC requests from stdin, one per line, for editor integrations
+    Rpc,
+    /// Keep the journal loaded and serve the rpc requests on a Unix socket next to it, which add, done, remove and count then go through
+    Daemon,
+    /// Print the completion script for the shell, e.g. `source <(rusty-journal-clap completions bash)`
+    Completions {
+        /// bash, zsh or fish
+        #[arg(value_parser = completions::Shell::parse)]
+        shell: completions::Shell,
+    },
+    // any other subcommand is looked up in the macros of the user config
+    #[command(external_subcommand)]
+    Macro(Vec<String>),
+}
+
+#[derive(Subcommand)]
+pub enum TrashAction {
+    /// Show the removed tasks with their id, to restore them
     List,
-}
\ No newline at end of file
+}
+
+impl Action {
+    /// The name of the subcommand, as typed on the command line
+    pub fn name(&self) -> &str {
+        match self {
+            Action::Add { .. } => "add",
+            Action::Remove { .. } => "remove",
+            Action::List { .. } => "list",
+            Action::Done { .. } => "done",
+            Action::MoveTo { .. } => "move-to",
+            Action::Bump { .. } => "bump",
+            Action::Sink { .. } => "sink",
+            Action::Renumber => "renumber",
+            Action::Annotate { .. } => "annotate",
+            Action::Show { .. } => "show",
+            Action::Next { .. } => "next",
+            Action::Wait { .. } => "wait",
+            Action::Delegate { .. } => "delegate",
+            Action::Relate { .. } => "relate",
+            Action::Goal { .. } => "goal",
+            Action::Goals => "goals",
+            Action::Search { .. } => "search",
+            Action::Trash { .. } => "trash",
+            Action::Restore { .. } => "restore",
+            Action::Undo { .. } => "undo",
+            Action::Tick => "tick",
+            Action::Stats => "stats",
+            Action::Info => "info",
+            Action::Estimate { .. } => "estimate",
+            Action::Plan { .. } => "plan",
+            Action::Open { .. } => "open",
+            Action::Swap { .. } => "swap",
+            Action::Edit { .. } => "edit",
+            Action::Append { .. } => "append",
+            Action::Import { .. } => "import",
+            Action::Report { .. } => "report",
+            Action::Export { .. } => "export",
+            Action::Count { .. } => "count",
+            Action::PromptSegment { .. } => "prompt-segment",
+            Action::Schema => "schema",
+            Action::Rpc => "rpc",
+            Action::Daemon => "daemon",
+            Action::Completions { .. } => "completions",
+            Action::Macro(args) => args.first().map_or("", String::as_str),
+        }
+    }
+
+    /// Whether the subcommand was given `--dry-run`, which leaves the journal untouched
+    pub fn is_dry_run(&self) -> bool {
+        match self {
+            Action::Remove { dry_run, .. } | Action::Done { dry_run, .. } | Action::Edit { dry_run, .. } | Action::Import { dry_run, .. } => *dry_run,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::env;
+use std::io::{Error, ErrorKind, Result as ioResult};
+use std::process::{Command, Stdio};
+use crate::i18n::tr;
+
+// Comment: like the notifications, the clipboard is read through the tools that ship with the platform
+// rather than a clipboard crate: pbpaste on macOS, PowerShell on Windows, and wl-paste, xclip or xsel
+// on Linux and the BSDs, whichever is installed for the running display server
+
+/// The commands that can print the clipboard, in the order they are tried
+fn readers() -> Vec<Vec<&'static str>> {
+    if cfg!(target_os = "macos") {
+        vec![vec!["pbpaste"]]
+    } else if cfg!(windows) {
+        vec![vec!["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"]]
+    } else {
+        let mut readers = vec![vec!["xclip", "-selection", "clipboard", "-out"],
+                               vec!["xsel", "--clipboard", "--output"]];
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            readers.insert(0, vec!["wl-paste", "--no-newline"]);
+        }
+        readers
+    }
+}
+
+/// Reads the text of the system clipboard
+pub fn read() -> ioResult<String> {
+    for reader in readers() {
+        let output = match Command::new(reader[0]).args(&reader[1..])
+                                                  .stdin(Stdio::null())
+                                                  .stderr(Stdio::null())
+                                                  .output() {
+            Ok(output) => output,
+            // the tool isn't installed, try the next one
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        if !output.status.success() {
+            return Err(Error::other(tr("error-tool-exited", &[("tool", &reader[0]), ("status", &output.status)])));
+        }
+        return String::from_utf8(output.stdout).map_err(|_| Error::new(ErrorKind::InvalidData, tr("error-clipboard-not-text", &[])));
+    }
+    Err(Error::new(ErrorKind::Unsupported,
+                   tr("error-no-clipboard-tool", &[("tools", &readers().iter().map(|reader| reader[0]).collect::<Vec<_>>().join(", "))])))
+}
+
+/// Turns copied text into a task name, joining the lines of e.g. a sentence wrapped over several lines
+/// # Examples
+/// ```
+/// use rusty_journal_clap::clipboard;
+/// assert_eq!(clipboard::task_name("  Reply to the\r\n  invoice mail \n"), "Reply to the invoice mail");
+/// assert_eq!(clipboard::task_name("https://example.com/issue/42\n"), "https://example.com/issue/42");
+/// ```
+pub fn task_name(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
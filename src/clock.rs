@@ -0,0 +1,86 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use chrono::{DateTime, Duration, Utc};
+
+/// The source of the current time for new and completed tasks, reports and scripts
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The default clock, reading the system time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock standing still at a given time until it is moved explicitly. Clones share the same time,
+/// so a test can keep a clone to move the clock after installing it
+#[derive(Clone)]
+pub struct FixedClock {
+    now: Rc<Cell<DateTime<Utc>>>,
+}
+
+impl FixedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        FixedClock { now: Rc::new(Cell::new(now)) }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        self.now.set(now);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.now.get()
+    }
+}
+
+// Comment: the clock is per thread so that tests, which run on a thread each, can install their own
+// clock without affecting each other
+thread_local! {
+    static CLOCK: RefCell<Rc<dyn Clock>> = RefCell::new(Rc::new(SystemClock));
+}
+
+/// The current time according to the installed clock, the system clock unless another one was installed
+pub fn now() -> DateTime<Utc> {
+    CLOCK.with(|clock| clock.borrow().now())
+}
+
+/// Installs the clock for the current thread until the returned guard is dropped
+/// # Examples
+/// ```
+/// use rusty_journal_clap::clock::{self, FixedClock};
+/// use chrono::{DateTime, Duration};
+/// let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+/// let fixed = FixedClock::new(start);
+/// let guard = clock::install(fixed.clone());
+/// fixed.advance(Duration::days(1));
+/// assert_eq!(clock::now(), start + Duration::days(1));
+/// drop(guard);
+/// assert!(clock::now() > start + Duration::days(1));
+/// ```
+pub fn install(clock: impl Clock + 'static) -> ClockGuard {
+    let previous = CLOCK.with(|current| current.replace(Rc::new(clock)));
+    ClockGuard { previous: Some(previous) }
+}
+
+/// Restores the previously installed clock when dropped
+pub struct ClockGuard {
+    previous: Option<Rc<dyn Clock>>,
+}
+
+impl Drop for ClockGuard {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            CLOCK.with(|current| current.replace(previous));
+        }
+    }
+}
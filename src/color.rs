@@ -0,0 +1,80 @@
+use crate::output;
+
+/// How color escape codes are written, shells need the non printing codes marked
+/// to get the prompt width right
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    Never,
+    // plain ANSI codes, e.g. for starship custom modules and other tools measuring the width themselves
+    Ansi,
+    // wrapped in %{ %}
+    Zsh,
+    // wrapped in \[ \]
+    Bash,
+}
+
+impl ColorMode {
+    pub fn parse(mode: &str) -> Result<ColorMode, String> {
+        match mode {
+            "never" => Ok(ColorMode::Never),
+            "ansi" => Ok(ColorMode::Ansi),
+            "zsh" => Ok(ColorMode::Zsh),
+            "bash" => Ok(ColorMode::Bash),
+            _ => Err(format!("unknown color mode '{}', expected never, ansi, zsh or bash", mode)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+}
+
+impl Color {
+    fn code(self) -> u8 {
+        match self {
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+        }
+    }
+}
+
+/// Colors the text, or leaves it as it is with `ColorMode::Never` and in deterministic mode
+/// # Examples
+/// ```
+/// use rusty_journal_clap::color::{paint, Color, ColorMode};
+/// assert_eq!(paint("3", Color::Red, ColorMode::Ansi), "\x1b[31m3\x1b[0m");
+/// assert_eq!(paint("3", Color::Red, ColorMode::Zsh), "%{\x1b[31m%}3%{\x1b[0m%}");
+/// assert_eq!(paint("3", Color::Red, ColorMode::Never), "3");
+/// ```
+pub fn paint(text: &str, color: Color, mode: ColorMode) -> String {
+    styled(text, color.code(), 0, mode)
+}
+
+/// Underlines the text, or leaves it as it is with `ColorMode::Never` and in deterministic mode
+/// # Examples
+/// ```
+/// use rusty_journal_clap::color::{underline, ColorMode};
+/// assert_eq!(underline("link", ColorMode::Ansi), "\x1b[4mlink\x1b[24m");
+/// ```
+pub fn underline(text: &str, mode: ColorMode) -> String {
+    styled(text, 4, 24, mode)
+}
+
+fn styled(text: &str, on: u8, off: u8, mode: ColorMode) -> String {
+    if output::is_deterministic() {
+        return text.to_string();
+    }
+    let (open, close) = match mode {
+        ColorMode::Never => return text.to_string(),
+        ColorMode::Ansi => ("", ""),
+        ColorMode::Zsh => ("%{", "%}"),
+        ColorMode::Bash => ("\\[", "\\]"),
+    };
+    format!("{open}\x1b[{on}m{close}{text}{open}\x1b[{off}m{close}")
+}
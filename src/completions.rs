@@ -0,0 +1,245 @@
+use std::io::{Result as ioResult, Write};
+use clap::{Arg, Command, ValueHint};
+
+// Comment: the scripts are generated from the clap definition of the command line, `cli::Cli`, so every subcommand
+// and option shows up in them as soon as it is added. They complete the subcommands, their options, the values of
+// options with a fixed set of values, and file names for the options taking a path
+
+/// The shells completion scripts can be generated for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub fn parse(shell: &str) -> Result<Shell, String> {
+        match shell {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!("unknown shell '{}', expected bash, zsh or fish", shell)),
+        }
+    }
+}
+
+/// Writes the completion script of the command for the shell, completing the program named `bin_name`
+/// # Examples
+/// ```
+/// use rusty_journal_clap::completions::{self, Shell};
+/// use clap::{Arg, Command};
+/// let command = Command::new("todo").subcommand(Command::new("add").arg(Arg::new("task").long("task")));
+/// let mut script = Vec::new();
+/// completions::generate(Shell::Fish, command, "todo", &mut script).unwrap();
+/// let script = String::from_utf8(script).unwrap();
+/// assert!(script.contains("complete -c todo -n '__fish_use_subcommand' -f -a 'add'"));
+/// assert!(script.contains("complete -c todo -n '__fish_seen_subcommand_from add' -l task -x"));
+/// ```
+pub fn generate(shell: Shell, mut command: Command, bin_name: &str, out: impl Write) -> ioResult<()> {
+    // building adds the --help and --version options and the help subcommand
+    command.build();
+    match shell {
+        Shell::Bash => bash(&command, bin_name, out),
+        Shell::Zsh => zsh(&command, bin_name, out),
+        Shell::Fish => fish(&command, bin_name, out),
+    }
+}
+
+fn options(command: &Command) -> impl Iterator<Item = &Arg> {
+    command.get_arguments().filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+}
+
+fn subcommands(command: &Command) -> impl Iterator<Item = &Command> {
+    // the subcommands of `help` only repeat those of the command
+    let nested = command.get_name() != "help";
+    command.get_subcommands().filter(move |subcommand| nested && !subcommand.is_hide_set())
+}
+
+/// The names the option can be given as, e.g. `-y` and `--yes`
+fn flags(arg: &Arg) -> Vec<String> {
+    arg.get_short().map(|short| format!("-{}", short)).into_iter()
+       .chain(arg.get_long().map(|long| format!("--{}", long)))
+       .collect()
+}
+
+fn values(arg: &Arg) -> Vec<String> {
+    arg.get_possible_values().iter().map(|value| value.get_name().to_string()).collect()
+}
+
+fn takes_path(arg: &Arg) -> bool {
+    matches!(arg.get_value_hint(), ValueHint::AnyPath | ValueHint::FilePath | ValueHint::DirPath)
+}
+
+fn help(help: Option<&clap::builder::StyledStr>) -> String {
+    help.map(|help| help.to_string().lines().next().unwrap_or_default().to_string()).unwrap_or_default()
+}
+
+/// Quotes the text for a shell, in single quotes
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+fn function_name(bin_name: &str) -> String {
+    format!("_{}", bin_name.replace(|c: char| !c.is_ascii_alphanumeric(), "_"))
+}
+
+fn bash(command: &Command, bin_name: &str, mut out: impl Write) -> ioResult<()> {
+    let function = function_name(bin_name);
+    writeln!(out, "{}() {{", function)?;
+    writeln!(out, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\" prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\" path=\"\" i")?;
+    // Comment: the path is the subcommands typed so far, e.g. trash/list, options and their values are skipped
+    writeln!(out, "    for ((i = 1; i < COMP_CWORD; i++)); do")?;
+    writeln!(out, "        case \"$path/${{COMP_WORDS[i]}}\" in")?;
+    let mut paths = Vec::new();
+    collect_paths(command, String::new(), &mut paths);
+    for (path, _) in paths.iter().filter(|(path, _)| !path.is_empty()) {
+        writeln!(out, "            {}) path=\"{}\" ;;", quote(path), path)?;
+    }
+    writeln!(out, "        esac")?;
+    writeln!(out, "    done")?;
+
+    writeln!(out, "    case \"$path\" in")?;
+    for (path, subcommand) in &paths {
+        writeln!(out, "        {})", quote(path))?;
+        let valued = options(subcommand).filter(|arg| arg.get_action().takes_values()).collect::<Vec<_>>();
+        if !valued.is_empty() {
+            writeln!(out, "            case \"$prev\" in")?;
+            for arg in valued {
+                let completion = match values(arg) {
+                    values if !values.is_empty() => format!("COMPREPLY=($(compgen -W {} -- \"$cur\"))", quote(&values.join(" "))),
+                    _ if takes_path(arg) => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string(),
+                    _ => "COMPREPLY=()".to_string(),
+                };
+                writeln!(out, "                {}) {}; return ;;", flags(arg).join("|"), completion)?;
+            }
+            writeln!(out, "            esac")?;
+        }
+        let words = options(subcommand).flat_map(flags)
+                                       .chain(subcommands(subcommand).map(|subcommand| subcommand.get_name().to_string()))
+                                       .collect::<Vec<_>>();
+        writeln!(out, "            COMPREPLY=($(compgen -W {} -- \"$cur\")) ;;", quote(&words.join(" ")))?;
+    }
+    writeln!(out, "    esac")?;
+    writeln!(out, "}}")?;
+    writeln!(out, "complete -o default -F {} {}", function, bin_name)
+}
+
+/// The subcommands at every depth with the path leading to them, e.g. `trash/list`, the command itself at the empty path
+fn collect_paths<'a>(command: &'a Command, path: String, paths: &mut Vec<(String, &'a Command)>) {
+    for subcommand in subcommands(command) {
+        let names = std::iter::once(subcommand.get_name()).chain(subcommand.get_visible_aliases());
+        for name in names {
+            collect_paths(subcommand, format!("{}/{}", path, name), paths);
+        }
+    }
+    paths.push((path, command));
+}
+
+/// Escapes the text for the brackets of a zsh `_arguments` spec
+fn zsh_escape(text: &str) -> String {
+    text.replace('\\', r"\\").replace('[', r"\[").replace(']', r"\]").replace(':', r"\:")
+}
+
+fn zsh(command: &Command, bin_name: &str, mut out: impl Write) -> ioResult<()> {
+    writeln!(out, "#compdef {}", bin_name)?;
+    writeln!(out)?;
+    zsh_function(command, &function_name(bin_name), &mut out)?;
+    writeln!(out)?;
+    writeln!(out, "{} \"$@\"", function_name(bin_name))
+}
+
+fn zsh_function(command: &Command, function: &str, out: &mut impl Write) -> ioResult<()> {
+    let nested = subcommands(command).collect::<Vec<_>>();
+    for subcommand in &nested {
+        zsh_function(subcommand, &format!("{}__{}", function, subcommand.get_name().replace('-', "_")), out)?;
+    }
+
+    writeln!(out, "{}() {{", function)?;
+    writeln!(out, "    local curcontext=\"$curcontext\" state line")?;
+    writeln!(out, "    _arguments -C \\")?;
+    for arg in options(command) {
+        let description = zsh_escape(&help(arg.get_help()));
+        let value = match values(arg) {
+            _ if !arg.get_action().takes_values() => String::new(),
+            values if !values.is_empty() => format!(":{}:({})", arg.get_id(), values.join(" ")),
+            _ if takes_path(arg) => format!(":{}:_files", arg.get_id()),
+            _ => format!(":{}: ", arg.get_id()),
+        };
+        let equals = if arg.get_action().takes_values() { "=" } else { "" };
+        for flag in flags(arg) {
+            // a short option takes its value as the next word only
+            let equals = if flag.starts_with("--") { equals } else { "" };
+            writeln!(out, "        {} \\", quote(&format!("{}{}[{}]{}", flag, equals, description, value)))?;
+        }
+    }
+    if nested.is_empty() {
+        writeln!(out, "        '*:: :_default'")?;
+        return writeln!(out, "}}");
+    }
+    writeln!(out, "        ': :->command' \\")?;
+    writeln!(out, "        '*:: :->args'")?;
+    writeln!(out, "    case $state in")?;
+    writeln!(out, "        command)")?;
+    writeln!(out, "            local -a commands=(")?;
+    for subcommand in &nested {
+        for name in std::iter::once(subcommand.get_name()).chain(subcommand.get_visible_aliases()) {
+            writeln!(out, "                {}", quote(&format!("{}:{}", name, help(subcommand.get_about()))))?;
+        }
+    }
+    writeln!(out, "            )")?;
+    writeln!(out, "            _describe -t commands command commands ;;")?;
+    writeln!(out, "        args)")?;
+    writeln!(out, "            case $line[1] in")?;
+    for subcommand in &nested {
+        let names = std::iter::once(subcommand.get_name()).chain(subcommand.get_visible_aliases()).collect::<Vec<_>>();
+        writeln!(out, "                {}) {}__{} ;;", names.join("|"), function, subcommand.get_name().replace('-', "_"))?;
+    }
+    writeln!(out, "            esac ;;")?;
+    writeln!(out, "    esac")?;
+    writeln!(out, "}}")
+}
+
+fn fish(command: &Command, bin_name: &str, mut out: impl Write) -> ioResult<()> {
+    fish_options(command, bin_name, "__fish_use_subcommand", &mut out)?;
+    for subcommand in subcommands(command) {
+        writeln!(out, "complete -c {} -n '__fish_use_subcommand' -f -a {} -d {}",
+                 bin_name, quote(subcommand.get_name()), quote(&help(subcommand.get_about())))?;
+        for alias in subcommand.get_visible_aliases() {
+            writeln!(out, "complete -c {} -n '__fish_use_subcommand' -f -a {} -d {}",
+                     bin_name, quote(alias), quote(&help(subcommand.get_about())))?;
+        }
+        let names = std::iter::once(subcommand.get_name()).chain(subcommand.get_visible_aliases()).collect::<Vec<_>>();
+        let condition = format!("__fish_seen_subcommand_from {}", names.join(" "));
+        fish_options(subcommand, bin_name, &condition, &mut out)?;
+        for nested in subcommands(subcommand) {
+            writeln!(out, "complete -c {} -n {} -f -a {} -d {}",
+                     bin_name, quote(&condition), quote(nested.get_name()), quote(&help(nested.get_about())))?;
+        }
+    }
+    Ok(())
+}
+
+fn fish_options(command: &Command, bin_name: &str, condition: &str, out: &mut impl Write) -> ioResult<()> {
+    for arg in options(command) {
+        let mut line = format!("complete -c {} -n {}", bin_name, quote(condition));
+        if let Some(short) = arg.get_short() {
+            line.push_str(&format!(" -s {}", short));
+        }
+        if let Some(long) = arg.get_long() {
+            line.push_str(&format!(" -l {}", long));
+        }
+        match values(arg) {
+            _ if !arg.get_action().takes_values() => {},
+            values if !values.is_empty() => line.push_str(&format!(" -x -a {}", quote(&values.join(" ")))),
+            _ if takes_path(arg) => line.push_str(" -r -F"),
+            _ => line.push_str(" -x"),
+        }
+        let description = help(arg.get_help());
+        if !description.is_empty() {
+            line.push_str(&format!(" -d {}", quote(&description)));
+        }
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result as ioResult, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use crate::paths;
+
+// Comment: like the clipboard, the journal is compressed through the gzip and zstd tools rather than
+// compression crates, a compressed journal is rewritten whole on every change anyway
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// The compression of a journal file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The compression a journal file is named after, e.g. `archive.json.gz`
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::compress::Compression;
+    /// use std::path::Path;
+    /// assert_eq!(Compression::from_extension(Path::new("archive.json.zst")), Some(Compression::Zstd));
+    /// assert_eq!(Compression::from_extension(Path::new("todo.json")), None);
+    /// ```
+    pub fn from_extension(path: &Path) -> Option<Compression> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// The compression of content starting with the magic bytes of gzip or zstd
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::compress::Compression;
+    /// assert_eq!(Compression::from_magic(&[0x1f, 0x8b, 0x08]), Some(Compression::Gzip));
+    /// assert_eq!(Compression::from_magic(b"[]"), None);
+    /// ```
+    pub fn from_magic(content: &[u8]) -> Option<Compression> {
+        if content.starts_with(GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if content.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// The compression to write the journal with: the one of the existing file, whatever its name,
+    /// otherwise the one the file is named after
+    pub fn of_journal(journal_path: &Path) -> Option<Compression> {
+        let mut head = [0; 4];
+        let read = paths::resolve(journal_path).and_then(File::open)
+                                               .and_then(|mut f| f.read(&mut head))
+                                               .unwrap_or(0);
+        Self::from_magic(&head[..read]).or_else(|| Self::from_extension(journal_path))
+    }
+
+    fn tool(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+}
+
+/// Decompresses the content when it starts with the magic bytes of gzip or zstd, and returns it as it is otherwise
+pub fn decompress(content: Vec<u8>) -> ioResult<Vec<u8>> {
+    match Compression::from_magic(&content) {
+        Some(compression) => pipe(compression, &["-d", "-c", "-q"], content),
+        None => Ok(content),
+    }
+}
+
+/// Compresses the content, c.f. `decompress`
+pub fn compress(compression: Compression, content: Vec<u8>) -> ioResult<Vec<u8>> {
+    pipe(compression, &["-c", "-q"], content)
+}
+
+/// Runs the content through the tool of the compression
+fn pipe(compression: Compression, args: &[&str], content: Vec<u8>) -> ioResult<Vec<u8>> {
+    let tool = compression.tool();
+    let mut child = Command::new(tool).args(args)
+                                      .stdin(Stdio::piped())
+                                      .stdout(Stdio::piped())
+                                      .stderr(Stdio::piped())
+                                      .spawn()
+                                      .map_err(|err| match err.kind() {
+                                          ErrorKind::NotFound => Error::new(ErrorKind::Unsupported,
+                                                                            format!("{} is needed for a {} compressed journal, but it isn't installed", tool, tool)),
+                                          _ => err,
+                                      })?;
+    // Comment: writing from another thread, as the tool stops reading once the pipe of its output is full
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = thread::spawn(move || stdin.write_all(&content));
+    let output = child.wait_with_output()?;
+    writer.join().map_err(|_| Error::other(format!("writing to {} failed", tool)))??;
+    if !output.status.success() {
+        return Err(Error::new(ErrorKind::InvalidData,
+                              format!("{} exited with {}: {}", tool, output.status, String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(output.stdout)
+}
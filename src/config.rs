@@ -0,0 +1,207 @@
+use std::{collections::BTreeMap, env, fs, path::{Path, PathBuf}};
+use chrono::Duration;
+use std::io::{Error, ErrorKind, Result as ioResult};
+use serde::Deserialize;
+use crate::error::Context;
+use crate::i18n::Locale;
+use crate::i18n::tr;
+use crate::normalize;
+use crate::output;
+use crate::score;
+use crate::span;
+use crate::stdio;
+
+/// The user configuration, read from a JSON file. Every setting is optional.
+///
+/// The file is looked up at `$RUSTY_JOURNAL_CONFIG` if set, and otherwise at
+/// `rusty-journal/config.json` in the user's config directory (`$XDG_CONFIG_HOME`,
+/// falling back to `~/.config`, or `%APPDATA%` on Windows).
+/// ```json
+/// {
+///     "macros": {
+///         "morning": ["list --tag today", "report --template /home/alice/standup.tera"]
+///     },
+///     "notify": true,
+///     "user": "alice",
+///     "locale": "de",
+///     "read_only": false,
+///     "redact": false,
+///     "urgency": {"tag": {"next": 15.0}},
+///     "daily_capacity": "6h",
+///     "stale_after": "30d",
+///     "trash_retention": "30d",
+///     "undo_depth": 10,
+///     "cascade": true,
+///     "journal": "/home/alice/notes/todo.json",
+///     "normalize": {
+///         "rename": [{"pattern": "^(?i)todo:?\\s*", "replace": ""}],
+///         "tags": {"wip": "in-progress"}
+///     }
+/// }
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Named sequences of subcommands, run in order by invoking the macro name as a subcommand. The steps work on
+    /// the journal of the invocation, read once and written once all of them succeeded
+    pub macros: BTreeMap<String, Vec<String>>,
+    /// Whether to show desktop notifications on significant events, can be turned off per run with `--no-notify`
+    pub notify: bool,
+    /// Normalization applied to tasks coming in through `add --from-file` and `import`
+    pub normalize: normalize::Rules,
+    /// The current user, recorded as the owner of the tasks they add, defaults to `$USER` (`%USERNAME%` on Windows)
+    pub user: Option<String>,
+    /// Whether to refuse any change to the journal, same as `--read-only`
+    pub read_only: bool,
+    /// Whether `list` masks the task names, same as `--redact`
+    pub redact: bool,
+    /// The weights of the urgency score used by `next` and `list --sort urgency`, see `score::Coefficients`
+    pub urgency: score::Coefficients,
+    /// The time available for tasks every day, e.g. `6h30m`, against which `plan` sums up the estimates, 8h by default
+    pub daily_capacity: Option<String>,
+    /// How long an active task may go untouched before `list` and `stats` point it out as stale, 30d by default
+    pub stale_after: Option<String>,
+    /// How long removed tasks can be restored before the trash is emptied of them, 30d by default
+    pub trash_retention: Option<String>,
+    /// How many changes of the journal `undo` can put back, 10 by default, 0 to keep no backups of the journal
+    pub undo_depth: Option<usize>,
+    /// Whether `done` and `remove` also complete or remove the subtasks of a task, true by default
+    pub cascade: Option<bool>,
+    /// The journal used when neither `--journal_file` nor `$RUSTY_JOURNAL_FILE` is given, c.f. `paths::journal`.
+    /// A relative path is relative to the directory of the config file
+    pub journal: Option<PathBuf>,
+    /// The language of the messages, e.g. `de`, taking precedence over `LC_ALL`, `LC_MESSAGES` and `LANG`
+    pub locale: Option<String>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("RUSTY_JOURNAL_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+                             .map(PathBuf::from)
+                             .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+                             .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+
+        Some(config_dir.join("rusty-journal").join("config.json"))
+    }
+
+    /// Loads the user configuration, where a missing config file means the default configuration
+    pub fn load() -> ioResult<Config> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        };
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Config::default()),
+            Err(err) => return Err(err).with_path("reading config", &path),
+        };
+
+        let mut config: Config = serde_json::from_str(&content)
+                                            .map_err(|err| Error::new(ErrorKind::InvalidData,
+                                                                      tr("error-config", &[("path", &path.display()), ("error", &err)])))?;
+        // Comment: a relative journal is relative to the config file, as the commands run from any directory
+        if let Some(journal) = config.journal.as_mut().filter(|journal| journal.is_relative() && !journal.as_os_str().is_empty() && !stdio::is_stdio(journal)) {
+            *journal = path.parent().unwrap_or(Path::new("")).join(&journal);
+        }
+        Ok(config)
+    }
+
+    /// The current user, as set in the config or else as logged in
+    pub fn user(&self) -> Option<String> {
+        if output::is_deterministic() {
+            return self.user.clone();
+        }
+        self.user.clone()
+            .or_else(|| env::var("USER").ok())
+            .or_else(|| env::var("USERNAME").ok())
+            .filter(|user| !user.is_empty())
+    }
+
+    /// The daily capacity set in the config, or 8 hours
+    pub fn daily_capacity(&self) -> ioResult<Duration> {
+        match &self.daily_capacity {
+            Some(capacity) => span::parse(capacity).map_err(|err| Error::new(ErrorKind::InvalidInput,
+                                                                             tr("error-config-setting", &[("setting", &"daily_capacity"), ("error", &err)]))),
+            None => Ok(Duration::hours(8)),
+        }
+    }
+
+    /// The stale_after set in the config, or 30 days
+    pub fn stale_after(&self) -> ioResult<Duration> {
+        match &self.stale_after {
+            Some(after) => span::parse(after).map_err(|err| Error::new(ErrorKind::InvalidInput,
+                                                                       tr("error-config-setting", &[("setting", &"stale_after"), ("error", &err)]))),
+            None => Ok(Duration::days(30)),
+        }
+    }
+
+    /// The trash_retention set in the config, or 30 days
+    pub fn trash_retention(&self) -> ioResult<Duration> {
+        match &self.trash_retention {
+            Some(retention) => span::parse(retention).map_err(|err| Error::new(ErrorKind::InvalidInput,
+                                                                               tr("error-config-setting", &[("setting", &"trash_retention"), ("error", &err)]))),
+            None => Ok(Duration::days(30)),
+        }
+    }
+
+    /// The locale set in the config, or else the one of the environment. Like the time zone,
+    /// the environment is ignored in deterministic mode
+    pub fn locale(&self) -> ioResult<Locale> {
+        match &self.locale {
+            Some(name) => Locale::parse(name).ok_or_else(|| Error::new(ErrorKind::InvalidInput,
+                                                                       tr("error-config-locale", &[("locale", &name)]))),
+            None if output::is_deterministic() => Ok(Locale::En),
+            None => Ok(Locale::from_env().unwrap_or(Locale::En)),
+        }
+    }
+}
+
+/// Splits a command line into its arguments, honoring single and double quotes
+/// as well as backslash escapes, roughly the way a POSIX shell would.
+/// # Examples
+/// ```
+/// use rusty_journal_clap::config::split_command_line;
+/// assert_eq!(split_command_line(r#"add --task "buy milk" --tag 'err''and'"#).unwrap(),
+///            vec!["add", "--task", "buy milk", "--tag", "errand"]);
+/// assert!(split_command_line(r#"add --task "buy milk"#).is_err());
+/// ```
+pub fn split_command_line(line: &str) -> ioResult<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some(escaped) = chars.next() {
+                    current.get_or_insert_with(String::new).push(escaped);
+                }
+            }
+            (Some(_), c) => current.get_or_insert_with(String::new).push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => {
+                if let Some(arg) = current.take() {
+                    args.push(arg);
+                }
+            }
+            (None, c) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err(Error::new(ErrorKind::InvalidInput, tr("error-unterminated-quote", &[("line", &line)])));
+    }
+    args.extend(current);
+
+    Ok(args)
+}
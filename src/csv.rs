@@ -0,0 +1,62 @@
+use std::io::Result as ioResult;
+use crate::error::ParseFailure;
+
+/// Parses CSV content as described by RFC 4180 into records of fields:
+/// fields are separated by commas, and quoted fields may contain commas, line breaks and doubled quotes.
+/// A byte order mark at the start and blank lines are ignored.
+/// # Examples
+/// ```
+/// use rusty_journal_clap::csv;
+/// let records = csv::parse("Title,Labels\r\n\"Say \"\"hi\"\"\",\"a,b\"\n").unwrap();
+/// assert_eq!(records, vec![vec!["Title", "Labels"], vec!["Say \"hi\"", "a,b"]]);
+/// assert!(csv::parse("\"unterminated").is_err());
+/// ```
+pub fn parse(content: &str) -> ioResult<Vec<Vec<String>>> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        match (in_quotes, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => in_quotes = false,
+            (true, c) => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+            (false, '"') if field.is_empty() => in_quotes = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {},
+            (false, '\n') => {
+                line += 1;
+                record.push(std::mem::take(&mut field));
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            (false, c) => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(ParseFailure::of(format!("Unterminated quoted field in CSV at line {}", line)));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
@@ -0,0 +1,102 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Result as ioResult, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde_json::{json, Value};
+use crate::error::Context;
+use crate::i18n::tr;
+use crate::rpc::RpcServer;
+
+// Comment: a client stuck halfway through a request would block every other client, the connections being
+// served one at a time, so it is dropped after a while
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The daemon of a journal listens next to it, e.g. on `todo.sock` for `todo.json`
+/// # Examples
+/// ```
+/// use rusty_journal_clap::daemon;
+/// use std::path::{Path, PathBuf};
+/// assert_eq!(daemon::socket_path(Path::new("work/todo.json")), PathBuf::from("work/todo.sock"));
+/// ```
+pub fn socket_path(journal_path: &Path) -> PathBuf {
+    journal_path.with_extension("sock")
+}
+
+/// Keeps the journal loaded and serves the JSON-RPC requests of `rpc::RpcServer` on the socket of the journal,
+/// one per line, until killed. The journal is loaded again only when another process changes it, and the daemon
+/// makes its changes to the tasks it keeps and writes them to the journal under its lock, same as the command line
+pub fn serve(journal_path: PathBuf) -> ioResult<()> {
+    let socket = socket_path(&journal_path);
+    if UnixStream::connect(&socket).is_ok() {
+        return Err(Error::new(ErrorKind::AddrInUse, tr("error-daemon-running", &[]))).with_path("starting daemon", &socket);
+    }
+    // Comment: a socket file left behind by a daemon that was killed refuses connections, and is replaced
+    match fs::remove_file(&socket) {
+        Err(err) if err.kind() != ErrorKind::NotFound => return Err(err).with_path("starting daemon", &socket),
+        _ => {}
+    }
+    let listener = UnixListener::bind(&socket).with_path("starting daemon", &socket)?;
+
+    let mut server = RpcServer::resident(journal_path)?;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        // a client going away in the middle of its requests only ends its own connection
+        let _ = serve_client(&mut server, stream);
+    }
+    Ok(())
+}
+
+fn serve_client(server: &mut RpcServer, stream: UnixStream) -> ioResult<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut output = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle(&line) {
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// A connection to the daemon of a journal
+pub struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    next_id: u64,
+}
+
+impl Client {
+    /// Connects to the daemon of the journal, or returns None when none is running
+    pub fn connect(journal_path: &Path) -> Option<Client> {
+        let writer = UnixStream::connect(socket_path(journal_path)).ok()?;
+        let reader = BufReader::new(writer.try_clone().ok()?);
+        Some(Client { reader, writer, next_id: 1 })
+    }
+
+    /// Sends a request to the daemon and waits for its result
+    pub fn call(&mut self, method: &str, params: Value) -> ioResult<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        writeln!(self.writer, "{}", json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))?;
+        self.writer.flush()?;
+
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, tr("error-daemon-closed", &[])));
+        }
+        let mut response: Value = serde_json::from_str(&line).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        match response.get("error") {
+            // Comment: the daemon answers with the message of the error the journal operation failed with
+            Some(error) => Err(Error::other(error["message"].as_str().unwrap_or("the daemon failed").to_string())),
+            None => Ok(response["result"].take()),
+        }
+    }
+}
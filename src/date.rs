@@ -0,0 +1,46 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc, Weekday};
+use crate::clock;
+use crate::i18n::tr;
+use crate::output;
+use crate::span;
+
+/// Parses a date given on the command line: `today`, `tomorrow`, `next week` for a week from today, a weekday for the next one, e.g. `monday`,
+/// `2024-07-01`, `2024-07-01 14:30`, or a span of time from now, e.g. `3d` (c.f. `span::parse`).
+/// A date without time is the start of that day in the local timezone, or in UTC in deterministic mode
+/// # Examples
+/// ```
+/// use rusty_journal_clap::{clock::{self, FixedClock}, date, output};
+/// use chrono::DateTime;
+/// output::set_deterministic(true);
+/// let _guard = clock::install(FixedClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap()));
+/// assert_eq!(date::parse("tomorrow").unwrap().to_rfc3339(), "2023-11-15T00:00:00+00:00");
+/// assert_eq!(date::parse("friday").unwrap().to_rfc3339(), "2023-11-17T00:00:00+00:00");
+/// assert_eq!(date::parse("next week").unwrap().to_rfc3339(), "2023-11-21T00:00:00+00:00");
+/// assert_eq!(date::parse("2024-07-01 14:30").unwrap().to_rfc3339(), "2024-07-01T14:30:00+00:00");
+/// assert_eq!(date::parse("2h").unwrap().to_rfc3339(), "2023-11-15T00:13:20+00:00");
+/// assert!(date::parse("someday").is_err());
+/// ```
+pub fn parse(date: &str) -> Result<DateTime<Utc>, String> {
+    let invalid = || tr("error-invalid-date", &[("date", &date)]);
+    let now = clock::now();
+    let today = output::date(now);
+    let text = date.trim().to_lowercase();
+
+    let day = match text.as_str() {
+        "today" => Some(today),
+        "tomorrow" => today.succ_opt(),
+        "next week" => Some(today + Duration::weeks(1)),
+        weekday => weekday.parse::<Weekday>().ok().map(|weekday| {
+            // the next one, a week ahead when today is that weekday
+            let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday() - 1) % 7 + 1;
+            today + Duration::days(ahead.into())
+        }),
+    };
+    if let Some(day) = day.or_else(|| NaiveDate::parse_from_str(&text, "%Y-%m-%d").ok()) {
+        return output::from_local(day.and_hms_opt(0, 0, 0).unwrap()).ok_or_else(invalid);
+    }
+    if let Ok(time) = NaiveDateTime::parse_from_str(&text, "%Y-%m-%d %H:%M") {
+        return output::from_local(time).ok_or_else(invalid);
+    }
+    span::parse(&text).map(|span| now + span).map_err(|_| invalid())
+}
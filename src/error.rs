@@ -0,0 +1,358 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{Error, ErrorKind, Result as ioResult};
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::i18n::{self, tr};
+
+/// An I/O or parse error together with what was being done and to which file, e.g.
+/// `while reading journal '/home/me/todo.json': No such file or directory (os error 2)`.
+/// It travels inside an `io::Error` of the same kind as the original error, so the helpers keep
+/// returning `io::Result` and the journal API sorts it into a `JournalError` by the error it wraps
+#[derive(Debug)]
+pub struct ContextError {
+    action: String,
+    path: Option<PathBuf>,
+    source: Error,
+}
+
+impl ContextError {
+    /// What was being done, e.g. `reading journal`
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    /// The file that was being worked on, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+}
+
+impl Display for ContextError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        // the actions are given in English, e.g. `reading journal` is translated as `action-reading-journal`
+        let action = i18n::lookup(&format!("action-{}", self.action.replace(' ', "-"))).unwrap_or(&self.action);
+        match &self.path {
+            Some(path) => write!(f, "{}", tr("error-while-path", &[("action", &action), ("path", &path.display()), ("error", &self.source)])),
+            None => write!(f, "{}", tr("error-while", &[("action", &action), ("error", &self.source)])),
+        }
+    }
+}
+
+impl error::Error for ContextError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Adds context to the error of an `io::Result`
+/// # Examples
+/// ```
+/// use rusty_journal_clap::error::Context;
+/// use std::{fs, path::Path};
+/// let path = Path::new("no/such/journal.json");
+/// let err = fs::read(path).with_path("reading journal", path).unwrap_err();
+/// assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+/// assert!(err.to_string().starts_with("while reading journal 'no/such/journal.json': "));
+/// ```
+pub trait Context<T> {
+    fn context(self, action: &str) -> ioResult<T>;
+
+    fn with_path(self, action: &str, path: &Path) -> ioResult<T>;
+}
+
+impl<T> Context<T> for ioResult<T> {
+    fn context(self, action: &str) -> ioResult<T> {
+        self.map_err(|err| wrap(err, action, None))
+    }
+
+    fn with_path(self, action: &str, path: &Path) -> ioResult<T> {
+        self.map_err(|err| wrap(err, action, Some(path.to_path_buf())))
+    }
+}
+
+fn wrap(source: Error, action: &str, path: Option<PathBuf>) -> Error {
+    Error::new(source.kind(), ContextError { action: action.to_string(), path, source })
+}
+
+/// A task index given by the user that is out of the journal's range, travelling inside
+/// an `io::Error` of kind `InvalidInput`
+#[derive(Debug)]
+pub struct IndexError {
+    index: usize,
+    max: usize,
+}
+
+impl IndexError {
+    pub fn out_of_range(index: usize, max: usize) -> Error {
+        Error::new(ErrorKind::InvalidInput, IndexError { index, max })
+    }
+
+    /// The index as given by the user, starting at 1
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The largest valid index, i.e. the number of tasks in the journal
+    pub fn max(&self) -> usize {
+        self.max
+    }
+}
+
+impl Display for IndexError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", index_message(self.index, self.max))
+    }
+}
+
+impl error::Error for IndexError {}
+
+/// Content that can't be parsed, e.g. a damaged journal or a CSV file with an unterminated quote, travelling inside
+/// an `io::Error` of kind `InvalidData`. Other `InvalidData` errors, e.g. a compression tool failing, aren't parse errors
+#[derive(Debug)]
+pub struct ParseFailure {
+    source: Box<dyn error::Error + Send + Sync>,
+}
+
+impl ParseFailure {
+    pub fn of(source: impl Into<Box<dyn error::Error + Send + Sync>>) -> Error {
+        Error::new(ErrorKind::InvalidData, ParseFailure { source: source.into() })
+    }
+}
+
+impl Display for ParseFailure {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl error::Error for ParseFailure {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+fn index_message(given: usize, max: usize) -> String {
+    match max {
+        0 => tr("error-index-empty", &[("given", &given)]),
+        _ => tr("error-index-out-of-range", &[("given", &given), ("max", &max)]),
+    }
+}
+
+/// The error of the journal API, sorting out what a caller may want to handle: an index out of range,
+/// a journal (or import) that can't be parsed, or any other I/O error
+/// # Examples
+/// ```
+/// use rusty_journal_clap::error::{IndexError, JournalError, ParseFailure};
+/// use std::io::{Error, ErrorKind};
+/// assert!(matches!(JournalError::from(IndexError::out_of_range(9, 5)), JournalError::IndexOutOfRange { given: 9, max: 5 }));
+/// assert!(matches!(JournalError::from(ParseFailure::of("expected value")), JournalError::ParseError(_)));
+/// assert_eq!(JournalError::from(ParseFailure::of("expected value")).exit_code(), 4);
+/// assert_eq!(JournalError::from(Error::new(ErrorKind::InvalidData, "zstd exited with 1")).exit_code(), 1);
+/// assert_eq!(JournalError::from(Error::new(ErrorKind::NotFound, "gone")).exit_code(), 1);
+/// assert_eq!(JournalError::Overdue { count: 2 }.exit_code(), 5);
+/// ```
+#[derive(Debug)]
+pub enum JournalError {
+    /// A task index given by the user, starting at 1, beyond the number of tasks
+    IndexOutOfRange { given: usize, max: usize },
+    /// Content that isn't a valid journal or export, with what was being read
+    ParseError(Error),
+    Io(Error),
+    /// Tasks past their due date, reported by `report --plain` after printing the report, so cron can key off it
+    Overdue { count: usize },
+}
+
+pub type JournalResult<T> = Result<T, JournalError>;
+
+impl JournalError {
+    /// The exit status of the process failing with this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            JournalError::Io(_) => 1,
+            JournalError::IndexOutOfRange { .. } => 3,
+            JournalError::ParseError(_) => 4,
+            JournalError::Overdue { .. } => 5,
+        }
+    }
+}
+
+impl Display for JournalError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            JournalError::IndexOutOfRange { given, max } => write!(f, "{}", index_message(*given, *max)),
+            JournalError::ParseError(err) => write!(f, "{}", tr("error-parse", &[("error", err)])),
+            JournalError::Io(err) => write!(f, "{}", err),
+            JournalError::Overdue { count } => write!(f, "{}", tr("error-overdue", &[("count", count)])),
+        }
+    }
+}
+
+impl error::Error for JournalError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            JournalError::IndexOutOfRange { .. } | JournalError::Overdue { .. } => None,
+            JournalError::ParseError(err) | JournalError::Io(err) => Some(err),
+        }
+    }
+}
+
+impl From<Error> for JournalError {
+    fn from(err: Error) -> Self {
+        // Comment: the index or parse error may be wrapped in context, which says nothing the message doesn't
+        let mut current: Option<&(dyn error::Error + 'static)> = err.get_ref().map(|inner| inner as _);
+        while let Some(inner) = current {
+            if let Some(index_error) = inner.downcast_ref::<IndexError>() {
+                return JournalError::IndexOutOfRange { given: index_error.index, max: index_error.max };
+            }
+            if inner.is::<ParseFailure>() {
+                return JournalError::ParseError(err);
+            }
+            current = match inner.downcast_ref::<ContextError>() {
+                Some(context_error) => context_error.source.get_ref().map(|inner| inner as _),
+                None => None,
+            };
+        }
+        JournalError::Io(err)
+    }
+}
+
+// Comment: so the journal API can still be called from code returning io::Result
+impl From<JournalError> for Error {
+    fn from(err: JournalError) -> Self {
+        match err {
+            JournalError::IndexOutOfRange { given, max } => IndexError::out_of_range(given, max),
+            JournalError::ParseError(err) | JournalError::Io(err) => err,
+            overdue @ JournalError::Overdue { .. } => Error::other(overdue.to_string()),
+        }
+    }
+}
+
+/// An error of a subcommand, naming the subcommand in its message, e.g.
+/// ``error in `list`: while reading journal 'todo.json': ...``
+#[derive(Debug)]
+pub struct CommandError {
+    command: String,
+    source: Box<dyn error::Error>,
+}
+
+impl CommandError {
+    pub fn new(command: &str, source: Box<dyn error::Error>) -> Self {
+        // Comment: an io::Error is sorted into a JournalError, which `exit_code` takes the exit status of
+        let source = match source.downcast::<Error>() {
+            Ok(io_error) => Box::new(JournalError::from(*io_error)),
+            Err(source) => source,
+        };
+        CommandError { command: command.to_string(), source }
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", tr("error-in-command", &[("command", &self.command), ("error", &self.source)]))
+    }
+}
+
+impl error::Error for CommandError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// The details of an error that programs may want to act on, gathered from the context
+/// attached along the way, c.f. `--error-format json`
+/// # Examples
+/// ```
+/// use rusty_journal_clap::error::{CommandError, Context, Details, IndexError};
+/// use std::path::Path;
+/// let err: Result<(), _> = Err(IndexError::out_of_range(9, 5));
+/// let err = err.with_path("removing from journal", Path::new("todo.json")).unwrap_err();
+/// let err = CommandError::new("remove", err.into());
+/// let details = Details::of(&err);
+/// assert_eq!(details.code, "invalid_index");
+/// assert_eq!(details.index, Some(9));
+/// assert_eq!(details.command.as_deref(), Some("remove"));
+/// ```
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Details {
+    /// A stable identifier of the kind of error, e.g. `not_found` or `invalid_index`
+    pub code: &'static str,
+    /// The message as shown to humans
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "crate::paths::serialize_lossy")]
+    pub path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+}
+
+impl Details {
+    pub fn of(err: &(dyn error::Error + 'static)) -> Details {
+        let mut details = Details { code: "error", message: err.to_string(), ..Details::default() };
+
+        // Comment: io::Error hides its payload from source(), so the chain is walked with get_ref() there
+        let mut current = Some(err);
+        while let Some(err) = current {
+            if let Some(command_error) = err.downcast_ref::<CommandError>() {
+                details.command = Some(command_error.command.clone());
+            } else if let Some(context_error) = err.downcast_ref::<ContextError>() {
+                details.path = details.path.take().or_else(|| context_error.path.clone());
+            } else if let Some(index_error) = err.downcast_ref::<IndexError>() {
+                details.code = "invalid_index";
+                details.index = Some(index_error.index);
+            } else if let Some(&JournalError::IndexOutOfRange { given, .. }) = err.downcast_ref::<JournalError>() {
+                details.code = "invalid_index";
+                details.index = Some(given);
+            } else if let Some(io_error) = err.downcast_ref::<Error>() {
+                if details.code == "error" {
+                    details.code = io_code(io_error.kind());
+                }
+                if let Some(inner) = io_error.get_ref() {
+                    current = Some(inner);
+                    continue;
+                }
+            }
+            current = err.source();
+        }
+
+        details
+    }
+}
+
+fn io_code(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::NotFound => "not_found",
+        ErrorKind::PermissionDenied => "permission_denied",
+        ErrorKind::AlreadyExists => "already_exists",
+        ErrorKind::InvalidInput => "invalid_input",
+        ErrorKind::InvalidData => "invalid_data",
+        ErrorKind::Interrupted => "interrupted",
+        _ => "io",
+    }
+}
+
+/// The exit status of the process failing with the error, the one of the `JournalError` it holds, c.f. `JournalError::exit_code`,
+/// and 1 for any other error
+/// # Examples
+/// ```
+/// use rusty_journal_clap::error::{exit_code, CommandError, IndexError, ParseFailure};
+/// use std::io::{Error, ErrorKind};
+/// assert_eq!(exit_code(&CommandError::new("remove", IndexError::out_of_range(9, 5).into())), 3);
+/// assert_eq!(exit_code(&CommandError::new("list", ParseFailure::of("expected value").into())), 4);
+/// assert_eq!(exit_code(&CommandError::new("list", Error::new(ErrorKind::InvalidData, "gzip exited with 1").into())), 1);
+/// ```
+pub fn exit_code(err: &(dyn error::Error + 'static)) -> i32 {
+    let mut current = Some(err);
+    while let Some(err) = current {
+        if let Some(journal_error) = err.downcast_ref::<JournalError>() {
+            return journal_error.exit_code();
+        }
+        current = err.source();
+    }
+    1
+}
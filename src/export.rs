@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use chrono::{DateTime, Utc};
+use crate::output;
+use crate::task::Task;
+use crate::template::escape_html;
+
+/// The file formats tasks can be exported to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Html,
+    // a standalone journal, e.g. to share the tasks of one client
+    Json,
+    // one row per task, which `import --from csv` reads back
+    Csv,
+    // a table to paste into an issue or a wiki page
+    Markdown,
+}
+
+impl Format {
+    pub fn parse(format: &str) -> Result<Format, String> {
+        match format {
+            "html" => Ok(Format::Html),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "markdown" | "md" => Ok(Format::Markdown),
+            _ => Err(format!("unknown export format '{}', expected html, json, csv or markdown", format)),
+        }
+    }
+}
+
+/// How the tasks of an HTML export are grouped into sections
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    // one section per tag, a task with several tags shows up in each of them
+    Tag,
+    // active tasks first, then the completed ones
+    State,
+}
+
+impl GroupBy {
+    pub fn parse(group_by: &str) -> Result<GroupBy, String> {
+        match group_by {
+            "tag" => Ok(GroupBy::Tag),
+            "state" => Ok(GroupBy::State),
+            _ => Err(format!("unknown grouping '{}', expected tag or state", group_by)),
+        }
+    }
+}
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 60rem; color: #222; }
+h1 { font-size: 1.5rem; }
+h2 { font-size: 1.1rem; margin-top: 2rem; border-bottom: 1px solid #ddd; }
+#filter { width: 100%; padding: .5rem; font-size: 1rem; box-sizing: border-box; }
+table { width: 100%; border-collapse: collapse; }
+td { padding: .3rem .5rem; border-bottom: 1px solid #eee; vertical-align: top; }
+td.index { width: 3rem; color: #888; text-align: right; }
+td.date { width: 9rem; color: #888; white-space: nowrap; }
+tr.complete td.name { text-decoration: line-through; color: #888; }
+.tag { display: inline-block; margin-left: .3rem; padding: 0 .4rem; border-radius: .6rem; background: #e8eef8; font-size: .8rem; }
+";
+
+// Comment: hides the rows not containing the typed text, and the sections left without rows
+const SCRIPT: &str = "
+document.getElementById('filter').addEventListener('input', function (event) {
+  var query = event.target.value.toLowerCase();
+  document.querySelectorAll('section').forEach(function (section) {
+    var shown = 0;
+    section.querySelectorAll('tr').forEach(function (row) {
+      var match = row.textContent.toLowerCase().indexOf(query) !== -1;
+      row.hidden = !match;
+      if (match) { shown++; }
+    });
+    section.hidden = shown === 0;
+  });
+});
+";
+
+/// Renders the tasks as a single self-contained HTML page, with the CSS and the filtering
+/// script embedded so the file can be shared as it is
+/// # Examples
+/// ```
+/// use rusty_journal_clap::export::{html, GroupBy};
+/// let page = html(&[], GroupBy::Tag);
+/// assert!(page.starts_with("<!DOCTYPE html>"));
+/// ```
+pub fn html(tasks: &[Task], group_by: GroupBy) -> String {
+    let indexed = tasks.iter()
+                       .enumerate()
+                       .map(|(i, task)| (i + 1, task))
+                       .collect::<Vec<_>>();
+
+    let sections: Vec<(String, Vec<(usize, &Task)>)> = match group_by {
+        GroupBy::Tag => {
+            let mut by_tag: BTreeMap<&str, Vec<(usize, &Task)>> = BTreeMap::new();
+            let mut untagged = Vec::new();
+            for &(index, task) in &indexed {
+                if task.tags().is_empty() {
+                    untagged.push((index, task));
+                }
+                for tag in task.tags() {
+                    by_tag.entry(tag).or_default().push((index, task));
+                }
+            }
+            let mut sections = by_tag.into_iter()
+                                     .map(|(tag, tasks)| (tag.to_string(), tasks))
+                                     .collect::<Vec<_>>();
+            if !untagged.is_empty() {
+                sections.push(("Untagged".to_string(), untagged));
+            }
+            sections
+        }
+        GroupBy::State => {
+            let (complete, active): (Vec<_>, Vec<_>) = indexed.iter().partition(|(_, task)| task.is_complete());
+            vec![("Active".to_string(), active), ("Complete".to_string(), complete)]
+        }
+    };
+
+    let active = tasks.iter().filter(|task| !task.is_complete()).count();
+    let mut page = String::new();
+    // Comment: writing to a String can't fail
+    let _ = write!(page, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+                          <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+                          <title>Tasks</title>\n<style>{}</style>\n</head>\n<body>\n\
+                          <h1>Tasks <small>{} active, {} complete</small></h1>\n\
+                          <input id=\"filter\" type=\"search\" placeholder=\"Filter tasks\" autofocus>\n",
+                   STYLE, active, tasks.len() - active);
+    for (title, section_tasks) in &sections {
+        let _ = write!(page, "<section>\n<h2>{} ({})</h2>\n<table>\n", escape_html(title), section_tasks.len());
+        for (index, task) in section_tasks {
+            let class = if task.is_complete() { "complete" } else { "active" };
+            let tags = task.tags()
+                           .iter()
+                           .map(|tag| format!("<span class=\"tag\">{}</span>", escape_html(tag)))
+                           .collect::<String>();
+            let _ = writeln!(page, "<tr class=\"{}\"><td class=\"index\">{}</td><td class=\"name\">{}{}</td><td class=\"date\">{}</td></tr>",
+                             class, index, escape_html(task.name()), tags,
+                             output::format_time(task.completed_at().unwrap_or(task.created_at()), "%Y-%m-%d %H:%M"));
+        }
+        page.push_str("</table>\n</section>\n");
+    }
+    let _ = write!(page, "<script>{}</script>\n</body>\n</html>\n", SCRIPT);
+    page
+}
+
+// the tags of a task are joined as `import` splits them
+fn joined_tags(task: &Task) -> String {
+    task.tags().join("; ")
+}
+
+fn format_date(date: Option<DateTime<Utc>>) -> String {
+    date.map(|date| output::format_time(date, "%Y-%m-%d %H:%M")).unwrap_or_default()
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Renders the tasks as CSV with a header row, the columns `import --from csv` recognizes coming first
+/// # Examples
+/// ```
+/// use rusty_journal_clap::export::csv;
+/// assert_eq!(csv(&[]), "index,name,state,tags,created_at,due,completed_at\r\n");
+/// ```
+pub fn csv(tasks: &[Task]) -> String {
+    let mut table = String::from("index,name,state,tags,created_at,due,completed_at\r\n");
+    for (i, task) in tasks.iter().enumerate() {
+        let fields = [(i + 1).to_string(),
+                      task.name().to_string(),
+                      task.state_name().to_string(),
+                      joined_tags(task),
+                      format_date(Some(task.created_at())),
+                      format_date(task.due()),
+                      format_date(task.completed_at())];
+        // Comment: writing to a String can't fail
+        let _ = write!(table, "{}\r\n", fields.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+    }
+    table
+}
+
+/// Renders the tasks as a Markdown table
+/// # Examples
+/// ```
+/// use rusty_journal_clap::export::markdown;
+/// assert!(markdown(&[]).starts_with("| # | Task | State | Tags | Created | Due |\n"));
+/// ```
+pub fn markdown(tasks: &[Task]) -> String {
+    let cell = |text: &str| text.replace('|', "\\|").replace('\n', " ");
+    let mut table = String::from("| # | Task | State | Tags | Created | Due |\n|---:|---|---|---|---|---|\n");
+    for (i, task) in tasks.iter().enumerate() {
+        let _ = writeln!(table, "| {} | {} | {} | {} | {} | {} |", i + 1, cell(task.name()), task.state_name(), cell(&task.tags().join(", ")),
+                         format_date(Some(task.created_at())), format_date(task.due()));
+    }
+    table
+}
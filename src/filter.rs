@@ -0,0 +1,216 @@
+use std::io::{Error, ErrorKind, Result as ioResult};
+use chrono::{DateTime, Duration, Utc};
+use crate::{clock, config::split_command_line, date, regex::Regex, span, task::Task, trace};
+use crate::i18n::tr;
+
+/// A compiled regular expression compared by its source, so terms holding one can still be compared
+#[derive(Debug, Clone)]
+struct Pattern {
+    source: String,
+    regex: Regex,
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    Tag(String),
+    // any of the tags
+    AnyTag(Vec<String>),
+    Name(String),
+    NameRegex(Pattern),
+    Owner(String),
+    Assignee(String),
+    Goal(String),
+    // related to the task with the id
+    Related(u64),
+    // any delegated task without a person
+    Delegated(Option<String>),
+    // active and untouched for at least that long
+    Stale(Duration),
+    CreatedAfter(DateTime<Utc>),
+    CreatedBefore(DateTime<Utc>),
+    Active,
+    Waiting,
+    Complete,
+}
+
+impl Term {
+    fn matches(&self, task: &Task) -> bool {
+        match self {
+            Term::Tag(tag) => task.has_tag(tag),
+            Term::AnyTag(tags) => tags.iter().any(|tag| task.has_tag(tag)),
+            Term::Name(part) => task.name().to_lowercase().contains(part.as_str()),
+            Term::NameRegex(pattern) => pattern.regex.is_match(task.name()),
+            Term::Owner(owner) => task.owner() == Some(owner),
+            Term::Assignee(assignee) => task.assignee() == Some(assignee),
+            Term::Goal(goal) => task.goal() == Some(goal),
+            Term::Related(id) => task.related().contains(id),
+            Term::Delegated(None) => task.delegated_to().is_some(),
+            Term::Delegated(Some(person)) => task.delegated_to() == Some(person),
+            Term::Stale(after) => task.is_stale(*after, clock::now()),
+            Term::CreatedAfter(after) => task.created_at() >= *after,
+            Term::CreatedBefore(before) => task.created_at() < *before,
+            Term::Active => !task.is_complete() && !task.is_waiting(),
+            Term::Waiting => task.is_waiting(),
+            Term::Complete => task.is_complete(),
+        }
+    }
+}
+
+/// A filter expression selecting tasks, as given to `--where`.
+///
+/// The expression is a whitespace separated list of terms which must all match:
+/// `tag:<tag>`, `name:<text>` (case-insensitive substring of the name), `regex:<pattern>` (c.f. `regex::Regex`),
+/// `owner:<user>`, `assignee:<user>`, `delegated:<person>` (`delegated:` for any delegated task), `goal:<name>`, `related:<id>`,
+/// `stale:<span>` (active and untouched for at least that long, e.g. `stale:30d`), `created-after:<date>` and `created-before:<date>`
+/// (c.f. `date::parse`, e.g. `created-after:2024-07-01`) and `state:active|waiting|complete`.
+/// A term prefixed with `-` must not match, and values containing spaces can be quoted.
+/// # Examples
+/// ```
+/// use rusty_journal_clap::filter::TaskFilter;
+/// assert!(TaskFilter::parse(r#"tag:work -tag:done name:"quarterly report""#).is_ok());
+/// assert!(TaskFilter::parse(r#"regex:^(?i)review created-after:2024-07-01"#).is_ok());
+/// assert!(TaskFilter::parse("state:sleeping").is_err());
+/// assert!(TaskFilter::parse("work").is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskFilter {
+    terms: Vec<(bool, Term)>,
+}
+
+impl TaskFilter {
+    pub fn parse(expression: &str) -> ioResult<TaskFilter> {
+        let invalid = |msg: String| Error::new(ErrorKind::InvalidInput, tr("error-invalid-filter", &[("expression", &expression), ("error", &msg)]));
+
+        let mut terms = Vec::new();
+        for word in split_command_line(expression)? {
+            let (negated, word) = match word.strip_prefix('-') {
+                Some(word) => (true, word),
+                None => (false, word.as_str()),
+            };
+            let (field, value) = word.split_once(':')
+                                     .ok_or_else(|| invalid(tr("error-filter-term", &[("word", &word)])))?;
+            let term = match (field, value) {
+                ("tag", tag) => Term::Tag(tag.to_string()),
+                ("name", part) => Term::Name(part.to_lowercase()),
+                ("regex", source) => Term::NameRegex(Pattern { source: source.to_string(), regex: Regex::new(source).map_err(|err| invalid(err.to_string()))? }),
+                ("owner", owner) => Term::Owner(owner.to_string()),
+                ("assignee", assignee) => Term::Assignee(assignee.to_string()),
+                ("goal", goal) => Term::Goal(goal.to_string()),
+                ("related", id) => Term::Related(id.parse().map_err(|_| invalid(tr("error-filter-task-id", &[("id", &id)])))?),
+                ("stale", after) => Term::Stale(span::parse(after).map_err(invalid)?),
+                ("created-after", after) => Term::CreatedAfter(date::parse(after).map_err(invalid)?),
+                ("created-before", before) => Term::CreatedBefore(date::parse(before).map_err(invalid)?),
+                ("delegated", "") => Term::Delegated(None),
+                ("delegated", person) => Term::Delegated(Some(person.to_string())),
+                ("state", "active") => Term::Active,
+                ("state", "waiting") => Term::Waiting,
+                ("state", "complete") => Term::Complete,
+                ("state", other) => return Err(invalid(tr("error-filter-state", &[("state", &other)]))),
+                (other, _) => return Err(invalid(tr("error-filter-field", &[("field", &other)]))),
+            };
+            terms.push((negated, term));
+        }
+
+        Ok(TaskFilter { terms })
+    }
+
+    /// A filter matching the tasks with the given tag
+    pub fn tagged(tag: &str) -> TaskFilter {
+        TaskFilter { terms: vec![(false, Term::Tag(tag.to_string()))] }
+    }
+
+    /// A filter matching the tasks with any of the given tags
+    pub fn any_tag(tags: &[String]) -> TaskFilter {
+        TaskFilter { terms: vec![(false, Term::AnyTag(tags.to_vec()))] }
+    }
+
+    /// A filter matching the tasks with all of the given tags
+    pub fn all_tags(tags: &[String]) -> TaskFilter {
+        TaskFilter { terms: tags.iter().map(|tag| (false, Term::Tag(tag.clone()))).collect() }
+    }
+
+    /// A filter matching the tasks whose name contains the text, ignoring case
+    pub fn name_containing(part: &str) -> TaskFilter {
+        TaskFilter { terms: vec![(false, Term::Name(part.to_lowercase()))] }
+    }
+
+    /// A filter matching the tasks whose name matches the regular expression
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::filter::TaskFilter;
+    /// assert!(TaskFilter::name_matching(r"^(?i)write \w+").is_ok());
+    /// assert!(TaskFilter::name_matching("(unclosed").is_err());
+    /// ```
+    pub fn name_matching(source: &str) -> ioResult<TaskFilter> {
+        let regex = Regex::new(source)?;
+        Ok(TaskFilter { terms: vec![(false, Term::NameRegex(Pattern { source: source.to_string(), regex }))] })
+    }
+
+    /// A filter matching the tasks created at or after the time
+    pub fn created_after(after: DateTime<Utc>) -> TaskFilter {
+        TaskFilter { terms: vec![(false, Term::CreatedAfter(after))] }
+    }
+
+    /// A filter matching the tasks created before the time
+    pub fn created_before(before: DateTime<Utc>) -> TaskFilter {
+        TaskFilter { terms: vec![(false, Term::CreatedBefore(before))] }
+    }
+
+    /// A filter matching the tasks assigned to the user
+    pub fn assigned(assignee: &str) -> TaskFilter {
+        TaskFilter { terms: vec![(false, Term::Assignee(assignee.to_string()))] }
+    }
+
+    /// A filter matching the tasks delegated to the person, or to anyone
+    pub fn delegated(person: Option<&str>) -> TaskFilter {
+        TaskFilter { terms: vec![(false, Term::Delegated(person.map(str::to_string)))] }
+    }
+
+    /// A filter matching the tasks related to the task with the id
+    pub fn related_to(id: u64) -> TaskFilter {
+        TaskFilter { terms: vec![(false, Term::Related(id))] }
+    }
+
+    /// A filter matching the active tasks untouched for at least the given time
+    pub fn stale(after: Duration) -> TaskFilter {
+        TaskFilter { terms: vec![(false, Term::Stale(after))] }
+    }
+
+    /// A filter matching the active tasks
+    pub fn active() -> TaskFilter {
+        TaskFilter { terms: vec![(false, Term::Active)] }
+    }
+
+    /// A filter matching the complete tasks
+    pub fn complete() -> TaskFilter {
+        TaskFilter { terms: vec![(false, Term::Complete)] }
+    }
+
+    /// A filter matching the tasks matched by both filters
+    pub fn and(mut self, other: TaskFilter) -> TaskFilter {
+        self.terms.extend(other.terms);
+        self
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        self.terms.iter().all(|(negated, term)| term.matches(task) != *negated)
+    }
+
+    /// The positions (from 0) of the tasks matching the filter, in journal order
+    pub fn matching(&self, tasks: &[Task]) -> Vec<usize> {
+        let mut span = trace::span("filter.eval", &[("terms", &self.terms.len()), ("tasks", &tasks.len())]);
+        let matching = tasks.iter()
+                            .enumerate()
+                            .filter(|(_, task)| self.matches(task))
+                            .map(|(i, _)| i)
+                            .collect::<Vec<_>>();
+        span.record("matched", matching.len());
+        matching
+    }
+}
@@ -0,0 +1,57 @@
+// Comment: the bonuses favor what people tend to type, the first letters of words and runs of letters
+// as they appear in the name, over letters scattered across it
+const MATCH: i64 = 1;
+const CONSECUTIVE: i64 = 5;
+const WORD_START: i64 = 3;
+
+/// Scores how well the query matches the text, where every character of the query has to appear in the
+/// text in the same order, ignoring case. The higher the better, None when the text doesn't match at all
+/// # Examples
+/// ```
+/// use rusty_journal_clap::fuzzy;
+/// assert!(fuzzy::score("qrep", "Write the quarterly report").is_some());
+/// assert!(fuzzy::score("report", "Write the quarterly report") > fuzzy::score("rort", "Write the quarterly report"));
+/// assert_eq!(fuzzy::score("milk", "Book the dentist"), None);
+/// assert_eq!(fuzzy::score("", "Book the dentist"), Some(0));
+/// ```
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let mut query = query.chars().flat_map(char::to_lowercase).filter(|c| !c.is_whitespace()).peekable();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+    for c in text.chars().flat_map(char::to_lowercase) {
+        let Some(&wanted) = query.peek() else {
+            break;
+        };
+        let matched = c == wanted;
+        if matched {
+            query.next();
+            score += MATCH;
+            if previous_matched {
+                score += CONSECUTIVE;
+            }
+            if previous.is_none_or(|previous| !previous.is_alphanumeric()) {
+                score += WORD_START;
+            }
+        }
+        previous_matched = matched;
+        previous = Some(c);
+    }
+    query.peek().is_none().then_some(score)
+}
+
+/// The (1-based) indices of the names matching the query, best match first and in journal order among equals
+/// # Examples
+/// ```
+/// use rusty_journal_clap::fuzzy;
+/// assert_eq!(fuzzy::rank("dent", ["Buy milk", "Book the dentist", "Independent review"]), vec![2, 3]);
+/// ```
+pub fn rank<'a>(query: &str, names: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+    let mut ranked = names.into_iter()
+                          .enumerate()
+                          .filter_map(|(i, name)| score(query, name).map(|score| (i + 1, score)))
+                          .collect::<Vec<_>>();
+    // the sort is stable, so equal scores stay in journal order
+    ranked.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    ranked.into_iter().map(|(index, _)| index).collect()
+}
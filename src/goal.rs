@@ -0,0 +1,103 @@
+use std::fmt::Write;
+use std::fs;
+use std::io::{Error, ErrorKind, Result as ioResult};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc, serde::ts_seconds_option};
+use serde::{Deserialize, Serialize};
+use crate::error::{Context, ParseFailure};
+use crate::i18n::tr;
+use crate::paths;
+use crate::stdio;
+use crate::task::Task;
+
+/// A goal grouping the tasks that reference it by name, e.g. a milestone with a target date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Goal {
+    pub name: String,
+    #[serde(default, with = "ts_seconds_option", skip_serializing_if = "Option::is_none")]
+    pub target: Option<DateTime<Utc>>,
+}
+
+/// The goals are kept next to the journal, e.g. `todo.goals.json` for `todo.json`, so the journal
+/// itself stays a plain array of tasks
+/// # Examples
+/// ```
+/// use rusty_journal_clap::goal;
+/// use std::path::{Path, PathBuf};
+/// assert_eq!(goal::goals_path(Path::new("work/todo.json")), PathBuf::from("work/todo.goals.json"));
+/// ```
+pub fn goals_path(journal_path: &Path) -> PathBuf {
+    journal_path.with_extension("goals.json")
+}
+
+/// Reads the goals of the journal, where a missing goals file means no goals
+pub fn load(journal_path: &Path) -> ioResult<Vec<Goal>> {
+    // Comment: a journal piped through stdin has no place next to it for goals
+    if stdio::is_stdio(journal_path) {
+        return Ok(Vec::new());
+    }
+    let path = goals_path(journal_path);
+    let content = match paths::resolve(&path).and_then(fs::read) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_path("reading goals", &path),
+    };
+    serde_json::from_slice(&content).map_err(ParseFailure::of)
+                                    .with_path("reading goals", &path)
+}
+
+/// Creates the goal, or sets the target date of an existing one when given
+pub fn upsert(journal_path: &Path, name: &str, target: Option<DateTime<Utc>>) -> ioResult<()> {
+    if stdio::is_stdio(journal_path) {
+        return Err(Error::new(ErrorKind::InvalidInput, tr("error-goals-stdio", &[])));
+    }
+    let mut goals = load(journal_path)?;
+    match goals.iter_mut().find(|goal| goal.name == name) {
+        Some(goal) => goal.target = target.or(goal.target),
+        None => goals.push(Goal { name: name.to_string(), target }),
+    }
+
+    let path = goals_path(journal_path);
+    Task::_check_writable(&path)?;
+    let content = serde_json::to_string_pretty(&goals).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+    paths::resolve(&path).and_then(|resolved| fs::write(resolved, content))
+                         .with_path("writing goals", &path)
+}
+
+/// Renders every goal with its progress, i.e. how many of its tasks are done, and the days left until
+/// its target date. Goals only referenced by tasks are listed as well, without target date
+/// # Examples
+/// ```
+/// use rusty_journal_clap::goal::{progress, Goal};
+/// use chrono::DateTime;
+/// let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+/// let goals = [Goal { name: "Launch".to_string(), target: None }];
+/// assert_eq!(progress(&goals, &[], now), "Launch                         0/0 tasks done\n");
+/// ```
+pub fn progress(goals: &[Goal], tasks: &[Task], now: DateTime<Utc>) -> String {
+    let mut all = goals.to_vec();
+    for name in tasks.iter().filter_map(|task| task.goal()) {
+        if !all.iter().any(|goal| goal.name == name) {
+            all.push(Goal { name: name.to_string(), target: None });
+        }
+    }
+    if all.is_empty() {
+        return tr("no-goals", &[]) + "\n";
+    }
+
+    let mut report = String::new();
+    for goal in &all {
+        let (done, total) = tasks.iter()
+                                 .filter(|task| task.goal() == Some(&goal.name))
+                                 .fold((0, 0), |(done, total), task| (done + usize::from(task.is_complete()), total + 1));
+        // Comment: writing to a String can't fail
+        let _ = write!(report, "{:<30} {}", goal.name, tr("goal-progress", &[("done", &done), ("total", &total)]));
+        if let Some(target) = goal.target {
+            let left = target - now;
+            let key = if left.num_seconds() < 0 { "overdue-by" } else { "days-left" };
+            let _ = write!(report, ", {}", tr(key, &[("days", &left.num_days().abs())]));
+        }
+        report.push('\n');
+    }
+    report
+}
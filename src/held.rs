@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::task::Task;
+
+// Comment: a macro reads its journal once and holds it here while its steps run, each step loading and saving the
+// content in memory, the way a journal read from stdin is kept, c.f. `stdio`. The journal file is only written
+// once the last step is done, c.f. `Task::hold`
+static HELD: Mutex<Option<Held>> = Mutex::new(None);
+
+/// The content of a held journal, along with the tasks removed from it for the trash
+pub(crate) struct Held {
+    path: PathBuf,
+    pub(crate) content: Vec<u8>,
+    pub(crate) removed: Vec<Task>,
+    pub(crate) written: bool,
+}
+
+/// Whether the journal is the one held in memory by a running macro
+pub(crate) fn is_held(journal_path: &Path) -> bool {
+    HELD.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_ref()
+        .is_some_and(|held| held.path == journal_path)
+}
+
+/// Holds the content of the journal, which the loads and saves of the journal read and replace until `release`
+pub(crate) fn hold(journal_path: &Path, content: Vec<u8>) {
+    *HELD.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Held { path: journal_path.to_path_buf(), content, removed: Vec::new(), written: false });
+}
+
+/// The content of the held journal as the last step saved it
+pub(crate) fn read() -> Vec<u8> {
+    HELD.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_ref()
+        .map(|held| held.content.clone())
+        .unwrap_or_default()
+}
+
+/// Replaces the content of the held journal
+pub(crate) fn write(content: Vec<u8>) {
+    if let Some(held) = HELD.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_mut() {
+        held.content = content;
+        held.written = true;
+    }
+}
+
+/// Keeps the tasks removed from the held journal, which go to the trash when the journal is written
+pub(crate) fn put(removed: Vec<Task>) {
+    if let Some(held) = HELD.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_mut() {
+        held.removed.extend(removed);
+    }
+}
+
+/// Stops holding the journal, handing back what the steps made of it
+pub(crate) fn release() -> Option<Held> {
+    HELD.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take()
+}
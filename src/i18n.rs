@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// Comment: the fluent crates aren't a dependency, so this reads the subset of the Fluent syntax the
+// bundled locales use: `key = pattern` with indented continuation lines, `{ $variable }`, references
+// to other messages as `{ key }`, and selections on a variable. A selection picks the variant whose key
+// is the value itself, then `one` for 1 (the only plural rule English and German need), then the default
+const EN: &str = include_str!("../locales/en.ftl");
+const DE: &str = include_str!("../locales/de.ftl");
+
+/// The languages of the user facing messages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Locale {
+    En,
+    De,
+}
+
+impl Locale {
+    const ALL: [Locale; 2] = [Locale::En, Locale::De];
+
+    /// Reads the language of a locale name like `de`, `de-AT` or `de_DE.UTF-8`, where `C` and `POSIX` are English
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::i18n::Locale;
+    /// assert_eq!(Locale::parse("de_DE.UTF-8"), Some(Locale::De));
+    /// assert_eq!(Locale::parse("C"), Some(Locale::En));
+    /// assert_eq!(Locale::parse("fr_FR"), None);
+    /// ```
+    pub fn parse(name: &str) -> Option<Locale> {
+        let language = name.split(['_', '-', '.', '@']).next().unwrap_or_default().to_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            _ => None,
+        }
+    }
+
+    /// The locale of the environment: the first one set of `LC_ALL`, `LC_MESSAGES` and `LANG` decides, as with gettext
+    pub fn from_env() -> Option<Locale> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+                                         .filter_map(|var| env::var(var).ok())
+                                         .find(|value| !value.is_empty())
+                                         .and_then(|value| Locale::parse(&value))
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Locale::En => EN,
+            Locale::De => DE,
+        }
+    }
+
+    fn messages(self) -> &'static HashMap<String, String> {
+        static MESSAGES: [OnceLock<HashMap<String, String>>; 2] = [OnceLock::new(), OnceLock::new()];
+        MESSAGES[self as usize].get_or_init(|| parse_messages(self.source()))
+    }
+}
+
+static LOCALE: AtomicU8 = AtomicU8::new(Locale::En as u8);
+
+/// Sets the language of the messages for the whole process, English unless set
+pub fn set_locale(locale: Locale) {
+    LOCALE.store(locale as u8, Ordering::Relaxed);
+}
+
+pub fn locale() -> Locale {
+    Locale::ALL[LOCALE.load(Ordering::Relaxed) as usize]
+}
+
+fn parse_messages(source: &str) -> HashMap<String, String> {
+    let mut messages = HashMap::new();
+    let mut current: Option<(String, String)> = None;
+    for line in source.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // Comment: the closing brace of a multiline selection starts at the beginning of the line as well
+        if line.starts_with([' ', '\t']) || line.starts_with('}') {
+            if let Some((_, pattern)) = current.as_mut() {
+                pattern.push('\n');
+                pattern.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((key, pattern)) = current.take() {
+            messages.insert(key, pattern);
+        }
+        if let Some((key, pattern)) = line.split_once('=') {
+            current = Some((key.trim().to_string(), pattern.trim().to_string()));
+        }
+    }
+    messages.extend(current);
+    messages
+}
+
+/// Formats the message in the current locale, falling back to English for a message that isn't translated,
+/// and to the key itself for an unknown message
+/// # Examples
+/// ```
+/// use rusty_journal_clap::i18n::{self, Locale};
+/// assert_eq!(i18n::tr("empty-list", &[]), "Empty to-do list");
+/// assert_eq!(i18n::tr("age", &[("days", &3)]), "3 days old");
+/// assert_eq!(i18n::tr("due-in", &[("days", &1)]), "due in 1 day");
+/// assert_eq!(i18n::tr("no-such-message", &[]), "no-such-message");
+/// ```
+pub fn tr(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    match lookup(key) {
+        Some(pattern) => format_pattern(pattern, args),
+        None => key.to_string(),
+    }
+}
+
+/// The pattern of the message in the current locale or in English, if there is one
+pub fn lookup(key: &str) -> Option<&'static str> {
+    locale().messages()
+            .get(key)
+            .or_else(|| Locale::En.messages().get(key))
+            .map(String::as_str)
+}
+
+/// The English messages the locale has no translation for, to keep the locales complete
+/// # Examples
+/// ```
+/// use rusty_journal_clap::i18n::{self, Locale};
+/// assert!(i18n::untranslated(Locale::De).is_empty());
+/// ```
+pub fn untranslated(locale: Locale) -> Vec<&'static str> {
+    let mut missing = Locale::En.messages()
+                                .keys()
+                                .filter(|key| !locale.messages().contains_key(*key))
+                                .map(String::as_str)
+                                .collect::<Vec<_>>();
+    missing.sort();
+    missing
+}
+
+fn format_pattern(pattern: &str, args: &[(&str, &dyn Display)]) -> String {
+    // plain text is by far the most common, e.g. the labels of every listed task
+    if !pattern.contains('{') {
+        return pattern.to_string();
+    }
+
+    let mut formatted = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(open) = rest.find('{') {
+        formatted.push_str(&rest[..open]);
+        let mut depth = 0;
+        let close = rest[open..].char_indices()
+                                .find(|&(_, c)| {
+                                    match c {
+                                        '{' => depth += 1,
+                                        '}' => depth -= 1,
+                                        _ => {}
+                                    }
+                                    depth == 0
+                                })
+                                .map(|(i, _)| open + i);
+        let Some(close) = close else {
+            // an unbalanced brace is left as it is
+            formatted.push_str(&rest[open..]);
+            return formatted;
+        };
+        formatted.push_str(&placeable(rest[open + 1..close].trim(), args));
+        rest = &rest[close + 1..];
+    }
+    formatted.push_str(rest);
+    formatted
+}
+
+fn arg(name: &str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter()
+        .find(|(arg_name, _)| *arg_name == name)
+        .map(|(_, value)| value.to_string())
+        .unwrap_or_else(|| format!("{{${}}}", name))
+}
+
+fn placeable(expression: &str, args: &[(&str, &dyn Display)]) -> String {
+    if let Some((selector, variants)) = expression.split_once("->") {
+        let value = arg(selector.trim().trim_start_matches('$'), args);
+        let variants = variants.lines()
+                               .map(str::trim)
+                               .filter_map(|line| {
+                                   let default = line.starts_with('*');
+                                   let (key, pattern) = line.trim_start_matches('*').strip_prefix('[')?.split_once(']')?;
+                                   Some((default, key.trim(), pattern.trim()))
+                               })
+                               .collect::<Vec<_>>();
+        let chosen = variants.iter().find(|(_, key, _)| *key == value)
+                             .or_else(|| variants.iter().find(|(_, key, _)| *key == "one" && value == "1"))
+                             .or_else(|| variants.iter().find(|(default, _, _)| *default));
+        return chosen.map(|(_, _, pattern)| format_pattern(pattern, args)).unwrap_or_default();
+    }
+    if let Some(name) = expression.strip_prefix('$') {
+        return arg(name, args);
+    }
+    if let Some(literal) = expression.strip_prefix('"').and_then(|literal| literal.strip_suffix('"')) {
+        return literal.to_string();
+    }
+    // a reference to another message, formatted with the same variables
+    tr(expression, args)
+}
@@ -0,0 +1,199 @@
+use std::collections::HashSet;
+use std::io::Result as ioResult;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use crate::{clock, error::ParseFailure, import, normalize::Normalizer, output, task::Task};
+
+/// A content line of an iCalendar file, e.g. `DTSTART;TZID=Europe/Paris:20231117T090000`
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str, number: usize) -> ioResult<Property> {
+        // Comment: the value may contain colons (e.g. in URLs), while parameter values may only when quoted
+        let mut in_quotes = false;
+        let split = line.char_indices()
+                        .find(|&(_, c)| {
+                            if c == '"' {
+                                in_quotes = !in_quotes;
+                            }
+                            c == ':' && !in_quotes
+                        })
+                        .map(|(i, _)| i)
+                        .ok_or_else(|| ParseFailure::of(format!("Invalid iCalendar line {}, expected <name>:<value>", number)))?;
+        let (head, value) = (&line[..split], &line[split + 1..]);
+
+        let mut parts = head.split(';');
+        let name = parts.next().unwrap_or_default().trim().to_uppercase();
+        let params = parts.filter_map(|param| param.split_once('='))
+                          .map(|(key, value)| (key.trim().to_uppercase(), value.trim_matches('"').to_string()))
+                          .collect();
+        Ok(Property { name, params, value: value.to_string() })
+    }
+
+    fn param(&self, key: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// The value of a DATE or DATE-TIME property. Times in UTC end with a Z, others are read as local time:
+    /// without a time zone database the TZID parameter can't be honoured, which is right for the usual case
+    /// of a calendar exported in the user's own zone. A date without time is the start of that day
+    fn timestamp(&self) -> ioResult<DateTime<Utc>> {
+        let invalid = || ParseFailure::of(format!("Invalid date '{}' in {}", self.value, self.name));
+        let value = self.value.trim();
+        if self.param("VALUE") == Some("DATE") || value.len() == 8 {
+            let date = NaiveDate::parse_from_str(value, "%Y%m%d").map_err(|_| invalid())?;
+            return output::from_local(date.and_hms_opt(0, 0, 0).unwrap()).ok_or_else(invalid);
+        }
+        match value.strip_suffix('Z') {
+            Some(utc) => NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map(|naive| naive.and_utc())
+                                                                             .map_err(|_| invalid()),
+            None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()
+                                                                         .and_then(output::from_local)
+                                                                         .ok_or_else(invalid),
+        }
+    }
+}
+
+/// Undoes the escaping of TEXT values: `\\`, `\;`, `\,` and `\n`
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Joins folded lines, where a line starting with a space or a tab continues the previous one
+fn unfold(content: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continued), Some((_, previous))) => previous.push_str(continued),
+            _ => lines.push((i + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+/// Converts the events (VEVENT) and to-dos (VTODO) of an iCalendar file (RFC 5545) into tasks.
+/// The SUMMARY is the name, CATEGORIES are the tags, and the DUE of a to-do or the DTSTART of an
+/// event is the due date. A to-do with STATUS:COMPLETED is imported complete.
+/// The URL is kept as the task's link, and the UID in the task's extra fields, so importing the same calendar again skips
+/// the tasks already in the journal. Other components, e.g. alarms or time zones, are ignored
+/// # Examples
+/// ```
+/// use rusty_journal_clap::{ics, normalize::{Normalizer, Rules}};
+/// let normalizer = Normalizer::new(&Rules::default()).unwrap();
+/// let calendar = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:1@example.com\r\nSUMMARY:File taxes\r\nDUE:20240415T170000Z\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+/// let tasks = ics::from_ics(calendar, &normalizer).unwrap();
+/// assert_eq!(tasks[0].name(), "File taxes");
+/// assert_eq!(tasks[0].due().unwrap().to_rfc3339(), "2024-04-15T17:00:00+00:00");
+/// assert_eq!(tasks[0].extra("uid"), Some("1@example.com"));
+/// ```
+pub fn from_ics(content: &str, normalizer: &Normalizer) -> ioResult<Vec<Task>> {
+    let now = clock::now();
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+
+    let mut tasks = Vec::new();
+    let mut seen_uids = HashSet::new();
+    // the properties of the VEVENT/VTODO being read, and how deep inside it the current line is
+    let mut component: Option<(String, Vec<Property>)> = None;
+    let mut depth = 0;
+
+    for (number, line) in unfold(content) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let property = Property::parse(&line, number)?;
+        match (property.name.as_str(), &mut component) {
+            ("BEGIN", None) if ["VEVENT", "VTODO"].contains(&property.value.trim()) => {
+                component = Some((property.value.trim().to_string(), Vec::new()));
+                depth = 0;
+            }
+            ("BEGIN", Some(_)) => depth += 1,
+            ("END", Some(_)) if depth > 0 => depth -= 1,
+            ("END", Some((kind, _))) if property.value.trim() == kind => {
+                let (kind, properties) = component.take().unwrap();
+                if let Some(task) = to_task(&kind, &properties, normalizer, now)? {
+                    // Comment: the occurrences of a recurring event overridden with a RECURRENCE-ID share the UID,
+                    // only the first one becomes a task
+                    if task.extra(import::UID_FIELD).is_none_or(|uid| seen_uids.insert(uid.to_string())) {
+                        tasks.push(task);
+                    }
+                }
+            }
+            (_, Some((_, properties))) if depth == 0 => properties.push(property),
+            _ => {}
+        }
+    }
+
+    match component {
+        Some((kind, _)) => Err(ParseFailure::of(format!("Unterminated {} at the end of the iCalendar file", kind))),
+        None => Ok(tasks),
+    }
+}
+
+fn to_task(kind: &str, properties: &[Property], normalizer: &Normalizer, now: DateTime<Utc>) -> ioResult<Option<Task>> {
+    let find = |name: &str| properties.iter().find(|property| property.name == name);
+
+    let name = normalizer.name(&unescape(find("SUMMARY").map(|summary| summary.value.as_str()).unwrap_or_default()));
+    if name.is_empty() {
+        return Ok(None);
+    }
+    // Comment: CATEGORIES may be repeated, and each holds a comma separated list
+    let tags = properties.iter()
+                         .filter(|property| property.name == "CATEGORIES")
+                         .flat_map(|property| split_list(&property.value))
+                         .collect::<Vec<_>>();
+
+    let mut task = Task::new(name, normalizer.tags(Some(tags)), now);
+    let due = if kind == "VTODO" { find("DUE") } else { find("DTSTART") };
+    if let Some(due) = due {
+        task.set_due(Some(due.timestamp()?));
+    }
+    if let Some(uid) = find("UID").map(|uid| uid.value.trim()).filter(|uid| !uid.is_empty()) {
+        task.set_extra(import::UID_FIELD, uid.to_string());
+    }
+    if let Some(link) = find("URL").map(|link| link.value.trim()).filter(|link| !link.is_empty()) {
+        task.set_extra(import::LINK_FIELD, link.to_string());
+    }
+    if kind == "VTODO" && find("STATUS").is_some_and(|status| status.value.trim().eq_ignore_ascii_case("COMPLETED")) {
+        let completed_at = match find("COMPLETED") {
+            Some(completed) => completed.timestamp()?,
+            None => now,
+        };
+        task.completed(completed_at);
+    }
+    Ok(Some(task))
+}
+
+/// Splits a comma separated TEXT list, where an escaped comma is part of the item
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            ',' if !escaped => items.push(std::mem::take(&mut item)),
+            _ => item.push(c),
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    items.push(item);
+    items.iter()
+         .map(|item| unescape(item.trim()))
+         .filter(|item| !item.is_empty())
+         .collect()
+}
@@ -0,0 +1,106 @@
+use std::io::{Error, ErrorKind, Result as ioResult};
+use crate::{clock, csv, normalize::Normalizer, task::Task};
+use crate::i18n::tr;
+
+/// The extra field holding the identifier a task had in the source it was imported from,
+/// so that importing the same file again doesn't duplicate it
+pub const UID_FIELD: &str = "uid";
+
+/// The extra field holding a link to the task's source, e.g. the URL of a calendar event, which `open` falls back to
+pub const LINK_FIELD: &str = "link";
+
+/// Which column of the imported file holds which task field, as given to `--map`
+/// # Examples
+/// ```
+/// use rusty_journal_clap::import::ColumnMap;
+/// assert!(ColumnMap::parse("name=Title,tags=Labels").is_ok());
+/// assert!(ColumnMap::parse("title=Title").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMap {
+    name: String,
+    tags: Option<String>,
+    state: Option<String>,
+}
+
+impl Default for ColumnMap {
+    fn default() -> Self {
+        ColumnMap { name: "name".to_string(), tags: None, state: None }
+    }
+}
+
+impl ColumnMap {
+    /// Parses a comma separated list of `<field>=<column>` where the field is one of name, tags or state
+    pub fn parse(spec: &str) -> ioResult<ColumnMap> {
+        let mut map = ColumnMap::default();
+        for pair in spec.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let (field, column) = pair.split_once('=')
+                                      .ok_or_else(|| Error::new(ErrorKind::InvalidInput, tr("error-column-mapping", &[("pair", &pair)])))?;
+            let column = column.trim().to_string();
+            match field.trim() {
+                "name" => map.name = column,
+                "tags" => map.tags = Some(column),
+                "state" => map.state = Some(column),
+                other => return Err(Error::new(ErrorKind::InvalidInput, tr("error-mapping-field", &[("field", &other)]))),
+            }
+        }
+        Ok(map)
+    }
+}
+
+fn find_column(header: &[String], column: &str) -> ioResult<usize> {
+    header.iter()
+          .position(|h| h.trim().eq_ignore_ascii_case(column))
+          .ok_or_else(|| Error::new(ErrorKind::InvalidInput,
+                                    tr("error-missing-column", &[("column", &column), ("columns", &header.join(", "))])))
+}
+
+/// Values of the state column which mean the task is already complete, any other value means active
+fn is_complete_state(value: &str) -> bool {
+    ["complete", "completed", "done", "closed", "x", "yes", "true", "1"].contains(&value.trim().to_lowercase().as_str())
+}
+
+/// Converts CSV rows into tasks according to the column mapping. The first row is the header,
+/// and when no tags/state column is mapped explicitly, columns named `tags`/`state` are used if present.
+/// Several tags in one cell are separated by commas or semicolons.
+pub fn from_csv(content: &str, map: &ColumnMap, normalizer: &Normalizer) -> ioResult<Vec<Task>> {
+    let now = clock::now();
+    let mut records = csv::parse(content)?.into_iter();
+    let header = match records.next() {
+        Some(header) => header,
+        None => return Ok(Vec::new()),
+    };
+
+    let name_column = find_column(&header, &map.name)?;
+    let tags_column = match &map.tags {
+        Some(column) => Some(find_column(&header, column)?),
+        None => find_column(&header, "tags").ok(),
+    };
+    let state_column = match &map.state {
+        Some(column) => Some(find_column(&header, column)?),
+        None => find_column(&header, "state").ok(),
+    };
+
+    let mut tasks = Vec::new();
+    for record in records {
+        let cell = |column: Option<usize>| column.and_then(|c| record.get(c)).map(String::as_str).unwrap_or_default();
+
+        let name = normalizer.name(cell(Some(name_column)));
+        if name.is_empty() {
+            continue;
+        }
+        let tags = cell(tags_column).split([',', ';'])
+                                    .map(str::trim)
+                                    .filter(|tag| !tag.is_empty())
+                                    .map(str::to_string)
+                                    .collect::<Vec<_>>();
+
+        let mut task = Task::new(name, normalizer.tags(Some(tags)), now);
+        if is_complete_state(cell(state_column)) {
+            task.completed(now);
+        }
+        tasks.push(task);
+    }
+
+    Ok(tasks)
+}
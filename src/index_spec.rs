@@ -0,0 +1,69 @@
+use std::io::{Error, ErrorKind, Result as ioResult};
+use crate::i18n::tr;
+
+const MAX_RANGE: usize = 1_000_000;
+
+/// A selection of task indices as typed on the command line: a comma separated list
+/// of single indices and inclusive ranges, e.g. `1-5,8,11`. Indices start at 1.
+/// The subcommands doing the same to every task they are given take one, while those placing a task relative to
+/// another one, `move-to`, `swap`, `relate` and `add --parent`, take single indices.
+/// # Examples
+/// ```
+/// use rusty_journal_clap::index_spec::IndexSpec;
+/// assert_eq!(IndexSpec::parse("8,1-3,2").unwrap().indices(), vec![1, 2, 3, 8]);
+/// assert!(IndexSpec::parse("5-1").is_err());
+/// assert!(IndexSpec::parse("0").is_err());
+/// assert!(IndexSpec::parse("1,,2").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexSpec {
+    ranges: Vec<(usize, usize)>,
+}
+
+impl IndexSpec {
+    pub fn parse(spec: &str) -> ioResult<IndexSpec> {
+        let invalid = |msg: String| Error::new(ErrorKind::InvalidInput, tr("error-invalid-index", &[("spec", &spec), ("error", &msg)]));
+        let index = |part: &str| -> ioResult<usize> {
+            match part.trim().parse::<usize>() {
+                Ok(0) => Err(invalid(tr("error-index-zero", &[]))),
+                Ok(index) => Ok(index),
+                Err(_) => Err(invalid(tr("error-not-an-index", &[("part", &part.trim())]))),
+            }
+        };
+
+        let mut ranges = Vec::new();
+        for part in spec.split(',') {
+            let range = match part.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (index(start)?, index(end)?);
+                    if start > end {
+                        return Err(invalid(tr("error-range-backwards", &[("start", &start), ("end", &end)])));
+                    }
+                    // no journal comes close to this, and it keeps a typo from allocating a huge selection
+                    if end - start >= MAX_RANGE {
+                        return Err(invalid(tr("error-range-too-large", &[("start", &start), ("end", &end)])));
+                    }
+                    (start, end)
+                }
+                None => {
+                    let single = index(part)?;
+                    (single, single)
+                }
+            };
+            ranges.push(range);
+        }
+
+        Ok(IndexSpec { ranges })
+    }
+
+    /// The selected indices in ascending order, without duplicates
+    pub fn indices(&self) -> Vec<usize> {
+        let mut indices = self.ranges
+                              .iter()
+                              .flat_map(|&(start, end)| start..=end)
+                              .collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+}
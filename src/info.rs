@@ -0,0 +1,77 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+use crate::compress::Compression;
+use crate::i18n::tr;
+use crate::output;
+use crate::schema;
+use crate::storage::Backend;
+use crate::task::Task;
+
+/// What is known of a journal file besides its tasks
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalFile {
+    // the resolved path, None for a journal piped through stdin
+    pub path: Option<PathBuf>,
+    pub backend: Backend,
+    pub compression: Option<Compression>,
+    // in bytes, None when the file doesn't exist yet
+    pub size: Option<u64>,
+    // the socket of the daemon serving the journal, if one is running
+    pub daemon: Option<PathBuf>,
+    // whether another process holds the lock of the journal, to write it
+    pub locked: bool,
+}
+
+/// Renders an overview of the journal: where it is and how it is stored, how many tasks are in each state,
+/// and when the oldest and newest were created, to check on a journal or to paste into a bug report
+/// # Examples
+/// ```
+/// use rusty_journal_clap::{info::{info, JournalFile}, storage::Backend};
+/// use std::path::PathBuf;
+/// let file = JournalFile { path: Some(PathBuf::from("/home/alice/todo.json")), backend: Backend::Json, compression: None, size: None, daemon: None, locked: false };
+/// let overview = info(&file, &[]);
+/// assert!(overview.starts_with("Journal:     /home/alice/todo.json\nFormat:      json\n"));
+/// assert!(overview.contains("Tasks:       0 (0 active, 0 waiting, 0 complete)\n"));
+/// ```
+pub fn info(file: &JournalFile, tasks: &[Task]) -> String {
+    let format = "%d/%m/%Y %H:%M";
+    let count = |state: &str| tasks.iter().filter(|task| task.state_name() == state).count();
+
+    let mut report = String::new();
+    let mut field = |label: &str, value: String| {
+        // Comment: writing to a String can't fail
+        let _ = writeln!(report, "{:<12} {}", tr(label, &[]), value);
+    };
+    field("info-journal", match &file.path {
+        Some(path) => path.display().to_string(),
+        None => tr("info-stdin", &[]),
+    });
+    field("info-format", match file.compression {
+        Some(Compression::Gzip) => format!("{}, gzip", file.backend.name()),
+        Some(Compression::Zstd) => format!("{}, zstd", file.backend.name()),
+        None => file.backend.name().to_string(),
+    });
+    field("info-schema", schema::SCHEMA_ID.to_string());
+    if file.path.is_some() {
+        field("info-size", match file.size {
+            Some(bytes) => tr("info-bytes", &[("bytes", &bytes)]),
+            None => tr("info-missing", &[]),
+        });
+    }
+    field("info-tasks", tr("info-counts", &[("total", &tasks.len()),
+                                            ("active", &count("active")),
+                                            ("waiting", &count("waiting")),
+                                            ("complete", &count("complete"))]));
+    if let Some(oldest) = tasks.iter().map(Task::created_at).min() {
+        field("info-oldest", output::format_time(oldest, format));
+    }
+    if let Some(newest) = tasks.iter().map(Task::created_at).max() {
+        field("info-newest", output::format_time(newest, format));
+    }
+    field("info-lock", match &file.daemon {
+        Some(socket) => tr("info-daemon", &[("path", &socket.display())]),
+        None if file.locked => tr("info-writing", &[]),
+        None => tr("info-unlocked", &[]),
+    });
+    report
+}
@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+use crate::clock;
+use crate::error::JournalResult;
+use crate::task::{Task, TaskOptions};
+
+/// A journal loaded in memory, to make any number of changes with a single read and a single write.
+///
+/// The changes are the ones of the commands of `Task`, e.g. `complete_task` does what `done` does. But the commands
+/// each read and write the journal file, holding its lock in between, and a `Journal` doesn't: another process
+/// writing the file between `load` and `save` has its changes overwritten
+/// # Examples
+/// ```
+/// use rusty_journal_clap::journal::Journal;
+/// use rusty_journal_clap::test_util::TempJournal;
+/// let temp = TempJournal::seeded().unwrap();
+/// let mut journal = Journal::load(temp.path()).unwrap();
+/// let first = journal.add_task("read".to_string(), None);
+/// journal.add_task("write".to_string(), Some(vec!["work".to_string()]));
+/// journal.complete_task(first).unwrap();
+/// journal.save().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    tasks: Vec<Task>,
+    // the removed tasks, which go to the trash when the journal is saved
+    removed: Vec<Task>,
+}
+
+impl Journal {
+    /// Reads all the tasks of the journal, creating an empty journal file if there isn't one yet
+    pub fn load(path: PathBuf) -> JournalResult<Journal> {
+        let tasks = Task::_load_tasks(&path)?;
+        Ok(Journal { path, tasks, removed: Vec::new() })
+    }
+
+    /// The file the journal was loaded from, and is saved to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The tasks in journal order, the task at index 1 first
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Task> {
+        self.tasks.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// The task at the index, which goes from 1 to the number of tasks as on the command line
+    pub fn get(&self, index: usize) -> Option<&Task> {
+        index.checked_sub(1).and_then(|i| self.tasks.get(i))
+    }
+
+    /// Adds a new task at the end of the journal, giving it a fresh id, and returns its index
+    pub fn add_task(&mut self, name: String, tags: Option<Vec<String>>) -> usize {
+        Task::_push(&mut self.tasks, Task::new(name, tags, clock::now()));
+        self.tasks.len()
+    }
+
+    /// Adds a new task like `add_task`, with the given priority, due date, owner, assignee and parent task.
+    /// Fails when the parent index is out of range
+    pub fn add_task_with(&mut self, name: String, tags: Option<Vec<String>>, options: &TaskOptions) -> JournalResult<usize> {
+        Ok(Task::_add(&mut self.tasks, name, tags, options, clock::now())?)
+    }
+
+    /// Removes the task at the index like the `remove` command, along with its subtasks when `remove` cascades.
+    /// The removed tasks go to the trash when the journal is saved. Returns how many tasks were removed
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::journal::Journal;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let temp = TempJournal::seeded().unwrap();
+    /// let mut journal = Journal::load(temp.path()).unwrap();
+    /// let index = journal.add_task("play".to_string(), None);
+    /// assert_eq!(journal.remove_task(index).unwrap(), 1);
+    /// assert!(journal.remove_task(0).is_err());
+    /// ```
+    pub fn remove_task(&mut self, index: usize) -> JournalResult<usize> {
+        let removed = Task::_remove_at(&mut self.tasks, &[index])?;
+        let count = removed.len();
+        self.removed.extend(removed);
+        Ok(count)
+    }
+
+    /// Marks the task at the index as complete like the `done` command, along with its subtasks when `done` cascades,
+    /// adding the next occurrence of a recurring task at the end of the journal
+    pub fn complete_task(&mut self, index: usize) -> JournalResult<()> {
+        Ok(Task::_complete_at(&mut self.tasks, &[index], clock::now())?)
+    }
+
+    /// Removes all the tasks
+    pub fn clear(&mut self) {
+        self.tasks.clear();
+    }
+
+    /// Writes the tasks back to the journal file, and the tasks removed since the last save to its trash
+    pub fn save(&mut self) -> JournalResult<()> {
+        let path = self.path.clone();
+        self.save_as(path)
+    }
+
+    /// Writes the tasks to another journal file, which the journal is saved to from then on.
+    /// The format and compression are the ones of the new file
+    pub fn save_as(&mut self, path: PathBuf) -> JournalResult<()> {
+        Task::_save_tasks_removing(&path, &self.tasks, std::mem::take(&mut self.removed))?;
+        self.path = path;
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a Journal {
+    type Item = &'a Task;
+    type IntoIter = std::slice::Iter<'a, Task>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tasks.iter()
+    }
+}
+
+impl IntoIterator for Journal {
+    type Item = Task;
+    type IntoIter = std::vec::IntoIter<Task>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tasks.into_iter()
+    }
+}
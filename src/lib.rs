@@ -1,93 +1,508 @@
-use std::{error, path::PathBuf};
-use clap::{value_parser, Arg, ArgAction, Command};
-mod cli;
+use std::{env, fs, io::{self, IsTerminal}, path::{Path, PathBuf}};
+use std::error::Error;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use clap::parser::ValueSource;
+use cli::{Action, Cli, TrashAction};
+use error::Context;
+pub mod cli;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+pub mod clock;
+pub mod color;
+pub mod completions;
+pub mod compress;
+pub mod config;
+pub mod csv;
+#[cfg(unix)]
+pub mod daemon;
+pub mod date;
+pub mod error;
+pub mod export;
+pub mod filter;
+pub mod fuzzy;
+pub mod goal;
+pub mod held;
+pub mod i18n;
+pub mod ics;
+pub mod import;
+pub mod index_spec;
+pub mod info;
+pub mod journal;
+pub mod normalize;
+pub mod notify;
+pub mod output;
+pub mod paths;
+pub mod picker;
+pub mod plan;
+pub mod prompt;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod recur;
+pub mod redact;
+pub mod regex;
+pub mod reminders;
+pub mod rpc;
+pub mod schema;
+pub mod score;
+pub mod span;
+pub mod stats;
+pub mod stdio;
+pub mod storage;
+pub mod summary;
 pub mod task;
+pub mod template;
+pub mod toml;
+pub mod trace;
+pub mod trash;
+pub mod undo;
+pub mod url;
+pub mod watch;
+#[cfg(feature = "test_util")]
+pub mod test_util;
 
-pub fn run() -> Result<(), Box<dyn error::Error>> {
-    let arg_matches = Command::new("My Program")
-    .author("Me, me@mail.com")
-    .version("1.0.2")
-    .about("Explains in brief what the program does")
-    .arg(
-        Arg::new("journal_file")
-        .long("journal_file")
-        // used to user-facing help msg: https://docs.rs/clap/latest/clap/struct.Arg.html#method.value_name
-        .value_name("FILE")
-        .default_value("todo.json")
-        // used to type-check user input: https://docs.rs/clap/latest/clap/struct.Arg.html#method.value_parser
-        .value_parser(value_parser!(PathBuf))
-    )
-    .subcommand(
-Command::new("add")
-            .arg(Arg::new("task")
-                    .long("task")
-                    .required(true)
-            )
-            .arg(Arg::new("tag")
-                    .action(ArgAction::Append)
-                    .long("tag")
-            )
-    )
-    .subcommand(
-Command::new("remove")
-            .arg(Arg::new("index")
-                    .required(true)
-                    .value_parser(value_parser!(usize))
-            )
-    )
-    .subcommand(
-Command::new("list")
-            .arg(Arg::new("tag")
-                    .value_parser(value_parser!(String))
-                    .long("tag")                    
-            )    
-    )    
-    .after_help("Longer explanation to appear after the options when \
-                 displaying the help information from --help or -h")
-    .get_matches();
-
-    let journal_file = arg_matches.get_one::<PathBuf>("journal_file").unwrap().to_owned();
-    
-    // Comment: the following block of code works by destructuring the subcommand of the arg_matches struct
-    // Currently, in every destructuring instance, the desirable arg is extracted from the args_matches struct
-    // with to_owned() call to create an owned instance. There could be more fine-grained case-by-case consideration
-    // since some desirable arg are needed as owned instance to further be consumed e.g. adding new tasks would need to consume
-    // owned attributes in the creation of new owned Task instance anyway
-    // However, ops like removal by index or listing by tag DOES NOT SEEM TO neccessarily need to own the arg
-    // since they just need to reference the info from the arg to complete their jobs. The removal of to_owned call
-    // in this cases SEEM TO be benefitial w.r.t performance
-    match arg_matches.subcommand() {
-        Some(("list", list_args)) => {
-            let list_tag = list_args.get_one::<String>("tag")
-                                                     .to_owned();
-            task::Task::list(journal_file, list_tag)?
+
+/// Runs the command line. The process is expected to exit with `error::exit_code` of the error, if any
+pub fn run() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    output::set_json_errors(cli.error_format == "json");
+    output::set_deterministic(cli.deterministic || output::deterministic_from_env());
+    output::set_format(cli.output);
+
+    if let Some(log_file) = &cli.log_file {
+        trace::to_file(log_file)?;
+    } else if cli.trace {
+        trace::to_stderr();
+    }
+
+    let mut config = config::Config::load()?;
+    let journal_file = paths::journal(cli.journal_file.as_deref(), env::var_os(paths::JOURNAL_ENV).as_deref(), config.journal.as_deref());
+    i18n::set_locale(config.locale()?);
+    task::set_read_only(cli.read_only || config.read_only);
+    storage::set_backend(cli.journal_format);
+    trash::set_retention(config.trash_retention()?);
+    undo::set_depth(config.undo_depth.unwrap_or(10));
+    task::set_cascade(config.cascade.unwrap_or(true));
+    undo::set_command(Some(cli.action.name()));
+    if cli.no_notify {
+        config.notify = false;
+    }
+
+    let command_name = cli.action.name().to_string();
+    let result = dispatch(journal_file, cli.action, &config)
+                     .map_err(|err| error::CommandError::new(&command_name, err).into());
+    // Comment: a failed command leaves stdout empty rather than passing on a half-changed journal
+    let result = result.and_then(|()| Ok(stdio::flush()?));
+    if let Err(err) = &result {
+        trace::event("error", &[("message", err)]);
+    }
+    trace::flush();
+    result
+}
+
+/// Runs the steps of a macro from the user config in sequence on the journal held in memory, c.f. `Task::hold`,
+/// stopping at the first failing step, which leaves the journal as it was
+fn run_macro(journal_file: PathBuf, name: &str, config: &config::Config) -> Result<(), Box<dyn Error>> {
+    // Comment: every step is parsed before the first one runs, so a mistake in the last step doesn't leave the others half done
+    let mut actions = Vec::new();
+    for step in &config.macros[name] {
+        let step_args = config::split_command_line(step)?;
+        let matches = Cli::command().try_get_matches_from(std::iter::once(name.to_string()).chain(step_args))?;
+        // the options before the subcommand, e.g. --journal_file or --read-only, are those of the whole run
+        if let Some(flag) = Cli::command().get_arguments().find(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)) {
+            let flag = format!("--{}", flag.get_long().unwrap_or_default());
+            return Err(i18n::tr("error-macro-option", &[("name", &name), ("step", step), ("option", &flag)]).into());
+        }
+
+        match Cli::from_arg_matches(&matches)?.action {
+            Action::Macro(step_name) if config.macros.contains_key(&step_name[0]) => {
+                return Err(i18n::tr("error-macro-nested", &[("name", &name), ("step", &step_name[0])]).into());
+            }
+            action @ (Action::Undo { list: false, .. } | Action::Restore { .. }) => {
+                return Err(i18n::tr("error-macro-step", &[("name", &name), ("command", &action.name())]).into());
+            }
+            action => actions.push(action),
+        }
+    }
+
+    task::Task::hold(&journal_file, || actions.into_iter().try_for_each(|action| dispatch(journal_file.clone(), action, config)))
+}
+
+/// The subcommands that change the journal, unless run with `--dry-run`
+const MUTATING_COMMANDS: [&str; 20] = ["add", "remove", "done", "move-to", "bump", "sink", "renumber", "annotate", "wait", "delegate", "relate", "goal", "estimate", "swap", "edit", "append", "import", "restore", "undo", "tick"];
+
+/// The subcommands that list or report the tasks, which check the journal for tasks fallen overdue to notify of
+const OVERDUE_CHECKING_COMMANDS: [&str; 5] = ["list", "next", "search", "plan", "report"];
+
+/// Runs the command through the daemon of the journal, if one is running and the command is one of the simple
+/// forms it serves, e.g. `done 3` but not `done 1-5`. Returns whether the daemon ran it
+#[cfg(unix)]
+fn proxy(journal_file: &Path, action: &Action, config: &config::Config) -> Result<bool, Box<dyn Error>> {
+    let single_index = |index: &index_spec::IndexSpec| match index.indices()[..] {
+        [index] => Some(index),
+        _ => None,
+    };
+
+    // Comment: the patterns spell out every option, so that an option the daemon knows nothing of keeps the command local
+    let (method, params) = match action {
+        Action::Count { tag } => ("count", serde_json::json!({"tag": tag})),
+        Action::Add { task: Some(task), tag, priority: None, due: None, assign: None, goal: None, estimate: None, repeat: None, parent: None, from_file: None,
+                      #[cfg(feature = "clipboard")] clipboard: false } => {
+            ("add", serde_json::json!({"name": task, "tags": tag, "owner": config.user()}))
+        }
+        // notifying of a completion needs the journal before and after it, so such a `done` runs locally
+        Action::Done { index: Some(index), tag: None, dry_run: false, yes: false } if single_index(index).is_some() && !config.notify => {
+            ("complete", serde_json::json!({"index": single_index(index)}))
+        }
+        Action::Remove { index: Some(index), r#where: None, tag: None, dry_run: false, yes: false } if single_index(index).is_some() => {
+            ("remove", serde_json::json!({"index": single_index(index)}))
+        }
+        _ => return Ok(false),
+    };
+    let Some(mut client) = daemon::Client::connect(journal_file) else {
+        return Ok(false);
+    };
+    let _span = trace::span("daemon.call", &[("method", &method)]);
+    let result = client.call(method, params).with_path("calling daemon", &daemon::socket_path(journal_file))?;
+    if method == "count" {
+        println!("{}", result);
+    }
+    Ok(true)
+}
+
+fn dispatch(journal_file: PathBuf, action: Action, config: &config::Config) -> Result<(), Box<dyn Error>> {
+    let _span = trace::span("command", &[("name", &action.name())]);
+
+    // Comment: failing before the journal is opened, rather than when it is written, keeps a read-only
+    // run from doing half of the work, e.g. printing what it would remove
+    let mutating = MUTATING_COMMANDS.contains(&action.name()) && !action.is_dry_run();
+    if task::is_read_only() && mutating {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, i18n::tr("error-read-only-command", &[("command", &action.name())])).into());
+    }
+    // stdout carries the changed journal, so the messages for the user go to stderr
+    if stdio::is_stdio(&journal_file) && mutating {
+        stdio::set_messages_to_stderr(true);
+    }
+
+    #[cfg(unix)]
+    if !stdio::is_stdio(&journal_file) && !held::is_held(&journal_file) && proxy(&journal_file, &action, config)? {
+        return Ok(());
+    }
+
+    // Comment: stdin can be read for the journal only once, so a journal on stdio isn't checked for notifications
+    let notify = config.notify && !stdio::is_stdio(&journal_file);
+    if notify && OVERDUE_CHECKING_COMMANDS.contains(&action.name()) {
+        notify::overdue(&mut notify::Desktop, &journal_file, clock::now())?;
+    }
+
+    match action {
+        Action::List { tag, assignee, delegated, state, related_to, stale, r#where, sort, show_urgency, tree, waiting, watch, redact,
+                       #[cfg(feature = "scripting")] script } => {
+            let mut list_filter = tag.map(|tag| filter::TaskFilter::tagged(&tag))
+                                     .unwrap_or_default();
+            if let Some(assignee) = assignee {
+                let assignee = match assignee.as_str() {
+                    "" => config.user().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, i18n::tr("error-no-current-user", &[])))?,
+                    _ => assignee,
+                };
+                list_filter = list_filter.and(filter::TaskFilter::assigned(&assignee));
+            }
+            if let Some(person) = delegated {
+                let person = Some(person.as_str()).filter(|person| !person.is_empty());
+                list_filter = list_filter.and(filter::TaskFilter::delegated(person));
+            }
+            if let Some(id) = related_to {
+                list_filter = list_filter.and(filter::TaskFilter::related_to(id));
+            }
+            match state.as_str() {
+                "active" => list_filter = list_filter.and(filter::TaskFilter::active()),
+                "complete" => list_filter = list_filter.and(filter::TaskFilter::complete()),
+                _ => {},
+            }
+            if let Some(after) = stale {
+                list_filter = list_filter.and(filter::TaskFilter::stale(after));
+            }
+            if let Some(where_filter) = r#where {
+                list_filter = list_filter.and(where_filter);
+            }
+            // the banner is only for people reading the list, like the underlined URLs
+            let stale_banner = io::stdout().is_terminal() && stale.is_none() && !waiting;
+            let list_options = task::ListOptions {
+                filter: list_filter,
+                sort: sort.unwrap_or_default(),
+                show_urgency,
+                waiting,
+                redact: redact || config.redact,
+                stale_banner: if stale_banner { Some(config.stale_after()?) } else { None },
+                coefficients: config.urgency.clone(),
+                tree,
+            };
+            #[cfg(feature = "scripting")]
+            let list_script = script.map(|script_path| script::Script::from_file(&script_path))
+                                    .transpose()?;
+            let render = || {
+                #[cfg(feature = "scripting")]
+                if let Some(script) = &list_script {
+                    // Comment: the lines a script prints can't be told apart from task names, so a redacting config refuses them
+                    if list_options.redact {
+                        return Err(io::Error::new(io::ErrorKind::InvalidInput, i18n::tr("error-redact-script", &[])).into());
+                    }
+                    return task::Task::list_scripted(journal_file.clone(), &list_options.filter, script);
+                }
+                task::Task::list_with(journal_file.clone(), &list_options)
+            };
+            if watch {
+                watch::watch(&journal_file, render)?
+            } else {
+                render()?
+            }
         },
-        Some(("remove", remove_args)) => {
-            let remove_index = remove_args.get_one::<usize>("index")
-                                                 .unwrap()
-                                                 .to_owned();
-            task::Task::remove(journal_file, remove_index)?
+        Action::Remove { index, r#where, tag, dry_run, yes } => {
+            let remove_filter = match (r#where, tag) {
+                (Some(remove_filter), _) => Some(remove_filter),
+                (None, Some(remove_tag)) => Some(filter::TaskFilter::tagged(&remove_tag)),
+                (None, None) => None,
+            };
+            if let Some(remove_filter) = remove_filter {
+                return Ok(task::Task::remove_where(journal_file, &remove_filter, dry_run, yes)?);
+            }
+            // the selection group requires the index without a filter
+            task::Task::remove_many(journal_file, &index.unwrap().indices())?
         },
-        Some(("add", add_args)) => {
-            let add_task_name = add_args.get_one::<String>("task")
-                                                .unwrap()
-                                                .to_owned();
+        Action::Add { task, tag, priority, due, assign, goal, estimate, repeat, parent, from_file,
+                      #[cfg(feature = "clipboard")] clipboard } => {
+            let add_task_options = task::TaskOptions {
+                priority,
+                owner: config.user(),
+                assignee: assign,
+                estimate,
+                goal,
+                due,
+                parent,
+                repeat,
+            };
 
-            let add_task_tags  = add_args.get_many::<String>("tag")
-                                            // Since the get_many call returns an Option, to process the Some() case further and 
-                                            // levae the None case as is, and_then() is used, refered as the "flatMap" equivalent
-                                            .and_then(|x|
-                                                // the Some() case is a ValuesRef struct which is an iterator resulting from
-                                                // the get_many call. Thus just needing to processing it properly and collecting
-                                                // into a collection: https://docs.rs/clap/latest/clap/parser/struct.ValuesRef.html
-                                                Some(x.map(|s| 
-                                                    s.to_owned()).collect::<Vec<_>>()));
+            if let Some(from_file) = from_file {
+                let normalizer = normalize::Normalizer::new(&config.normalize)?;
+                let new_tasks = fs::read_to_string(&from_file).with_path("reading tasks", &from_file)?
+                                  .lines()
+                                  .map(|line| normalizer.name(line))
+                                  .filter(|name| !name.is_empty())
+                                  .map(|name| (name, normalizer.tags(tag.clone())))
+                                  .collect();
+                return Ok(task::Task::add_many(journal_file, new_tasks, &add_task_options)?);
+            }
 
+            #[cfg(feature = "clipboard")]
+            if clipboard {
+                let normalizer = normalize::Normalizer::new(&config.normalize)?;
+                let clipboard_name = normalizer.name(&clipboard::task_name(&clipboard::read()?));
+                if clipboard_name.is_empty() {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, i18n::tr("error-clipboard-empty", &[])).into());
+                }
+                return Ok(task::Task::add_with(journal_file, clipboard_name, normalizer.tags(tag), &add_task_options)?);
+            }
 
-            task::Task::add(journal_file, add_task_name, add_task_tags)?
+            // the task is required unless the tasks come from a file or the clipboard
+            task::Task::add_with(journal_file, task.unwrap(), tag, &add_task_options)?
+        }
+        Action::Done { index, tag, dry_run, yes } => {
+            let done = || -> Result<(), Box<dyn Error>> {
+                if let Some(done_indices) = index {
+                    return Ok(task::Task::complete_many(journal_file.clone(), &done_indices.indices())?);
+                }
+                let done_tag = tag.unwrap();
+                Ok(task::Task::complete_where(journal_file.clone(), &filter::TaskFilter::tagged(&done_tag), dry_run, yes)?)
+            };
+            if notify && !dry_run {
+                notify::completing(&mut notify::Desktop, &journal_file, done)?;
+            } else {
+                done()?;
+            }
+        }
+        Action::MoveTo { index, position, after } => {
+            match after {
+                Some(after) => task::Task::move_after(journal_file, index, after)?,
+                None => task::Task::move_to(journal_file, index, position.unwrap())?,
+            }
+        }
+        Action::Bump { index } => {
+            task::Task::bump(journal_file, &index.indices())?
+        }
+        Action::Sink { index } => {
+            task::Task::sink(journal_file, &index.indices())?
+        }
+        Action::Renumber => {
+            task::Task::renumber(journal_file)?
+        }
+        Action::Annotate { index, note } => {
+            task::Task::annotate(journal_file, &index.indices(), note)?
+        }
+        Action::Show { index } => {
+            task::Task::show(journal_file, &index.indices())?
+        }
+        Action::Next { explain } => {
+            task::Task::next(journal_file, explain, &config.urgency)?
+        }
+        Action::Wait { index, until, reason } => {
+            task::Task::wait(journal_file, &index.indices(), until, reason)?
+        }
+        Action::Delegate { index, person } => {
+            task::Task::delegate(journal_file, &index.indices(), person)?
+        }
+        Action::Relate { index, to } => {
+            task::Task::relate(journal_file, index, to)?
+        }
+        Action::Goal { name, by, task } => {
+            let mut goal_indices = task.iter().flat_map(|index| index.indices()).collect::<Vec<_>>();
+            goal_indices.sort_unstable();
+            goal_indices.dedup();
+            task::Task::set_goal(journal_file, &name, by, &goal_indices)?
+        }
+        Action::Goals => {
+            task::Task::goals(journal_file)?
+        }
+        Action::Search { query, exact, regex, tag, any_tag, created_after, created_before, interactive, done } => {
+            let search_query = query.as_deref().unwrap_or("");
+            if interactive {
+                return Ok(task::Task::search_interactive(journal_file, search_query, done)?);
+            }
+            let mut search_filter = filter::TaskFilter::default();
+            if !tag.is_empty() {
+                search_filter = if any_tag {
+                    filter::TaskFilter::any_tag(&tag)
+                } else {
+                    filter::TaskFilter::all_tags(&tag)
+                };
+            }
+            if let Some(after) = created_after {
+                search_filter = search_filter.and(filter::TaskFilter::created_after(after));
+            }
+            if let Some(before) = created_before {
+                search_filter = search_filter.and(filter::TaskFilter::created_before(before));
+            }
+            // Comment: an exact or regex query is a filter like the others, the tasks stay in journal order
+            if exact {
+                search_filter = search_filter.and(filter::TaskFilter::name_containing(search_query));
+                task::Task::search_with(journal_file, "", &search_filter)?
+            } else if regex {
+                search_filter = search_filter.and(filter::TaskFilter::name_matching(search_query)?);
+                task::Task::search_with(journal_file, "", &search_filter)?
+            } else {
+                task::Task::search_with(journal_file, search_query, &search_filter)?
+            }
+        }
+        Action::Trash { action: TrashAction::List } => {
+            task::Task::trash_list(journal_file)?
+        }
+        Action::Restore { id } => {
+            task::Task::restore(journal_file, id)?
+        }
+        Action::Undo { count, list } => {
+            if list {
+                return Ok(task::Task::undo_list(journal_file)?);
+            }
+            task::Task::undo(journal_file, count)?
+        }
+        Action::Tick => {
+            task::Task::tick(journal_file)?
+        }
+        Action::Stats => {
+            task::Task::stats(journal_file, config.stale_after()?)?
+        }
+        Action::Info => {
+            task::Task::info(journal_file)?
+        }
+        Action::Estimate { index, estimate } => {
+            task::Task::estimate_at(journal_file, &index.indices(), estimate)?
+        }
+        Action::Plan { horizon, capacity } => {
+            let plan_capacity = match capacity {
+                Some(capacity) => capacity,
+                None => config.daily_capacity()?,
+            };
+            task::Task::plan(journal_file, horizon, plan_capacity)?
+        }
+        Action::Open { index } => {
+            task::Task::open(journal_file, &index.indices())?
+        }
+        Action::Swap { index, other } => {
+            task::Task::swap(journal_file, index, other)?
+        }
+        Action::Edit { index, r#where, name, add_tag, remove_tag, due, no_due, dry_run } => {
+            match index {
+                Some(edit_index) => {
+                    let edit = task::TaskEdit {
+                        name,
+                        add_tags: add_tag,
+                        remove_tags: remove_tag,
+                        due: if no_due { Some(None) } else { due.map(Some) },
+                    };
+                    task::Task::edit(journal_file, &edit_index.indices(), &edit)?
+                }
+                None => {
+                    let edit_filter = r#where.unwrap();
+                    task::Task::edit_where(journal_file, &edit_filter, &add_tag, &remove_tag, dry_run)?
+                }
+            }
+        }
+        Action::Append { other } => {
+            task::Task::append(journal_file, other)?
+        }
+        Action::Import { from, file, map, dry_run } => {
+            let normalizer = normalize::Normalizer::new(&config.normalize)?;
+            let content = fs::read_to_string(&file).with_path("reading import file", &file)?;
+            let new_tasks = match from.as_str() {
+                "csv" => import::from_csv(&content, &map.unwrap_or_default(), &normalizer)?,
+                "ics" => ics::from_ics(&content, &normalizer)?,
+                "apple-reminders" => reminders::from_apple_reminders(&content, &normalizer)?,
+                "ms-todo" => reminders::from_ms_todo(&content, &normalizer)?,
+                _ => unreachable!(),
+            };
+            task::Task::import(journal_file, new_tasks, dry_run)?
+        }
+        Action::Report { template, plain, period } => {
+            if plain {
+                return Ok(task::Task::report_plain(journal_file, period)?);
+            }
+            let template = template::Template::from_file(&template.unwrap())?;
+            task::Task::report(journal_file, &template)?
+        }
+        Action::Export { format, r#where, out, out_file, group_by } => {
+            let out_path = out.or(out_file).unwrap();
+            task::Task::export(journal_file, format, group_by, &r#where.unwrap_or_default(), out_path)?
+        }
+        Action::Count { tag } => {
+            println!("{}", task::Task::count(journal_file, tag.as_deref())?)
+        }
+        Action::PromptSegment { tag, color } => {
+            // nothing is printed without pending tasks, so the segment disappears from the prompt
+            let (pending, overdue) = task::Task::count_with_overdue(journal_file, tag.as_deref())?;
+            if pending > 0 {
+                let overdue = if overdue > 0 { format!(" ⚠{}", overdue) } else { String::new() };
+                println!("{}", color::paint(&format!("⚑{}{}", pending, overdue), color::Color::Yellow, color));
+            }
+        }
+        Action::Schema => {
+            println!("{}", serde_json::to_string_pretty(&schema::journal())?)
+        }
+        Action::Completions { shell } => {
+            completions::generate(shell, Cli::command(), env!("CARGO_PKG_NAME"), io::stdout().lock())?
+        }
+        Action::Rpc => {
+            if stdio::is_stdio(&journal_file) {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, i18n::tr("error-rpc-stdio", &[])).into());
+            }
+            let mut server = rpc::RpcServer::new(journal_file)?;
+            server.serve(io::stdin().lock(), io::stdout().lock())?
         }
-        _ => unreachable!(),
+        #[cfg(unix)]
+        Action::Daemon => daemon::serve(journal_file)?,
+        #[cfg(not(unix))]
+        Action::Daemon => return Err(io::Error::new(io::ErrorKind::Unsupported, i18n::tr("error-daemon-unsupported", &[])).into()),
+        Action::Macro(args) if config.macros.contains_key(&args[0]) => run_macro(journal_file, &args[0], config)?,
+        Action::Macro(args) => return Err(i18n::tr("error-unknown-subcommand", &[("name", &args[0])]).into()),
     }
 
     Ok(())
-}
\ No newline at end of file
+}
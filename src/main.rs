@@ -1,6 +1,7 @@
 fn main() {
     // print!("hello");
     if let Err(err) = rusty_journal_clap::run() {
-        eprint!("{err}");
+        rusty_journal_clap::output::print_error(&*err);
+        std::process::exit(rusty_journal_clap::error::exit_code(&*err));
     }
 }
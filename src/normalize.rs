@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+use std::io::Result as ioResult;
+use serde::Deserialize;
+use crate::regex::Regex;
+
+/// Normalization rules from the user config, applied to tasks coming in through bulk imports
+/// ```json
+/// "normalize": {
+///     "rename": [{"pattern": "^(?i)todo:?\\s*", "replace": ""}],
+///     "tags": {"wip": "in-progress", "junk": ""}
+/// }
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rules {
+    /// Regex rewrites of the task name, applied in order
+    pub rename: Vec<RenameRule>,
+    /// Tag mapping table, where mapping a tag to an empty string drops the tag
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RenameRule {
+    pub pattern: String,
+    /// The replacement, where `$1`..`$9` refer to the capturing groups of the pattern
+    pub replace: String,
+}
+
+/// The compiled form of the normalization rules
+/// # Examples
+/// ```
+/// use rusty_journal_clap::normalize::{Normalizer, Rules, RenameRule};
+/// let mut rules = Rules::default();
+/// rules.rename.push(RenameRule { pattern: r"^\[(\w+)\]\s*".to_string(), replace: "".to_string() });
+/// rules.tags.insert("wip".to_string(), "in-progress".to_string());
+/// let normalizer = Normalizer::new(&rules).unwrap();
+/// assert_eq!(normalizer.name("[JIRA]  fix login "), "fix login");
+/// assert_eq!(normalizer.tags(Some(vec!["wip".to_string()])), Some(vec!["in-progress".to_string()]));
+/// ```
+pub struct Normalizer {
+    rename: Vec<(Regex, String)>,
+    tags: BTreeMap<String, String>,
+}
+
+impl Normalizer {
+    pub fn new(rules: &Rules) -> ioResult<Normalizer> {
+        let rename = rules.rename
+                          .iter()
+                          .map(|rule| Ok((Regex::new(&rule.pattern)?, rule.replace.to_owned())))
+                          .collect::<ioResult<Vec<_>>>()?;
+
+        Ok(Normalizer { rename, tags: rules.tags.clone() })
+    }
+
+    pub fn name(&self, name: &str) -> String {
+        let name = self.rename
+                       .iter()
+                       .fold(name.to_string(), |name, (pattern, replace)| pattern.replace_all(&name, replace));
+        name.trim().to_string()
+    }
+
+    pub fn tags(&self, tags: Option<Vec<String>>) -> Option<Vec<String>> {
+        let mut mapped: Vec<String> = Vec::new();
+        for tag in tags.into_iter().flatten() {
+            let tag = self.tags.get(&tag).cloned().unwrap_or(tag);
+            if !tag.is_empty() && !mapped.contains(&tag) {
+                mapped.push(tag);
+            }
+        }
+        // c.f. the tags field of Task, a task without tag is represented by None
+        if mapped.is_empty() { None } else { Some(mapped) }
+    }
+}
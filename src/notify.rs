@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::io::{Error, ErrorKind, Result as ioResult};
+use chrono::{DateTime, Utc};
+use crate::error::Context;
+use crate::i18n::tr;
+use crate::paths;
+use crate::task::{self, Priority, Task};
+use crate::trace;
+
+// Comment: desktop notifications go through the notifier that ships with the platform rather than
+// talking to the notification daemon directly: notify-send (libnotify) on Linux and the BSDs,
+// and AppleScript's `display notification` on macOS. Both are fire and forget, so the
+// child process is waited on but its output is discarded.
+
+/// Shows a desktop notification with the given summary and body
+pub fn send(summary: &str, body: &str) -> ioResult<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e")
+               .arg(format!("display notification {} with title {}", applescript_string(body), applescript_string(summary)));
+        command
+    } else if cfg!(unix) {
+        let mut command = Command::new("notify-send");
+        command.arg("--app-name=rusty-journal")
+               .arg(summary)
+               .arg(body);
+        command
+    } else {
+        return Err(Error::new(ErrorKind::Unsupported, tr("error-notify-unsupported", &[])));
+    };
+
+    let status = command.stdin(Stdio::null())
+                        .stdout(Stdio::null())
+                        .stderr(Stdio::null())
+                        .status()?;
+
+    if !status.success() {
+        return Err(Error::other(tr("error-notifier-exited", &[("status", &status)])));
+    }
+
+    Ok(())
+}
+
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Where the notifications are shown, the desktop unless something else stands in for it, e.g. in tests
+pub trait Notifier {
+    fn notify(&mut self, summary: &str, body: &str) -> ioResult<()>;
+}
+
+/// Shows the notifications on the desktop with `send`
+pub struct Desktop;
+
+impl Notifier for Desktop {
+    fn notify(&mut self, summary: &str, body: &str) -> ioResult<()> {
+        // a notifier that is missing or fails is no reason to fail the command
+        if let Err(err) = send(summary, body) {
+            trace::event("notify.failed", &[("message", &err)]);
+        }
+        Ok(())
+    }
+}
+
+/// The file keeping the time the journal was last checked for overdue tasks
+pub fn stamp_path(journal_path: &Path) -> PathBuf {
+    journal_path.with_extension("notified")
+}
+
+/// Notifies of the open tasks of the journal that became overdue since the last check, and records the time of this
+/// check. The first check of a journal only records its time, rather than notifying of every task overdue already.
+/// A missing journal is left as it is, and a read-only run, c.f. `task::set_read_only`, records nothing
+pub fn overdue(notifier: &mut impl Notifier, journal_path: &Path, now: DateTime<Utc>) -> ioResult<()> {
+    if !paths::resolve(journal_path).is_ok_and(|path| path.exists()) {
+        return Ok(());
+    }
+    let stamp = stamp_path(journal_path);
+    let since = match fs::read_to_string(&stamp) {
+        Ok(text) => DateTime::parse_from_rfc3339(text.trim()).ok().map(|since| since.with_timezone(&Utc)),
+        Err(err) if err.kind() == ErrorKind::NotFound => None,
+        Err(err) => return Err(err).with_path("reading notification stamp", &stamp),
+    };
+    if let Some(since) = since {
+        let tasks = Task::_read_tasks(journal_path)?;
+        for task in tasks.iter().filter(|task| task.is_overdue(now) && task.due().is_some_and(|due| due >= since)) {
+            notifier.notify(&tr("notify-overdue", &[]), task.name())?;
+        }
+    }
+    if task::is_read_only() {
+        return Ok(());
+    }
+    fs::write(&stamp, now.to_rfc3339()).with_path("writing notification stamp", &stamp)
+}
+
+/// Runs the command, e.g. `done`, then notifies of the open tasks of high priority it completed
+pub fn completing<E: From<Error>>(notifier: &mut impl Notifier, journal_path: &Path, command: impl FnOnce() -> Result<(), E>) -> Result<(), E> {
+    let open = Task::_load_tasks(journal_path)?
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, task)| !task.is_complete() && task.priority() == Some(Priority::High))
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>();
+    command()?;
+
+    // Comment: completing keeps the tasks in place, the next occurrences of recurring tasks are added at the end
+    let tasks = Task::_load_tasks(journal_path)?;
+    for task in open.into_iter().filter_map(|i| tasks.get(i)).filter(|task| task.is_complete()) {
+        notifier.notify(&tr("notify-completed", &[]), task.name())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use std::{env, error::Error};
+use std::io::{Result as ioResult, Write};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use serde_json::{json, Value};
+use crate::error::Details;
+use crate::redact;
+use crate::task::Task;
+
+/// Setting this environment variable to anything but `0` or an empty value turns on the deterministic mode,
+/// same as the `--deterministic` flag
+pub const DETERMINISTIC_ENV: &str = "RUSTY_JOURNAL_DETERMINISTIC";
+
+static DETERMINISTIC: AtomicBool = AtomicBool::new(false);
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+// the OutputFormat as its position in the enum
+static OUTPUT_FORMAT: AtomicU8 = AtomicU8::new(0);
+
+/// How `list` and `search` print the tasks, c.f. `--output`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// The task lines as shown to people, which may change from one version to the next
+    Human,
+    /// A JSON array of the tasks with their index
+    Json,
+    /// One line per task of tab separated fields, c.f. `write_porcelain`
+    Porcelain,
+}
+
+impl OutputFormat {
+    pub fn parse(format: &str) -> Result<OutputFormat, String> {
+        match format {
+            "human" => Ok(OutputFormat::Human),
+            "json" => Ok(OutputFormat::Json),
+            "porcelain" => Ok(OutputFormat::Porcelain),
+            _ => Err(format!("unknown output format '{}', expected human, json or porcelain", format)),
+        }
+    }
+}
+
+/// Sets for the whole process how the tasks are printed
+pub fn set_format(format: OutputFormat) {
+    OUTPUT_FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+pub fn format() -> OutputFormat {
+    match OUTPUT_FORMAT.load(Ordering::Relaxed) {
+        1 => OutputFormat::Json,
+        2 => OutputFormat::Porcelain,
+        _ => OutputFormat::Human,
+    }
+}
+
+/// Turns the deterministic mode on or off for the whole process. In deterministic mode the output
+/// doesn't depend on the machine it's produced on: times are shown in UTC instead of the local timezone
+/// and there is no color, so the output can be compared against snapshots
+pub fn set_deterministic(deterministic: bool) {
+    DETERMINISTIC.store(deterministic, Ordering::Relaxed);
+}
+
+pub fn is_deterministic() -> bool {
+    DETERMINISTIC.load(Ordering::Relaxed)
+}
+
+/// Whether the environment asks for the deterministic mode
+pub fn deterministic_from_env() -> bool {
+    env::var(DETERMINISTIC_ENV).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Formats the time for display, in the local timezone or in UTC in deterministic mode
+/// # Examples
+/// ```
+/// use rusty_journal_clap::output;
+/// use chrono::DateTime;
+/// output::set_deterministic(true);
+/// let time = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+/// assert_eq!(output::format_time(time, "%d/%m/%Y %H:%M"), "14/11/2023 22:13");
+/// ```
+pub fn format_time(time: DateTime<Utc>, format: &str) -> String {
+    if is_deterministic() {
+        time.format(format).to_string()
+    } else {
+        time.with_timezone(&Local).format(format).to_string()
+    }
+}
+
+/// The calendar day of the time, in the local timezone or in UTC in deterministic mode, like `format_time`
+pub fn date(time: DateTime<Utc>) -> NaiveDate {
+    if is_deterministic() {
+        time.date_naive()
+    } else {
+        time.with_timezone(&Local).date_naive()
+    }
+}
+
+/// The time of a wall clock reading, in the local timezone or in UTC in deterministic mode, the inverse of `format_time`.
+/// None for a reading skipped by a daylight saving change
+pub fn from_local(naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    if is_deterministic() {
+        return Some(naive.and_utc());
+    }
+    Local.from_local_datetime(&naive).earliest().map(|local| local.with_timezone(&Utc))
+}
+
+/// Reports errors as JSON objects instead of text, c.f. `--error-format json`
+pub fn set_json_errors(json_errors: bool) {
+    JSON_ERRORS.store(json_errors, Ordering::Relaxed);
+}
+
+/// Prints the error on stderr, either as its message or as a JSON object with its details on a single line,
+/// e.g. `{"code":"invalid_index","message":"...","command":"remove","index":9}`
+pub fn print_error(err: &(dyn Error + 'static)) {
+    if JSON_ERRORS.load(Ordering::Relaxed) {
+        eprintln!("{}", serde_json::to_string(&Details::of(err)).unwrap_or_default());
+    } else {
+        eprintln!("{}", err);
+    }
+}
+
+// Comment: the machine-readable formats give times in UTC whatever the mode, so scripts don't depend on the timezone
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// A listed task as a JSON object: its index in the journal, id, name, state, tags, priority and times.
+/// A redacted task has the placeholder of `redact::placeholder` for its name
+/// # Examples
+/// ```
+/// use rusty_journal_clap::output;
+/// # use rusty_journal_clap::test_util::TempJournal;
+/// let journal = TempJournal::seeded().unwrap();
+/// let tasks = journal.tasks().unwrap();
+/// let value = output::task_json(3, &tasks[2], false);
+/// assert_eq!(value["index"], 3);
+/// assert_eq!(value["state"], "complete");
+/// assert_eq!(value["tags"], serde_json::json!(["home", "admin"]));
+/// ```
+pub fn task_json(index: usize, task: &Task, redacted: bool) -> Value {
+    json!({
+        "index": index,
+        "id": task.id(),
+        "name": if redacted { redact::placeholder(task.name()) } else { task.name().to_string() },
+        "state": task.state_name(),
+        "tags": task.tags(),
+        "priority": task.priority().map(|priority| priority.name()),
+        "created_at": timestamp(task.created_at()),
+        "due": task.due().map(timestamp),
+        "completed_at": task.completed_at().map(timestamp),
+        "waiting_until": task.waiting_until().map(timestamp),
+        "parent": task.parent(),
+        "repeat": task.repeat().map(|repeat| repeat.to_string()),
+    })
+}
+
+/// Writes the listed tasks, with their (1-based) index in the journal, as a JSON array on a single line
+pub fn write_json(mut out: impl Write, listed: &[(usize, &Task)], redacted: bool) -> ioResult<()> {
+    let tasks = listed.iter().map(|&(index, task)| task_json(index, task, redacted)).collect::<Vec<_>>();
+    serde_json::to_writer(&mut out, &tasks)?;
+    writeln!(out)
+}
+
+/// Writes the listed tasks one per line, as tab separated fields in a fixed order:
+/// index, id, state, priority, created at, due, completed at, tags (comma separated) and name last.
+/// A missing field is empty, and tabs and line breaks in the name and tags are replaced by spaces
+/// # Examples
+/// ```
+/// use rusty_journal_clap::output;
+/// # use rusty_journal_clap::test_util::TempJournal;
+/// let journal = TempJournal::seeded().unwrap();
+/// let tasks = journal.tasks().unwrap();
+/// let mut out = Vec::new();
+/// output::write_porcelain(&mut out, &[(1, &tasks[0])], false).unwrap();
+/// let line = String::from_utf8(out).unwrap();
+/// assert!(line.starts_with("1\t1\tactive\t\t"));
+/// assert!(line.ends_with("\t\t\tgroceries\tBuy milk\n"));
+/// ```
+pub fn write_porcelain(mut out: impl Write, listed: &[(usize, &Task)], redacted: bool) -> ioResult<()> {
+    let clean = |text: &str| text.replace(['\t', '\n', '\r'], " ");
+    for &(index, task) in listed {
+        let name = if redacted { redact::placeholder(task.name()) } else { clean(task.name()) };
+        writeln!(out, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                 index,
+                 task.id().map(|id| id.to_string()).unwrap_or_default(),
+                 task.state_name(),
+                 task.priority().map_or("", |priority| priority.name()),
+                 timestamp(task.created_at()),
+                 task.due().map(timestamp).unwrap_or_default(),
+                 task.completed_at().map(timestamp).unwrap_or_default(),
+                 clean(&task.tags().join(",")),
+                 name)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{Error, ErrorKind, Result as ioResult};
+use std::path::{Path, PathBuf};
+use serde::Serializer;
+use crate::i18n::tr;
+
+/// The environment variable naming the journal when `--journal_file` isn't given
+pub const JOURNAL_ENV: &str = "RUSTY_JOURNAL_FILE";
+
+/// The journal used when it is named nowhere else
+pub const DEFAULT_JOURNAL: &str = "todo.json";
+
+/// Picks the journal to work on: the one given with `--journal_file`, otherwise the one in `$RUSTY_JOURNAL_FILE`,
+/// otherwise `journal` from the user config, and `DEFAULT_JOURNAL` when none is. An empty value counts as not given,
+/// e.g. `RUSTY_JOURNAL_FILE= rusty-journal-clap list`
+/// # Examples
+/// ```
+/// use rusty_journal_clap::paths;
+/// use std::{ffi::OsStr, path::{Path, PathBuf}};
+/// let (flag, env, config) = (Path::new("flag.json"), OsStr::new("env.json"), Path::new("config.json"));
+/// assert_eq!(paths::journal(Some(flag), Some(env), Some(config)), PathBuf::from("flag.json"));
+/// assert_eq!(paths::journal(None, Some(env), Some(config)), PathBuf::from("env.json"));
+/// assert_eq!(paths::journal(None, None, Some(config)), PathBuf::from("config.json"));
+/// assert_eq!(paths::journal(None, None, None), PathBuf::from("todo.json"));
+/// assert_eq!(paths::journal(None, Some(OsStr::new("")), Some(config)), PathBuf::from("config.json"));
+/// assert_eq!(paths::journal(Some(Path::new("-")), Some(env), None), PathBuf::from("-"));
+/// ```
+pub fn journal(flag: Option<&Path>, env: Option<&OsStr>, config: Option<&Path>) -> PathBuf {
+    flag.filter(|path| !path.as_os_str().is_empty())
+        .or_else(|| env.map(Path::new).filter(|path| !path.as_os_str().is_empty()))
+        .or_else(|| config.filter(|path| !path.as_os_str().is_empty()))
+        .unwrap_or(Path::new(DEFAULT_JOURNAL))
+        .to_path_buf()
+}
+
+/// Resolves a journal path before the journal is opened: the directory is made absolute and canonical,
+/// while the file itself, which may not exist yet, keeps its name. Paths are kept as `Path`s throughout,
+/// so non UTF-8 file names on Linux work as any other.
+///
+/// A missing directory is a `NotFound` error naming that directory, rather than the bare
+/// "No such file or directory" of opening the file
+/// # Examples
+/// ```
+/// use rusty_journal_clap::paths;
+/// use std::{env, path::Path};
+/// let resolved = paths::resolve(Path::new("todo.json")).unwrap();
+/// assert_eq!(resolved, env::current_dir().unwrap().canonicalize().unwrap().join("todo.json"));
+/// let err = paths::resolve(Path::new("no/such/dir/todo.json")).unwrap_err();
+/// assert_eq!(err.to_string(), "the directory 'no/such/dir' doesn't exist");
+/// assert!(paths::resolve(Path::new("..")).is_err());
+/// ```
+pub fn resolve(path: &Path) -> ioResult<PathBuf> {
+    let file_name = path.file_name()
+                        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, tr("error-not-a-file", &[("path", &path.display())])))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    // Comment: on Windows the canonical form is the verbatim `\\?\C:\...` or `\\?\UNC\server\share\...` path,
+    // which isn't limited to MAX_PATH, so long journal paths open as well
+    let dir = match fs::canonicalize(dir) {
+        Ok(dir) => dir,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Err(Error::new(ErrorKind::NotFound, tr("error-missing-directory", &[("path", &dir.display())])));
+        }
+        Err(err) => return Err(err),
+    };
+    if !dir.is_dir() {
+        return Err(Error::new(ErrorKind::InvalidInput, tr("error-not-a-directory", &[("path", &dir.display())])));
+    }
+
+    Ok(dir.join(file_name))
+}
+
+/// Serializes an optional path as a string, replacing what isn't valid UTF-8, where serde's
+/// own serialization of paths fails
+pub(crate) fn serialize_lossy<S: Serializer>(path: &Option<PathBuf>, serializer: S) -> Result<S::Ok, S::Error> {
+    match path {
+        Some(path) => serializer.serialize_str(&path.to_string_lossy()),
+        None => serializer.serialize_none(),
+    }
+}
@@ -0,0 +1,199 @@
+use std::fmt::Write as _;
+use std::io::{self, Error, ErrorKind, IsTerminal, Read, Result as ioResult, Write};
+use std::process::{Command, Stdio};
+use crate::fuzzy;
+use crate::i18n::tr;
+use crate::task::Task;
+
+// how many matches are shown under the query
+const SHOWN: usize = 10;
+
+/// A key pressed in the picker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Char(char),
+    Backspace,
+    Up,
+    Down,
+    Enter,
+    Escape,
+}
+
+/// What the picker ended with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    // the (1-based) index of the picked task
+    Picked(usize),
+    Cancelled,
+}
+
+/// The state of an interactive search, the query typed so far and the selected match, refiltered on every key
+/// # Examples
+/// ```
+/// use rusty_journal_clap::picker::{Key, Outcome, Picker};
+/// let names = ["Buy milk", "Book the dentist", "Independent review"];
+/// let mut picker = Picker::new("");
+/// for c in "dent".chars() {
+///     assert_eq!(picker.key(Key::Char(c), names), None);
+/// }
+/// assert_eq!(picker.key(Key::Down, names), None);
+/// assert_eq!(picker.key(Key::Enter, names), Some(Outcome::Picked(3)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Picker {
+    query: String,
+    // position in the matches
+    selected: usize,
+}
+
+impl Picker {
+    pub fn new(query: &str) -> Self {
+        Picker { query: query.to_string(), selected: 0 }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// The matches of the query, best first, as (1-based) indices
+    pub fn matches<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+        fuzzy::rank(&self.query, names)
+    }
+
+    /// Handles a key, returning the outcome once the search is over
+    pub fn key<'a>(&mut self, key: Key, names: impl IntoIterator<Item = &'a str>) -> Option<Outcome> {
+        let matches = self.matches(names);
+        match key {
+            Key::Char(c) => {
+                self.query.push(c);
+                self.selected = 0;
+            }
+            Key::Backspace => {
+                self.query.pop();
+                self.selected = 0;
+            }
+            Key::Up => self.selected = self.selected.saturating_sub(1),
+            Key::Down => self.selected = (self.selected + 1).min(matches.len().min(SHOWN).saturating_sub(1)),
+            // Comment: Enter without any match does nothing, the user can still type or cancel
+            Key::Enter => return matches.get(self.selected).map(|&index| Outcome::Picked(index)),
+            Key::Escape => return Some(Outcome::Cancelled),
+        }
+        None
+    }
+
+    /// Renders the query and the first matches, the selected one marked with `>`
+    pub fn render(&self, tasks: &[Task]) -> String {
+        let matches = self.matches(tasks.iter().map(Task::name));
+        let mut screen = String::new();
+        // Comment: writing to a String can't fail
+        let _ = writeln!(screen, "> {}", self.query);
+        for (position, &index) in matches.iter().take(SHOWN).enumerate() {
+            let marker = if position == self.selected { '>' } else { ' ' };
+            let _ = writeln!(screen, "{} {:>4}: {}", marker, index, tasks[index - 1].name());
+        }
+        let _ = write!(screen, "  {}/{}", matches.len(), tasks.len());
+        screen
+    }
+}
+
+/// Puts the terminal in non-canonical mode without echo or signals for as long as it lives, so every key,
+/// Ctrl-C included, is read as soon as it is pressed. Like the clipboard, this goes through the platform tool, stty
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enter() -> ioResult<RawMode> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        Ok(RawMode { saved: saved.trim().to_string() })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> ioResult<String> {
+    // stty works on the terminal of its stdin
+    let output = Command::new("stty").args(args)
+                                     .stdin(Stdio::inherit())
+                                     .stderr(Stdio::null())
+                                     .output()?;
+    if !output.status.success() {
+        return Err(Error::other(tr("error-tool-exited", &[("tool", &"stty"), ("status", &output.status)])));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Reads the next key from the terminal, or None for the keys the picker doesn't know
+fn read_key(input: &mut impl Read) -> ioResult<Option<Key>> {
+    let mut byte = [0];
+    input.read_exact(&mut byte)?;
+    let key = match byte[0] {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        // Ctrl-C doesn't interrupt without signals, and cancels like Escape
+        0x03 => Key::Escape,
+        0x1b => {
+            // Comment: the arrows come as ESC [ A and ESC [ B. A lone Escape can't be told apart from the start
+            // of a sequence without a timeout, so it takes effect with the next key, which it swallows
+            let mut next = [0];
+            input.read_exact(&mut next)?;
+            if next[0] != b'[' {
+                return Ok(Some(Key::Escape));
+            }
+            input.read_exact(&mut next)?;
+            match next[0] {
+                b'A' => Key::Up,
+                b'B' => Key::Down,
+                _ => return Ok(None),
+            }
+        }
+        first if first < 0x20 => return Ok(None),
+        first => {
+            // the continuation bytes of a multi-byte character
+            let length = match first {
+                0xf0.. => 4,
+                0xe0.. => 3,
+                0xc0.. => 2,
+                _ => 1,
+            };
+            let mut bytes = vec![first; length];
+            input.read_exact(&mut bytes[1..])?;
+            match std::str::from_utf8(&bytes).ok().and_then(|c| c.chars().next()) {
+                Some(c) => Key::Char(c),
+                None => return Ok(None),
+            }
+        }
+    };
+    Ok(Some(key))
+}
+
+/// Lets the user pick a task by typing, refiltering the tasks on every key. The picker is drawn on stderr,
+/// so stdout only gets what the caller prints of the outcome
+pub fn pick(tasks: &[Task], query: &str) -> ioResult<Outcome> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return Err(Error::new(ErrorKind::InvalidInput, tr("error-search-terminal", &[])));
+    }
+    let _raw_mode = RawMode::enter()?;
+
+    let mut picker = Picker::new(query);
+    let mut input = stdin.lock();
+    let mut screen = io::stderr().lock();
+    loop {
+        // clears the screen and moves to its top left corner
+        write!(screen, "\x1b[2J\x1b[H{}", picker.render(tasks))?;
+        screen.flush()?;
+        let Some(key) = read_key(&mut input)? else {
+            continue;
+        };
+        if let Some(outcome) = picker.key(key, tasks.iter().map(Task::name)) {
+            write!(screen, "\x1b[2J\x1b[H")?;
+            return Ok(outcome);
+        }
+    }
+}
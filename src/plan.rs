@@ -0,0 +1,64 @@
+use std::fmt::Write;
+use chrono::{DateTime, Duration, Utc};
+use crate::i18n::tr;
+use crate::output;
+use crate::span;
+use crate::task::Task;
+
+/// Renders the plan of the `days` days starting today: every day lists the active tasks due that day,
+/// with their estimates summed up against the daily capacity, and the days over capacity are flagged.
+/// Overdue tasks are planned for today, and the active tasks without due date are summed up at the end
+/// # Examples
+/// ```
+/// use rusty_journal_clap::{output, plan::plan};
+/// use chrono::{DateTime, Duration};
+/// output::set_deterministic(true);
+/// let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+/// let plan = plan(&[], 2, Duration::hours(6), now);
+/// assert_eq!(plan, "Plan for 2 days, 6h a day\n2023-11-14  0m / 6h\n2023-11-15  0m / 6h\n");
+/// ```
+pub fn plan(tasks: &[Task], days: i64, capacity: Duration, now: DateTime<Utc>) -> String {
+    let today = output::date(now);
+    let mut planned: Vec<Vec<(usize, &Task)>> = vec![Vec::new(); days.max(0) as usize];
+    let mut unscheduled = Vec::new();
+    for (index, task) in tasks.iter().enumerate().map(|(i, task)| (i + 1, task)) {
+        if task.is_complete() {
+            continue;
+        }
+        match task.due() {
+            Some(due) => {
+                let day = (output::date(due) - today).num_days().max(0);
+                if let Some(tasks) = planned.get_mut(day as usize) {
+                    tasks.push((index, task));
+                }
+            }
+            None => unscheduled.push(task),
+        }
+    }
+
+    let total = |tasks: &mut dyn Iterator<Item = &Task>| tasks.filter_map(|task| task.estimate())
+                                                              .fold(Duration::zero(), |total, estimate| total + estimate);
+    let mut plan = String::new();
+    // Comment: writing to a String can't fail
+    let _ = writeln!(plan, "{}", tr("plan-title", &[("days", &days), ("capacity", &span::format(capacity))]));
+    for (day, tasks) in planned.iter().enumerate() {
+        let busy = total(&mut tasks.iter().map(|&(_, task)| task));
+        let date = today + Duration::days(day as i64);
+        let _ = write!(plan, "{}", tr("plan-day", &[("date", &date.format("%Y-%m-%d")),
+                                                    ("planned", &span::format(busy)),
+                                                    ("capacity", &span::format(capacity))]));
+        if busy > capacity {
+            let _ = write!(plan, "  {}", tr("plan-overcommitted", &[("over", &span::format(busy - capacity))]));
+        }
+        plan.push('\n');
+        for (index, task) in tasks {
+            let estimate = task.estimate().map_or_else(|| tr("plan-no-estimate", &[]), span::format);
+            let _ = writeln!(plan, "  {:>4}. {} ({})", index, task.name(), estimate);
+        }
+    }
+    if !unscheduled.is_empty() {
+        let _ = writeln!(plan, "{}", tr("plan-unscheduled", &[("count", &unscheduled.len()),
+                                                              ("estimate", &span::format(total(&mut unscheduled.iter().copied())))]));
+    }
+    plan
+}
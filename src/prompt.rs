@@ -0,0 +1,21 @@
+use std::io::{self, BufRead, Error, ErrorKind, IsTerminal, Write, Result as ioResult};
+use crate::i18n::tr;
+
+/// Asks the user a yes/no question on the terminal, where anything but yes means no.
+/// Without a terminal to ask on, this fails rather than guessing, and the
+/// caller should offer a way to skip the question such as a `--yes` flag.
+pub fn confirm(question: &str) -> ioResult<bool> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return Err(Error::new(ErrorKind::InvalidInput, tr("error-confirm-terminal", &[("question", &question)])));
+    }
+
+    print!("{} {} ", question, tr("confirm-answers", &[]));
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    stdin.lock().read_line(&mut answer)?;
+
+    let answer = answer.trim().to_lowercase();
+    Ok(tr("confirm-yes", &[]).split_whitespace().any(|yes| yes == answer))
+}
@@ -0,0 +1,145 @@
+use std::fmt::{Display, Formatter};
+use std::fmt::Result as fmtResult;
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+use serde::{Deserialize, Serialize};
+use crate::i18n::tr;
+#[cfg(feature = "test_util")]
+use crate::test_util::{Arbitrary, Rng};
+
+/// How often a recurring task comes back, c.f. `add --repeat`.
+/// Stored in the journal the way `parse` reads it, e.g. `"weekly"` or `"every 3 days"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Recurrence {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+}
+
+impl Recurrence {
+    /// Parses `daily`, `weekly` and `monthly`, or `every` followed by a number and a unit, e.g. `every 3 days`
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::recur::Recurrence;
+    /// assert_eq!(Recurrence::parse("weekly"), Ok(Recurrence::Weeks(1)));
+    /// assert_eq!(Recurrence::parse("every 3 days"), Ok(Recurrence::Days(3)));
+    /// assert_eq!(Recurrence::parse("every month"), Ok(Recurrence::Months(1)));
+    /// assert!(Recurrence::parse("every 0 days").is_err());
+    /// assert!(Recurrence::parse("hourly").is_err());
+    /// ```
+    pub fn parse(rule: &str) -> Result<Recurrence, String> {
+        let invalid = || tr("error-invalid-repetition", &[("rule", &rule)]);
+        let rule = rule.trim().to_lowercase();
+        let words = rule.split_whitespace().collect::<Vec<_>>();
+        let (count, unit) = match words[..] {
+            ["daily"] => (1, "day"),
+            ["weekly"] => (1, "week"),
+            ["monthly"] => (1, "month"),
+            ["every", unit] => (1, unit),
+            ["every", count, unit] => (count.parse::<u32>().map_err(|_| invalid())?, unit),
+            _ => return Err(invalid()),
+        };
+        if count == 0 {
+            return Err(invalid());
+        }
+        match unit.strip_suffix('s').unwrap_or(unit) {
+            "day" => Ok(Recurrence::Days(count)),
+            "week" => Ok(Recurrence::Weeks(count)),
+            "month" => Ok(Recurrence::Months(count)),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// The date one period after the given one. A month later keeps the day of the month,
+    /// or falls on the last day of a month that is too short, e.g. from 31/01 to 29/02
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::recur::Recurrence;
+    /// use chrono::{TimeZone, Utc};
+    /// let due = Utc.with_ymd_and_hms(2024, 1, 31, 9, 0, 0).unwrap();
+    /// assert_eq!(Recurrence::Days(3).after(due), Utc.with_ymd_and_hms(2024, 2, 3, 9, 0, 0).unwrap());
+    /// assert_eq!(Recurrence::Months(1).after(due), Utc.with_ymd_and_hms(2024, 2, 29, 9, 0, 0).unwrap());
+    /// ```
+    pub fn after(self, date: DateTime<Utc>) -> DateTime<Utc> {
+        // Comment: past the dates chrono can represent the date stays as it is, which `next_due` stops at
+        match self {
+            Recurrence::Days(days) => date.checked_add_signed(Duration::days(days.into())),
+            Recurrence::Weeks(weeks) => date.checked_add_signed(Duration::weeks(weeks.into())),
+            Recurrence::Months(months) => date.checked_add_months(Months::new(months)),
+        }.unwrap_or(date)
+    }
+
+    /// The date one period after the given one, like `after`, where a month later falls on the given day of the month
+    /// rather than the one of the date, or on the last day of a month that is too short. A task due on the 31st keeps
+    /// coming back on the last day of the month, e.g. from 29/02 to 31/03 rather than to 29/03
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::recur::Recurrence;
+    /// use chrono::{TimeZone, Utc};
+    /// let due = Utc.with_ymd_and_hms(2024, 2, 29, 9, 0, 0).unwrap();
+    /// assert_eq!(Recurrence::Months(1).after_on(due, 31), Utc.with_ymd_and_hms(2024, 3, 31, 9, 0, 0).unwrap());
+    /// assert_eq!(Recurrence::Months(2).after_on(due, 31), Utc.with_ymd_and_hms(2024, 4, 30, 9, 0, 0).unwrap());
+    /// assert_eq!(Recurrence::Weeks(1).after_on(due, 31), Utc.with_ymd_and_hms(2024, 3, 7, 9, 0, 0).unwrap());
+    /// ```
+    pub fn after_on(self, date: DateTime<Utc>, day: u32) -> DateTime<Utc> {
+        let next = self.after(date);
+        match self {
+            Recurrence::Months(_) => (next.day()..=day).rev().find_map(|day| next.with_day(day)).unwrap_or(next),
+            _ => next,
+        }
+    }
+
+    /// The due date of the next occurrence of a task due at the given date, or completed then when it had no due date.
+    /// A monthly one falls on the given day of the month, c.f. `after_on`. The periods missed entirely are skipped,
+    /// so the next occurrence is always due after now
+    pub fn next_due(self, due: DateTime<Utc>, day: u32, now: DateTime<Utc>) -> DateTime<Utc> {
+        let mut next = self.after_on(due, day);
+        while next <= now {
+            let later = self.after_on(next, day);
+            if later == next {
+                break;
+            }
+            next = later;
+        }
+        next
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut Formatter) -> fmtResult {
+        match *self {
+            Recurrence::Days(1) => write!(f, "daily"),
+            Recurrence::Weeks(1) => write!(f, "weekly"),
+            Recurrence::Months(1) => write!(f, "monthly"),
+            Recurrence::Days(days) => write!(f, "every {} days", days),
+            Recurrence::Weeks(weeks) => write!(f, "every {} weeks", weeks),
+            Recurrence::Months(months) => write!(f, "every {} months", months),
+        }
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> String {
+        recurrence.to_string()
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(rule: String) -> Result<Recurrence, String> {
+        Recurrence::parse(&rule)
+    }
+}
+
+#[cfg(feature = "test_util")]
+impl Arbitrary for Recurrence {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let count = rng.below(30) as u32 + 1;
+        match rng.below(3) {
+            0 => Recurrence::Days(count),
+            1 => Recurrence::Weeks(count),
+            _ => Recurrence::Months(count),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt::{Display, Formatter, Result as fmtResult};
+use crate::i18n::tr;
+use crate::output;
+use crate::task::Task;
+
+/// A placeholder standing in for a task name, the same for the same name, so a task can be followed
+/// from one screenshot to the next without its name being readable.
+/// The hash is FNV-1a, which unlike the hasher of std stays the same across Rust versions
+/// # Examples
+/// ```
+/// use rusty_journal_clap::redact::placeholder;
+/// assert_eq!(placeholder("Buy milk"), placeholder("Buy milk"));
+/// assert_ne!(placeholder("Buy milk"), placeholder("Buy milk!"));
+/// assert_eq!(placeholder("Buy milk").len(), 7);
+/// ```
+pub fn placeholder(name: &str) -> String {
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    format!("#{:06x}", hash & 0xff_ffff)
+}
+
+/// A task listed without its name, or what it waits for, showing a placeholder and the tags instead,
+/// with the state and dates as `list` shows them, e.g. to share a screenshot of the journal
+pub struct Redacted<'a>(pub &'a Task);
+
+impl Display for Redacted<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmtResult {
+        let task = self.0;
+        let mut masked = placeholder(task.name());
+        if !task.tags().is_empty() {
+            masked = format!("{} [{}]", masked, task.tags().join(", "));
+        }
+        write!(f, "{} {:<50} {} {}", tr("label-task", &[]), masked, tr("label-created", &[]), output::format_time(task.created_at(), "%d/%m/%Y %H:%M"))?;
+        if let Some(due) = task.due() {
+            write!(f, " {} {}", tr("label-due", &[]), output::format_time(due, "%d/%m/%Y %H:%M"))?;
+        }
+        if let Some(completed_at) = task.completed_at() {
+            write!(f, " {} {}", tr("label-done", &[]), output::format_time(completed_at, "%d/%m/%Y %H:%M"))?;
+        }
+        if let Some(until) = task.waiting_until() {
+            write!(f, " {} {}", tr("label-waiting", &[]), output::format_time(until, "%d/%m/%Y %H:%M"))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,497 @@
+use std::io::{Error, ErrorKind, Result as ioResult};
+
+// Comment: a small regular expression engine covering the commonly used syntax:
+// literals, `.`, classes like `[a-z_]` / `[^0-9]`, the escapes \d \w \s (and their negations \D \W \S),
+// anchors `^` / `$` / `\b`, capturing `( )` and non-capturing `(?: )` groups, alternation `|`,
+// greedy and lazy quantifiers `* + ? {n} {n,} {n,m}` (suffixed with `?` for lazy), and a leading `(?i)`
+// for case-insensitive matching.
+// The pattern is compiled to a small instruction set and run by a backtracking matcher which
+// remembers the (instruction, position) pairs it already visited. A pair that failed once fails again,
+// no matter which captures were recorded on the way, so the matching time stays linear in
+// pattern size * text length instead of blowing up on patterns like `(a*)*b`.
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Vec<(char, char)>, bool),
+    Start,
+    End,
+    WordBoundary(bool),
+    Split(usize, usize),
+    Jmp(usize),
+    Save(usize),
+    Match,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Vec<(char, char)>, bool),
+    Start,
+    End,
+    WordBoundary(bool),
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat { node: Box<Node>, min: usize, max: Option<usize>, greedy: bool },
+}
+
+fn regex_error(pattern: &str, msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, format!("Invalid regular expression '{}': {}", pattern, msg))
+}
+
+const DIGIT: &[(char, char)] = &[('0', '9')];
+const WORD: &[(char, char)] = &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+const SPACE: &[(char, char)] = &[('\t', '\r'), (' ', ' ')];
+
+struct Parser<'a> {
+    pattern: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+    groups: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> Error {
+        regex_error(self.pattern, msg)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn alternation(&mut self) -> ioResult<Node> {
+        let mut branches = vec![self.concat()?];
+        while self.eat('|') {
+            branches.push(self.concat()?);
+        }
+        Ok(if branches.len() == 1 { branches.pop().unwrap() } else { Node::Alternate(branches) })
+    }
+
+    fn concat(&mut self) -> ioResult<Node> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect::<String>().parse().ok()
+    }
+
+    fn quantified(&mut self, node: Node) -> ioResult<Node> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                let start = self.pos;
+                self.pos += 1;
+                let min = match self.number() {
+                    Some(min) => min,
+                    // not a counted repetition, so `{` is taken literally like most engines do
+                    None => {
+                        self.pos = start;
+                        return Ok(node);
+                    }
+                };
+                let max = if self.eat(',') { self.number() } else { Some(min) };
+                if !self.eat('}') {
+                    return Err(self.error("unclosed counted repetition"));
+                }
+                if max.is_some_and(|max| max < min) {
+                    return Err(self.error("invalid counted repetition"));
+                }
+                self.pos -= 1;
+                (min, max)
+            }
+            _ => return Ok(node),
+        };
+        self.pos += 1;
+        if matches!(node, Node::Start | Node::End | Node::WordBoundary(_)) {
+            return Err(self.error("an anchor cannot be repeated"));
+        }
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat { node: Box::new(node), min, max, greedy })
+    }
+
+    fn escape(&mut self) -> ioResult<Node> {
+        let c = self.peek().ok_or_else(|| self.error("trailing backslash"))?;
+        self.pos += 1;
+        Ok(match c {
+            'd' => Node::Class(DIGIT.to_vec(), false),
+            'D' => Node::Class(DIGIT.to_vec(), true),
+            'w' => Node::Class(WORD.to_vec(), false),
+            'W' => Node::Class(WORD.to_vec(), true),
+            's' => Node::Class(SPACE.to_vec(), false),
+            'S' => Node::Class(SPACE.to_vec(), true),
+            'b' => Node::WordBoundary(true),
+            'B' => Node::WordBoundary(false),
+            'n' => Node::Char('\n'),
+            't' => Node::Char('\t'),
+            'r' => Node::Char('\r'),
+            c if c.is_alphanumeric() => return Err(self.error(&format!("unknown escape \\{}", c))),
+            c => Node::Char(c),
+        })
+    }
+
+    fn class(&mut self) -> ioResult<Node> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or_else(|| self.error("unclosed character class"))?;
+            self.pos += 1;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let lo = if c == '\\' {
+                match self.escape()? {
+                    Node::Char(c) => c,
+                    Node::Class(class, false) => {
+                        ranges.extend(class);
+                        continue;
+                    }
+                    _ => return Err(self.error("only plain classes like \\d \\w \\s can be used inside [...]")),
+                }
+            } else {
+                c
+            };
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']') {
+                self.pos += 1;
+                let hi = self.peek().unwrap();
+                self.pos += 1;
+                if hi < lo {
+                    return Err(self.error("invalid range in character class"));
+                }
+                ranges.push((lo, hi));
+            } else {
+                ranges.push((lo, lo));
+            }
+        }
+        Ok(Node::Class(ranges, negated))
+    }
+
+    fn atom(&mut self) -> ioResult<Node> {
+        let c = self.peek().unwrap();
+        self.pos += 1;
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '\\' => self.escape()?,
+            '[' => self.class()?,
+            '(' => {
+                let index = if self.eat('?') {
+                    if !self.eat(':') {
+                        return Err(self.error("only (?: ) groups and a leading (?i) are supported"));
+                    }
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups)
+                };
+                let inner = self.alternation()?;
+                if !self.eat(')') {
+                    return Err(self.error("unclosed group"));
+                }
+                Node::Group(Box::new(inner), index)
+            }
+            '*' | '+' | '?' => return Err(self.error(&format!("nothing to repeat before '{}'", c))),
+            c => Node::Char(c),
+        })
+    }
+}
+
+fn compile(node: &Node, prog: &mut Vec<Inst>) {
+    match node {
+        Node::Char(c) => prog.push(Inst::Char(*c)),
+        Node::Any => prog.push(Inst::Any),
+        Node::Class(ranges, negated) => prog.push(Inst::Class(ranges.clone(), *negated)),
+        Node::Start => prog.push(Inst::Start),
+        Node::End => prog.push(Inst::End),
+        Node::WordBoundary(b) => prog.push(Inst::WordBoundary(*b)),
+        Node::Concat(nodes) => nodes.iter().for_each(|node| compile(node, prog)),
+        Node::Group(inner, None) => compile(inner, prog),
+        Node::Group(inner, Some(index)) => {
+            prog.push(Inst::Save(2 * index));
+            compile(inner, prog);
+            prog.push(Inst::Save(2 * index + 1));
+        }
+        Node::Alternate(branches) => {
+            let mut jumps = Vec::new();
+            for (i, branch) in branches.iter().enumerate() {
+                if i + 1 < branches.len() {
+                    let split = prog.len();
+                    prog.push(Inst::Split(split + 1, 0));
+                    compile(branch, prog);
+                    jumps.push(prog.len());
+                    prog.push(Inst::Jmp(0));
+                    let next = prog.len();
+                    prog[split] = Inst::Split(split + 1, next);
+                } else {
+                    compile(branch, prog);
+                }
+            }
+            let end = prog.len();
+            for jump in jumps {
+                prog[jump] = Inst::Jmp(end);
+            }
+        }
+        Node::Repeat { node, min, max, greedy } => {
+            for _ in 0..*min {
+                compile(node, prog);
+            }
+            let split = |prog: &mut Vec<Inst>, body: usize, exit: usize, at: usize| {
+                prog[at] = if *greedy { Inst::Split(body, exit) } else { Inst::Split(exit, body) };
+            };
+            match max {
+                None => {
+                    let start = prog.len();
+                    prog.push(Inst::Match);
+                    compile(node, prog);
+                    prog.push(Inst::Jmp(start));
+                    let end = prog.len();
+                    split(prog, start + 1, end, start);
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(prog.len());
+                        prog.push(Inst::Match);
+                        compile(node, prog);
+                    }
+                    let end = prog.len();
+                    for at in splits {
+                        split(prog, at + 1, end, at);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_word(c: Option<&char>) -> bool {
+    c.is_some_and(|c| c.is_alphanumeric() || *c == '_')
+}
+
+/// A compiled regular expression
+/// # Examples
+/// ```
+/// use rusty_journal_clap::regex::Regex;
+/// let re = Regex::new(r"^(?i)todo:?\s*").unwrap();
+/// assert_eq!(re.replace_all("TODO: buy milk", ""), "buy milk");
+/// let re = Regex::new(r"(\w+)@(\w+)\.com").unwrap();
+/// assert_eq!(re.replace_all("mail bob@example.com", "$2 ($1)"), "mail example (bob)");
+/// assert!(Regex::new("(unclosed").is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Regex {
+    prog: Vec<Inst>,
+    slots: usize,
+    case_insensitive: bool,
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> ioResult<Regex> {
+        let (case_insensitive, body) = match pattern.strip_prefix("(?i)") {
+            Some(body) => (true, body),
+            None => (false, pattern),
+        };
+        // `^(?i)...` is common enough to be accepted too
+        let (case_insensitive, body) = match body.strip_prefix("^(?i)") {
+            Some(rest) => (true, format!("^{}", rest)),
+            None => (case_insensitive, body.to_string()),
+        };
+
+        let mut parser = Parser { pattern, chars: body.chars().collect(), pos: 0, groups: 0 };
+        let node = parser.alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("unmatched ')'"));
+        }
+
+        // an unanchored search is a lazy `.*?` prefix in front of the whole pattern, i.e. group 0
+        let mut prog = vec![Inst::Split(3, 1), Inst::Any, Inst::Jmp(0)];
+        compile(&Node::Group(Box::new(node), Some(0)), &mut prog);
+        prog.push(Inst::Match);
+
+        Ok(Regex { prog, slots: 2 * (parser.groups + 1), case_insensitive })
+    }
+
+    fn char_eq(&self, a: char, b: char) -> bool {
+        a == b || (self.case_insensitive && a.to_lowercase().eq(b.to_lowercase()))
+    }
+
+    fn class_matches(&self, ranges: &[(char, char)], negated: bool, c: char) -> bool {
+        let in_class = |c: char| ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+        let found = in_class(c) || (self.case_insensitive && (c.to_lowercase().any(in_class) || c.to_uppercase().any(in_class)));
+        found != negated
+    }
+
+    /// Runs the program from the given position, returning the capture slots (in char indices) on a match
+    fn exec(&self, text: &[char], from: usize) -> Option<Vec<Option<usize>>> {
+        let width = text.len() + 1;
+        let mut visited = vec![false; self.prog.len() * width];
+        let mut slots = vec![None; self.slots];
+        // the stack holds either a thread to try, or a capture slot to restore when backtracking
+        enum Job { Try(usize, usize), Restore(usize, Option<usize>) }
+        let mut stack = vec![Job::Try(0, from)];
+
+        while let Some(job) = stack.pop() {
+            let (mut pc, mut pos) = match job {
+                Job::Restore(slot, value) => {
+                    slots[slot] = value;
+                    continue;
+                }
+                Job::Try(pc, pos) => (pc, pos),
+            };
+            loop {
+                let state = pc * width + pos;
+                if visited[state] {
+                    break;
+                }
+                visited[state] = true;
+                match &self.prog[pc] {
+                    Inst::Char(c) if text.get(pos).is_some_and(|t| self.char_eq(*t, *c)) => (pc, pos) = (pc + 1, pos + 1),
+                    Inst::Any if pos < text.len() && text[pos] != '\n' => (pc, pos) = (pc + 1, pos + 1),
+                    Inst::Class(ranges, negated) if text.get(pos).is_some_and(|t| self.class_matches(ranges, *negated, *t)) => {
+                        (pc, pos) = (pc + 1, pos + 1)
+                    }
+                    Inst::Start if pos == 0 => pc += 1,
+                    Inst::End if pos == text.len() => pc += 1,
+                    Inst::WordBoundary(expected) if (is_word(pos.checked_sub(1).and_then(|p| text.get(p))) != is_word(text.get(pos))) == *expected => pc += 1,
+                    Inst::Split(first, second) => {
+                        stack.push(Job::Try(*second, pos));
+                        pc = *first;
+                    }
+                    Inst::Jmp(target) => pc = *target,
+                    Inst::Save(slot) => {
+                        stack.push(Job::Restore(*slot, slots[*slot]));
+                        slots[*slot] = Some(pos);
+                        pc += 1;
+                    }
+                    Inst::Match => return Some(slots),
+                    _ => break,
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.captures(text).is_some()
+    }
+
+    /// Returns the byte ranges of the whole match (index 0) and of every capturing group
+    pub fn captures(&self, text: &str) -> Option<Vec<Option<(usize, usize)>>> {
+        self.captures_from(text, 0)
+    }
+
+    fn captures_from(&self, text: &str, from_byte: usize) -> Option<Vec<Option<(usize, usize)>>> {
+        let chars: Vec<char> = text.chars().collect();
+        let offsets: Vec<usize> = text.char_indices()
+                                      .map(|(i, _)| i)
+                                      .chain(std::iter::once(text.len()))
+                                      .collect();
+        let from = offsets.iter().position(|&offset| offset >= from_byte)?;
+        let slots = self.exec(&chars, from)?;
+        Some(slots.chunks(2)
+                  .map(|pair| match pair {
+                      [Some(start), Some(end)] => Some((offsets[*start], offsets[*end])),
+                      _ => None,
+                  })
+                  .collect())
+    }
+
+    /// Replaces every non-overlapping match, where `$0`..`$9` and `${n}` in the
+    /// replacement refer to the capturing groups and `$$` is a literal dollar sign
+    pub fn replace_all(&self, text: &str, replacement: &str) -> String {
+        let mut result = String::new();
+        let mut last = 0;
+        let mut from = 0;
+        let mut previous_end = None;
+
+        while from <= text.len() {
+            let groups = match self.captures_from(text, from) {
+                Some(groups) => groups,
+                None => break,
+            };
+            let (start, end) = groups[0].unwrap();
+            // same as other engines, an empty match right after the previous match is not replaced
+            if start != end || previous_end != Some(start) {
+                result.push_str(&text[last..start]);
+                Self::expand(replacement, &groups, text, &mut result);
+                last = end;
+                previous_end = Some(end);
+            }
+            // an empty match must not be found again at the same position
+            from = if end > start {
+                end
+            } else {
+                match text[end..].chars().next() {
+                    Some(c) => end + c.len_utf8(),
+                    None => break,
+                }
+            };
+        }
+
+        result.push_str(&text[last..]);
+        result
+    }
+
+    fn expand(replacement: &str, groups: &[Option<(usize, usize)>], text: &str, out: &mut String) {
+        let mut chars = replacement.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '$' {
+                out.push(c);
+                continue;
+            }
+            let index = match chars.peek() {
+                Some('$') => {
+                    chars.next();
+                    out.push('$');
+                    continue;
+                }
+                Some('{') => {
+                    chars.next();
+                    let digits: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                    digits.parse::<usize>().ok()
+                }
+                Some(d) if d.is_ascii_digit() => {
+                    let index = d.to_digit(10).map(|d| d as usize);
+                    chars.next();
+                    index
+                }
+                _ => {
+                    out.push('$');
+                    continue;
+                }
+            };
+            if let Some(Some((start, end))) = index.and_then(|index| groups.get(index)) {
+                out.push_str(&text[*start..*end]);
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::io::{Error, Result as ioResult};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+use crate::{clock, error::ParseFailure, import, normalize::Normalizer, output, task::{Priority, Task}};
+
+// Comment: neither app has an export of its own. Apple Reminders is read in the JSON of reminders-cli
+// (`reminders show-all --format json`), Microsoft To Do in the JSON of the Graph API, its lists with their tasks
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Reminder {
+    title: String,
+    #[serde(default)]
+    list: Option<String>,
+    #[serde(default)]
+    is_completed: bool,
+    #[serde(default)]
+    completion_date: Option<String>,
+    #[serde(default)]
+    due_date: Option<String>,
+    #[serde(default)]
+    external_id: Option<String>,
+    // 0 for none, 1 to 4 high, 5 medium and 6 to 9 low, as in EventKit
+    #[serde(default)]
+    priority: u8,
+}
+
+fn invalid(err: impl ToString) -> Error {
+    ParseFailure::of(err.to_string())
+}
+
+fn parse_rfc3339(value: &str) -> ioResult<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).map(|time| time.with_timezone(&Utc))
+                                       .map_err(|_| invalid(format!("Invalid date '{}'", value)))
+}
+
+/// Converts the reminders exported from Apple Reminders by reminders-cli into tasks. The list of a reminder
+/// becomes its tag, and its externalId is kept so importing the same export again skips the tasks already in the journal
+/// # Examples
+/// ```
+/// use rusty_journal_clap::{normalize::{Normalizer, Rules}, reminders};
+/// let normalizer = Normalizer::new(&Rules::default()).unwrap();
+/// let export = r#"[{"title": "File taxes", "list": "Home", "isCompleted": false, "dueDate": "2024-04-15T17:00:00Z", "priority": 1}]"#;
+/// let tasks = reminders::from_apple_reminders(export, &normalizer).unwrap();
+/// assert_eq!(tasks[0].tags(), ["Home"]);
+/// assert_eq!(tasks[0].due().unwrap().to_rfc3339(), "2024-04-15T17:00:00+00:00");
+/// ```
+pub fn from_apple_reminders(content: &str, normalizer: &Normalizer) -> ioResult<Vec<Task>> {
+    let now = clock::now();
+    let reminders: Vec<Reminder> = serde_json::from_str(content).map_err(invalid)?;
+
+    let mut tasks = Vec::new();
+    for reminder in reminders {
+        let name = normalizer.name(&reminder.title);
+        if name.is_empty() {
+            continue;
+        }
+        let mut task = Task::new(name, normalizer.tags(Some(reminder.list.into_iter().collect())), now);
+        if let Some(due) = &reminder.due_date {
+            task.set_due(Some(parse_rfc3339(due)?));
+        }
+        task.set_priority(match reminder.priority {
+            1..=4 => Some(Priority::High),
+            5 => Some(Priority::Medium),
+            6..=9 => Some(Priority::Low),
+            _ => None,
+        });
+        if let Some(uid) = reminder.external_id.filter(|uid| !uid.is_empty()) {
+            task.set_extra(import::UID_FIELD, uid);
+        }
+        if reminder.is_completed {
+            let completed_at = match &reminder.completion_date {
+                Some(completed) => parse_rfc3339(completed)?,
+                None => now,
+            };
+            task.completed(completed_at);
+        }
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TodoList {
+    display_name: String,
+    tasks: TodoTasks,
+}
+
+// Comment: the Graph API wraps collections in a `value` field, which an export may have kept or left out
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TodoTasks {
+    Wrapped { value: Vec<TodoTask> },
+    Plain(Vec<TodoTask>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TodoTask {
+    #[serde(default)]
+    id: Option<String>,
+    title: String,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    importance: Option<String>,
+    #[serde(default)]
+    due_date_time: Option<GraphTime>,
+    #[serde(default)]
+    completed_date_time: Option<GraphTime>,
+    #[serde(default)]
+    categories: Vec<String>,
+}
+
+/// A dateTimeTimeZone of the Graph API, e.g. `{"dateTime": "2024-04-15T00:00:00.0000000", "timeZone": "UTC"}`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphTime {
+    date_time: String,
+    time_zone: String,
+}
+
+impl GraphTime {
+    /// Like the TZID of iCalendar, a time zone other than UTC can't be honoured without a time zone database
+    /// and the time is read as local time
+    fn timestamp(&self) -> ioResult<DateTime<Utc>> {
+        let invalid_date = || invalid(format!("Invalid date '{}'", self.date_time));
+        let naive = NaiveDateTime::parse_from_str(&self.date_time, "%Y-%m-%dT%H:%M:%S%.f").map_err(|_| invalid_date())?;
+        match self.time_zone.as_str() {
+            "UTC" => Ok(naive.and_utc()),
+            _ => output::from_local(naive).ok_or_else(invalid_date),
+        }
+    }
+}
+
+/// Converts the lists exported from Microsoft To Do through the Graph API into tasks: an array of lists with their
+/// `displayName` and `tasks`. The name of the list and the categories of a task become its tags, and its id is kept
+/// so importing the same export again skips the tasks already in the journal
+/// # Examples
+/// ```
+/// use rusty_journal_clap::{normalize::{Normalizer, Rules}, reminders};
+/// let normalizer = Normalizer::new(&Rules::default()).unwrap();
+/// let export = r#"[{"displayName": "Work", "tasks": {"value": [{"id": "AAMk", "title": "Review pull requests", "status": "completed",
+///                   "completedDateTime": {"dateTime": "2024-04-15T00:00:00.0000000", "timeZone": "UTC"}}]}}]"#;
+/// let tasks = reminders::from_ms_todo(export, &normalizer).unwrap();
+/// assert_eq!(tasks[0].tags(), ["Work"]);
+/// assert_eq!(tasks[0].completed_at().unwrap().to_rfc3339(), "2024-04-15T00:00:00+00:00");
+/// ```
+pub fn from_ms_todo(content: &str, normalizer: &Normalizer) -> ioResult<Vec<Task>> {
+    let now = clock::now();
+    let lists: Vec<TodoList> = serde_json::from_str(content).map_err(invalid)?;
+
+    let mut tasks = Vec::new();
+    for list in lists {
+        let list_tasks = match list.tasks {
+            TodoTasks::Wrapped { value } => value,
+            TodoTasks::Plain(tasks) => tasks,
+        };
+        for todo in list_tasks {
+            let name = normalizer.name(&todo.title);
+            if name.is_empty() {
+                continue;
+            }
+            let tags = std::iter::once(list.display_name.clone()).chain(todo.categories).collect();
+            let mut task = Task::new(name, normalizer.tags(Some(tags)), now);
+            if let Some(due) = &todo.due_date_time {
+                task.set_due(Some(due.timestamp()?));
+            }
+            task.set_priority(match todo.importance.as_deref() {
+                Some("high") => Some(Priority::High),
+                Some("low") => Some(Priority::Low),
+                // normal is the default of every task, and says nothing about it
+                _ => None,
+            });
+            if let Some(uid) = todo.id.filter(|uid| !uid.is_empty()) {
+                task.set_extra(import::UID_FIELD, uid);
+            }
+            if todo.status.as_deref() == Some("completed") {
+                let completed_at = match &todo.completed_date_time {
+                    Some(completed) => completed.timestamp()?,
+                    None => now,
+                };
+                task.completed(completed_at);
+            }
+            tasks.push(task);
+        }
+    }
+    Ok(tasks)
+}
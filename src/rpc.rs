@@ -0,0 +1,253 @@
+use std::path::PathBuf;
+use std::io::{BufRead, Write, Result as ioResult};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use crate::clock;
+use crate::error::JournalError;
+use crate::task::{Task, TaskEdit, TaskOptions};
+use crate::trash;
+use crate::undo;
+use crate::watch::Watcher;
+
+// Error codes as defined by the JSON-RPC 2.0 specification: https://www.jsonrpc.org/specification#error_object
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// the range -32000 to -32099 is reserved for implementation-defined server errors
+const JOURNAL_ERROR: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+    // a request without an id is a notification, which must not be answered
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct AddParams {
+    name: String,
+    tags: Option<Vec<String>>,
+    owner: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ListParams {
+    tag: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct IndexParams {
+    index: usize,
+}
+
+#[derive(Deserialize)]
+struct EditParams {
+    index: usize,
+    name: Option<String>,
+    #[serde(default)]
+    add_tags: Vec<String>,
+    #[serde(default)]
+    remove_tags: Vec<String>,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        RpcError { code, message: message.to_string() }
+    }
+}
+
+fn params<'a, T: Deserialize<'a>>(params: &'a Value) -> Result<T, RpcError> {
+    T::deserialize(params).map_err(|err| RpcError::new(INVALID_PARAMS, err))
+}
+
+/// A JSON-RPC 2.0 server for the journal file. Every request reads the journal as it is at the time,
+/// and every mutating request changes it under the journal lock, same as the commands of the command line.
+///
+/// Supported methods are `add` (`name`, optional `tags` and `owner`), `list` (optional `tag`), `count` (optional `tag`),
+/// `complete` (`index`), `remove` (`index`) and `edit` (`index`, optional `name`, `add_tags`, `remove_tags`).
+/// Indices are 1-based, same as on the command line.
+/// # Examples
+/// ```
+/// use rusty_journal_clap::rpc::RpcServer;
+/// use rusty_journal_clap::test_util::TempJournal;
+/// let journal = TempJournal::seeded().unwrap();
+/// let mut server = RpcServer::new(journal.path()).unwrap();
+/// let response = server.handle(r#"{"jsonrpc": "2.0", "id": 1, "method": "list"}"#).unwrap();
+/// assert!(response.contains(r#""result""#));
+/// ```
+pub struct RpcServer {
+    journal_path: PathBuf,
+    // the tasks as of the last request
+    tasks: Vec<Task>,
+    // a resident server reads the journal again only when it changed on disk
+    watcher: Option<Watcher>,
+}
+
+impl RpcServer {
+    pub fn new(journal_path: PathBuf) -> ioResult<Self> {
+        // the journal is read once up front, so that a journal that can't be read fails the session right away
+        let tasks = Task::_load_tasks(&journal_path)?;
+        Ok(RpcServer { journal_path, tasks, watcher: None })
+    }
+
+    /// A server keeping the tasks in memory, reading the journal file again only when another process changed it.
+    /// The mutating requests change the tasks in memory and write them to the journal, under its lock
+    pub fn resident(journal_path: PathBuf) -> ioResult<Self> {
+        // Comment: the watcher is set up before reading, so that a change made in between is read on the first request
+        let watcher = Watcher::new(&journal_path);
+        let tasks = Task::_load_tasks(&journal_path)?;
+        Ok(RpcServer { journal_path, tasks, watcher: Some(watcher) })
+    }
+
+    /// Serves one request per line from the input until it is exhausted, writing one response per line
+    pub fn serve(&mut self, input: impl BufRead, mut output: impl Write) -> ioResult<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle(&line) {
+                writeln!(output, "{}", response)?;
+                // editors wait for the response before sending the next request
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles a single serialized request, returning the serialized response
+    /// or None if the request was a notification
+    pub fn handle(&mut self, request: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(request) {
+            Ok(request) => request,
+            Err(err) => return Some(Self::response(Value::Null, Err(RpcError::new(PARSE_ERROR, err)))),
+        };
+
+        let request = match Request::deserialize(&request) {
+            Ok(request) => request,
+            Err(err) => {
+                let id = request.get("id").cloned().unwrap_or(Value::Null);
+                return Some(Self::response(id, Err(RpcError::new(INVALID_REQUEST, err))));
+            }
+        };
+
+        let result = self.dispatch(&request.method, &request.params);
+
+        request.id.map(|id| Self::response(id, result))
+    }
+
+    fn response(id: Value, result: Result<Value, RpcError>) -> String {
+        let response = match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(err) => json!({"jsonrpc": "2.0", "id": id, "error": {"code": err.code, "message": err.message}}),
+        };
+        response.to_string()
+    }
+
+    fn dispatch(&mut self, method: &str, params_value: &Value) -> Result<Value, RpcError> {
+        // Comment: the changes made for the clients can be undone like the ones of the command line, under the method name
+        undo::set_command(Some(method));
+        match method {
+            "list" => {
+                let list_params: ListParams = if params_value.is_null() {
+                    ListParams { tag: None }
+                } else {
+                    params(params_value)?
+                };
+                let tasks = self.tasks()?.iter()
+                                         .enumerate()
+                                         .filter(|(_, task)| list_params.tag
+                                                                        .as_ref()
+                                                                        .is_none_or(|tag| task.has_tag(tag)))
+                                         .map(|(i, task)| Self::task_json(i + 1, task))
+                                         .collect::<Vec<_>>();
+                Ok(Value::Array(tasks))
+            }
+            "count" => {
+                let count_params: ListParams = if params_value.is_null() {
+                    ListParams { tag: None }
+                } else {
+                    params(params_value)?
+                };
+                // Comment: a resident server may hold waiting tasks past their time, which count as active, c.f. `Task::count`
+                let now = clock::now();
+                let count = self.tasks()?.iter()
+                                         .filter(|task| !task.is_complete() && task.waiting_until().is_none_or(|until| until <= now))
+                                         .filter(|task| count_params.tag.as_ref().is_none_or(|tag| task.has_tag(tag)))
+                                         .count();
+                Ok(json!(count))
+            }
+            "add" => {
+                let add_params: AddParams = params(params_value)?;
+                let options = TaskOptions { owner: add_params.owner, ..TaskOptions::default() };
+                let index = self.update(|tasks| Task::_add(tasks, add_params.name, add_params.tags, &options, clock::now()))?;
+                Ok(json!({"index": index}))
+            }
+            "complete" => {
+                let index = params::<IndexParams>(params_value)?.index;
+                self.update(|tasks| {
+                    Task::_complete_at(tasks, &[index], clock::now())?;
+                    Ok(Self::task_json(index, &tasks[index - 1]))
+                })
+            }
+            "remove" => {
+                let index = params::<IndexParams>(params_value)?.index;
+                let journal_path = self.journal_path.clone();
+                self.update(|tasks| {
+                    Task::_check_index(tasks, index)?;
+                    let removed_json = Self::task_json(index, &tasks[index - 1]);
+                    let removed = Task::_remove_at(tasks, &[index])?;
+                    trash::put(&journal_path, removed, tasks, clock::now())?;
+                    Ok(removed_json)
+                })
+            }
+            "edit" => {
+                let edit_params: EditParams = params(params_value)?;
+                let index = edit_params.index;
+                let edit = TaskEdit { name: edit_params.name, add_tags: edit_params.add_tags, remove_tags: edit_params.remove_tags, due: None };
+                self.update(|tasks| {
+                    Task::_check_index(tasks, index)?;
+                    edit.apply(&mut tasks[index - 1], clock::now());
+                    Ok(Self::task_json(index, &tasks[index - 1]))
+                })
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+        }
+    }
+
+    /// The tasks as they are in the journal now, which another process may have changed since the last request
+    fn tasks(&mut self) -> Result<&[Task], RpcError> {
+        if self.watcher.as_mut().is_none_or(|watcher| watcher.changed()) {
+            self.tasks = Task::_load_tasks(&self.journal_path).map_err(|err| RpcError::new(JOURNAL_ERROR, err))?;
+        }
+        Ok(&self.tasks)
+    }
+
+    /// Applies the change to the journal as it is now, holding its lock from reading it to writing it back,
+    /// so that the changes made by other processes in the meantime aren't lost. A resident server changes the tasks
+    /// it keeps, which it only reads again when the journal changed on disk
+    fn update<T>(&mut self, update: impl FnOnce(&mut Vec<Task>) -> ioResult<T>) -> Result<T, RpcError> {
+        let updated = match self.watcher.as_mut() {
+            Some(watcher) => Task::_update_resident(&self.journal_path, &mut self.tasks, watcher, update),
+            None => Task::_update_tasks(&self.journal_path, update),
+        };
+        updated.map_err(|err| match err {
+            JournalError::IndexOutOfRange { .. } => RpcError::new(INVALID_PARAMS, err),
+            _ => RpcError::new(JOURNAL_ERROR, err),
+        })
+    }
+
+    fn task_json(index: usize, task: &Task) -> Value {
+        let mut value = json!(task);
+        value["index"] = json!(index);
+        value
+    }
+}
@@ -0,0 +1,128 @@
+use serde_json::{json, Value};
+
+/// The id of the schema, editors use it to tell schema versions apart. A journal is a plain array,
+/// so it can't point to its schema itself and the editor has to be configured to use it
+pub const SCHEMA_ID: &str = "urn:rusty-journal-clap:journal:1";
+
+/// The JSON Schema (draft 2020-12) of the journal file, to be kept in sync with the serde
+/// attributes of `Task` and its state
+/// # Examples
+/// ```
+/// use rusty_journal_clap::schema;
+/// let schema = schema::journal();
+/// assert_eq!(schema["type"], "array");
+/// assert!(schema["$defs"]["task"]["properties"]["name"].is_object());
+/// ```
+pub fn journal() -> Value {
+    let timestamp = json!({
+        "type": "integer",
+        "description": "Seconds since the Unix epoch, in UTC",
+    });
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": SCHEMA_ID,
+        "title": "rusty-journal-clap journal",
+        "description": "The tasks of a journal, in the order they are listed",
+        "type": "array",
+        "items": { "$ref": "#/$defs/task" },
+        "$defs": {
+            "task": {
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Stable identifier of the task, missing in journals written before ids were introduced",
+                    },
+                    "name": { "type": "string" },
+                    "state": { "$ref": "#/$defs/state" },
+                    "tags": {
+                        "type": ["array", "null"],
+                        "items": { "type": "string" },
+                        "uniqueItems": true,
+                        "description": "null for a task without tags",
+                    },
+                    // the misspelling is part of the format, c.f. the creted_at field of Task
+                    "creted_at": timestamp,
+                    "updated_at": timestamp,
+                    "due": timestamp,
+                    "priority": { "enum": ["low", "medium", "high"] },
+                    "owner": { "type": "string", "description": "The user who added the task" },
+                    "assignee": { "type": "string", "description": "The user the task is assigned to" },
+                    "delegated_to": { "type": "string", "description": "The person the task was handed to" },
+                    "goal": { "type": "string", "description": "The name of the goal the task contributes to" },
+                    "related": {
+                        "type": "array",
+                        "items": { "type": "integer", "minimum": 0 },
+                        "description": "The ids of the related tasks, missing when there are none",
+                    },
+                    "repeat": {
+                        "type": "string",
+                        "pattern": "^(daily|weekly|monthly|every ([1-9][0-9]* )?(day|week|month)s?)$",
+                        "description": "How often the task comes back, e.g. weekly or every 3 days",
+                    },
+                    "repeat_day": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "maximum": 31,
+                        "description": "The day of the month a monthly repetition falls on, when a shorter month moved the due date before it",
+                    },
+                    "parent": { "type": "integer", "minimum": 0, "description": "The id of the task this one is a subtask of" },
+                    "estimate": { "type": "integer", "minimum": 0, "description": "How long doing the task should take, in minutes" },
+                    "history": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/history_entry" },
+                        "description": "Changes and notes, oldest first, missing when there are none",
+                    },
+                    "extra": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" },
+                        "description": "Fields carried over from the source the task was imported from, e.g. its uid",
+                    },
+                },
+                "required": ["name", "state", "creted_at"],
+                "additionalProperties": false,
+            },
+            "history_entry": {
+                "type": "object",
+                "properties": {
+                    "at": timestamp,
+                    "kind": { "enum": ["state", "edit", "note"] },
+                    "text": { "type": "string" },
+                },
+                "required": ["at", "kind", "text"],
+                "additionalProperties": false,
+            },
+            "state": {
+                "oneOf": [
+                    {
+                        "type": "object",
+                        "properties": { "type": { "const": "Active" } },
+                        "required": ["type"],
+                        "additionalProperties": false,
+                    },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "type": { "const": "Complete" },
+                            "completed_at": timestamp,
+                        },
+                        "required": ["type", "completed_at"],
+                        "additionalProperties": false,
+                    },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "type": { "const": "Waiting" },
+                            "until": timestamp,
+                            "reason": { "type": "string" },
+                        },
+                        "required": ["type", "until"],
+                        "additionalProperties": false,
+                    },
+                ],
+            },
+        },
+    })
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Result as fmtResult};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use crate::i18n::tr;
+use crate::task::{Priority, Task};
+
+// the age part stops growing after a year
+const AGE_MAX_DAYS: f64 = 365.0;
+
+/// The weights of the parts of a score, set under `urgency` in the config. Every weight is optional,
+/// the defaults follow Taskwarrior's urgency coefficients, so a high priority task due in a few days
+/// beats an old one without priority, and an overdue task beats everything.
+/// ```json
+/// {
+///     "urgency": {
+///         "due": 15.0,
+///         "tag": {"next": 15.0, "blocked": -5.0}
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Coefficients {
+    pub priority_high: f64,
+    pub priority_medium: f64,
+    pub priority_low: f64,
+    pub due: f64,
+    pub age: f64,
+    /// Scaled by the number of tags, from 0.8 for one tag to 1 for three tags or more
+    pub tags: f64,
+    /// Added for each tag of the task, e.g. a negative weight for `blocked` or `someday`
+    pub tag: BTreeMap<String, f64>,
+}
+
+impl Default for Coefficients {
+    fn default() -> Self {
+        Coefficients {
+            priority_high: 6.0,
+            priority_medium: 3.9,
+            priority_low: 1.8,
+            due: 12.0,
+            age: 2.0,
+            tags: 1.0,
+            // Comment: the journal has no dependencies between tasks, the blocked ones are tagged as such
+            tag: BTreeMap::from([("blocked".to_string(), -5.0)]),
+        }
+    }
+}
+
+/// One part of a score: its name, the points it adds and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    pub name: &'static str,
+    pub points: f64,
+    pub reason: String,
+}
+
+/// How important doing a task now is, as the sum of its components
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score {
+    pub components: Vec<Component>,
+}
+
+impl Score {
+    pub fn total(&self) -> f64 {
+        self.components.iter().map(|component| component.points).sum()
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut Formatter) -> fmtResult {
+        writeln!(f, "Score {:.2}", self.total())?;
+        for component in &self.components {
+            writeln!(f, "  {:<10} {:>6.2}  ({})", component.name, component.points, component.reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// The due part grows from 0.2 two weeks ahead of the due date to 1 a week past it
+fn due_factor(days_left: f64) -> f64 {
+    if days_left >= 14.0 {
+        0.2
+    } else if days_left <= -7.0 {
+        1.0
+    } else {
+        (14.0 - days_left) * 0.8 / 21.0 + 0.2
+    }
+}
+
+/// The tags part grows with the number of tags, the way Taskwarrior's does
+fn tags_factor(count: usize) -> f64 {
+    match count {
+        0 => 0.0,
+        1 => 0.8,
+        2 => 0.9,
+        _ => 1.0,
+    }
+}
+
+/// Scores the task at `now` from its priority, how close its due date is, its age and its tags,
+/// weighted by the coefficients. Parts that don't apply, e.g. the due date of a task without one, are left out
+/// # Examples
+/// ```
+/// use rusty_journal_clap::score::{score, Coefficients};
+/// use rusty_journal_clap::test_util::TempJournal;
+/// use chrono::Duration;
+/// let tasks = TempJournal::seeded().unwrap().tasks().unwrap();
+/// let score = score(&tasks[0], tasks[0].created_at() + Duration::days(73), &Coefficients::default());
+/// assert_eq!(score.components[0].name, "age");
+/// assert!((score.total() - 1.2).abs() < 1e-9);
+/// ```
+pub fn score(task: &Task, now: DateTime<Utc>, coefficients: &Coefficients) -> Score {
+    let mut components = Vec::new();
+
+    if let Some(priority) = task.priority() {
+        let points = match priority {
+            Priority::High => coefficients.priority_high,
+            Priority::Medium => coefficients.priority_medium,
+            Priority::Low => coefficients.priority_low,
+        };
+        components.push(Component { name: "priority", points, reason: priority.name().to_string() });
+    }
+
+    if let Some(due) = task.due() {
+        let left = due - now;
+        let key = if left.num_seconds() < 0 { "overdue-by" } else { "due-in" };
+        let reason = tr(key, &[("days", &left.num_days().abs())]);
+        let points = coefficients.due * due_factor(left.num_seconds() as f64 / 86_400.0);
+        components.push(Component { name: "due", points, reason });
+    }
+
+    let age_days = (now - task.created_at()).num_days().max(0);
+    let points = coefficients.age * (age_days as f64).min(AGE_MAX_DAYS) / AGE_MAX_DAYS;
+    components.push(Component { name: "age", points, reason: tr("age", &[("days", &age_days)]) });
+
+    let tags = task.tags();
+    if !tags.is_empty() {
+        let points = coefficients.tags * tags_factor(tags.len());
+        components.push(Component { name: "tags", points, reason: tr("tag-count", &[("count", &tags.len())]) });
+    }
+    for tag in tags {
+        if let Some(&points) = coefficients.tag.get(tag) {
+            components.push(Component { name: "tag", points, reason: tag.to_string() });
+        }
+    }
+
+    Score { components }
+}
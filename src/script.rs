@@ -0,0 +1,512 @@
+use std::{collections::HashMap, fmt, fs, path::Path};
+use std::io::{Error, ErrorKind, Result as ioResult};
+use crate::clock;
+use crate::error::Context;
+use crate::task::Task;
+
+// Comment: the scripting language is intentionally tiny and borrows its surface syntax from rhai
+// (`&&`, `||`, `if cond { a } else { b }`, method-call sugar like `tags.contains("x")`) so that
+// a script stays readable to anyone who has seen rhai or JavaScript. A script is a single expression
+// evaluated once per task:
+//   - evaluating to a bool makes the script a filter, and matching tasks are printed as usual
+//   - evaluating to a string/number makes the script a report generator, and the value is printed
+//     in place of the default task line
+//   - evaluating to () (e.g. an `if` without `else` whose condition is false) skips the task
+
+/// A value produced while evaluating a script
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Unit,
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    Array(Vec<Value>),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Unit => Ok(()),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Unit => "()",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Str(_) => "string",
+            Value::Array(_) => "array",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Int(i64),
+    Op(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    Add,
+    Sub,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Lit(Value),
+    Var(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Array(Vec<Expr>),
+    If(Box<Expr>, Box<Expr>, Option<Box<Expr>>),
+}
+
+fn script_error(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, format!("Script error: {}", msg))
+}
+
+fn tokenize(source: &str) -> ioResult<Vec<Token>> {
+    // longer operators first so that e.g. "<=" is not split into "<" and "="
+    const OPS: [&str; 20] = ["||", "&&", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-",
+                             "(", ")", "{", "}", "[", "]", ",", ".", ";"];
+
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    'outer: while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '"' {
+            let mut s = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(script_error("unterminated string literal".to_string())),
+                    Some('"') => break,
+                    Some('\\') => {
+                        match chars.get(i + 1) {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some(&other) => s.push(other),
+                            None => return Err(script_error("unterminated string literal".to_string())),
+                        }
+                        i += 1;
+                    }
+                    Some(&other) => s.push(other),
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push(Token::Str(s));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits: String = chars[start..i].iter().collect();
+            let n = digits.parse::<i64>()
+                          .map_err(|_| script_error(format!("integer literal {} is too large", digits)))?;
+            tokens.push(Token::Int(n));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            for op in OPS {
+                let len = op.chars().count();
+                if chars[i..].iter().take(len).copied().eq(op.chars()) {
+                    tokens.push(Token::Op(op));
+                    i += len;
+                    continue 'outer;
+                }
+            }
+            return Err(script_error(format!("unexpected character '{}'", c)));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Op(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_ident(&mut self, ident: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(i)) if i == ident) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_op(&mut self, op: &str) -> ioResult<()> {
+        if self.eat_op(op) {
+            Ok(())
+        } else {
+            Err(script_error(format!("expected '{}' but found {}", op, self.describe_next())))
+        }
+    }
+
+    fn describe_next(&self) -> String {
+        match self.peek() {
+            None => "end of script".to_string(),
+            Some(Token::Ident(i)) => format!("'{}'", i),
+            Some(Token::Str(s)) => format!("\"{}\"", s),
+            Some(Token::Int(n)) => n.to_string(),
+            Some(Token::Op(o)) => format!("'{}'", o),
+        }
+    }
+
+    fn expr(&mut self) -> ioResult<Expr> {
+        let mut lhs = self.and()?;
+        while self.eat_op("||") {
+            lhs = Expr::Binary(BinOp::Or, Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> ioResult<Expr> {
+        let mut lhs = self.comparison()?;
+        while self.eat_op("&&") {
+            lhs = Expr::Binary(BinOp::And, Box::new(lhs), Box::new(self.comparison()?));
+        }
+        Ok(lhs)
+    }
+
+    fn comparison(&mut self) -> ioResult<Expr> {
+        let lhs = self.additive()?;
+        let op = match self.peek() {
+            Some(Token::Op("==")) => BinOp::Eq,
+            Some(Token::Op("!=")) => BinOp::Ne,
+            Some(Token::Op("<")) => BinOp::Lt,
+            Some(Token::Op("<=")) => BinOp::Le,
+            Some(Token::Op(">")) => BinOp::Gt,
+            Some(Token::Op(">=")) => BinOp::Ge,
+            Some(Token::Ident(i)) if i == "in" => BinOp::In,
+            _ => return Ok(lhs),
+        };
+        self.pos += 1;
+        Ok(Expr::Binary(op, Box::new(lhs), Box::new(self.additive()?)))
+    }
+
+    fn additive(&mut self) -> ioResult<Expr> {
+        let mut lhs = self.unary()?;
+        loop {
+            if self.eat_op("+") {
+                lhs = Expr::Binary(BinOp::Add, Box::new(lhs), Box::new(self.unary()?));
+            } else if self.eat_op("-") {
+                lhs = Expr::Binary(BinOp::Sub, Box::new(lhs), Box::new(self.unary()?));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> ioResult<Expr> {
+        if self.eat_op("!") {
+            Ok(Expr::Not(Box::new(self.unary()?)))
+        } else if self.eat_op("-") {
+            Ok(Expr::Neg(Box::new(self.unary()?)))
+        } else {
+            self.postfix()
+        }
+    }
+
+    fn postfix(&mut self) -> ioResult<Expr> {
+        let mut expr = self.primary()?;
+        // method-call sugar: `x.f(a, b)` is the same as `f(x, a, b)`
+        while self.eat_op(".") {
+            let name = match self.next() {
+                Some(Token::Ident(name)) => name,
+                _ => return Err(script_error("expected a method name after '.'".to_string())),
+            };
+            self.expect_op("(")?;
+            let mut args = vec![expr];
+            args.extend(self.list(")")?);
+            expr = Expr::Call(name, args);
+        }
+        Ok(expr)
+    }
+
+    fn list(&mut self, close: &str) -> ioResult<Vec<Expr>> {
+        let mut items = Vec::new();
+        if self.eat_op(close) {
+            return Ok(items);
+        }
+        loop {
+            items.push(self.expr()?);
+            if self.eat_op(close) {
+                return Ok(items);
+            }
+            self.expect_op(",")?;
+        }
+    }
+
+    fn block(&mut self) -> ioResult<Expr> {
+        self.expect_op("{")?;
+        let body = self.expr()?;
+        self.eat_op(";");
+        self.expect_op("}")?;
+        Ok(body)
+    }
+
+    fn primary(&mut self) -> ioResult<Expr> {
+        match self.next() {
+            Some(Token::Int(n)) => Ok(Expr::Lit(Value::Int(n))),
+            Some(Token::Str(s)) => Ok(Expr::Lit(Value::Str(s))),
+            Some(Token::Op("(")) => {
+                let inner = self.expr()?;
+                self.expect_op(")")?;
+                Ok(inner)
+            }
+            Some(Token::Op("[")) => Ok(Expr::Array(self.list("]")?)),
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "true" => Ok(Expr::Lit(Value::Bool(true))),
+                "false" => Ok(Expr::Lit(Value::Bool(false))),
+                "if" => {
+                    let cond = self.expr()?;
+                    let then = self.block()?;
+                    let otherwise = if self.eat_ident("else") {
+                        // `else if` chains are parsed as a nested if expression
+                        if matches!(self.peek(), Some(Token::Ident(i)) if i == "if") {
+                            Some(Box::new(self.primary()?))
+                        } else {
+                            Some(Box::new(self.block()?))
+                        }
+                    } else {
+                        None
+                    };
+                    Ok(Expr::If(Box::new(cond), Box::new(then), otherwise))
+                }
+                _ if self.eat_op("(") => Ok(Expr::Call(ident, self.list(")")?)),
+                _ => Ok(Expr::Var(ident)),
+            },
+            _ => {
+                self.pos -= 1;
+                Err(script_error(format!("unexpected {}", self.describe_next())))
+            }
+        }
+    }
+}
+
+fn expect_bool(value: Value, context: &str) -> ioResult<bool> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        other => Err(script_error(format!("{} expects a bool, found {}", context, other.type_name()))),
+    }
+}
+
+fn eval(expr: &Expr, vars: &HashMap<&str, Value>) -> ioResult<Value> {
+    match expr {
+        Expr::Lit(value) => Ok(value.clone()),
+        Expr::Var(name) => vars.get(name.as_str())
+                               .cloned()
+                               .ok_or_else(|| script_error(format!("unknown variable '{}'", name))),
+        Expr::Not(inner) => Ok(Value::Bool(!expect_bool(eval(inner, vars)?, "'!'")?)),
+        Expr::Neg(inner) => match eval(inner, vars)? {
+            Value::Int(n) => Ok(Value::Int(-n)),
+            other => Err(script_error(format!("cannot negate a {}", other.type_name()))),
+        },
+        Expr::Array(items) => Ok(Value::Array(items.iter()
+                                                   .map(|item| eval(item, vars))
+                                                   .collect::<ioResult<Vec<_>>>()?)),
+        Expr::If(cond, then, otherwise) => {
+            if expect_bool(eval(cond, vars)?, "'if'")? {
+                eval(then, vars)
+            } else {
+                match otherwise {
+                    Some(otherwise) => eval(otherwise, vars),
+                    None => Ok(Value::Unit),
+                }
+            }
+        }
+        // && and || short-circuit, so the right hand side is only evaluated when needed
+        Expr::Binary(BinOp::And, lhs, rhs) => {
+            Ok(Value::Bool(expect_bool(eval(lhs, vars)?, "'&&'")? && expect_bool(eval(rhs, vars)?, "'&&'")?))
+        }
+        Expr::Binary(BinOp::Or, lhs, rhs) => {
+            Ok(Value::Bool(expect_bool(eval(lhs, vars)?, "'||'")? || expect_bool(eval(rhs, vars)?, "'||'")?))
+        }
+        Expr::Binary(op, lhs, rhs) => binary(*op, eval(lhs, vars)?, eval(rhs, vars)?),
+        Expr::Call(name, args) => {
+            let args = args.iter()
+                           .map(|arg| eval(arg, vars))
+                           .collect::<ioResult<Vec<_>>>()?;
+            call(name, args)
+        }
+    }
+}
+
+fn contains(haystack: &Value, needle: &Value) -> ioResult<bool> {
+    match (haystack, needle) {
+        (Value::Array(items), needle) => Ok(items.contains(needle)),
+        (Value::Str(s), Value::Str(sub)) => Ok(s.contains(sub.as_str())),
+        (haystack, needle) => Err(script_error(format!("cannot look for a {} in a {}",
+                                                       needle.type_name(), haystack.type_name()))),
+    }
+}
+
+fn binary(op: BinOp, lhs: Value, rhs: Value) -> ioResult<Value> {
+    let result = match (op, &lhs, &rhs) {
+        (BinOp::Eq, _, _) => Value::Bool(lhs == rhs),
+        (BinOp::Ne, _, _) => Value::Bool(lhs != rhs),
+        (BinOp::In, _, _) => Value::Bool(contains(&rhs, &lhs)?),
+        (BinOp::Add, Value::Int(a), Value::Int(b)) => Value::Int(a.saturating_add(*b)),
+        (BinOp::Sub, Value::Int(a), Value::Int(b)) => Value::Int(a.saturating_sub(*b)),
+        // string concatenation stringifies the other operand, handy for building report lines
+        (BinOp::Add, Value::Str(_), _) | (BinOp::Add, _, Value::Str(_)) => Value::Str(format!("{}{}", lhs, rhs)),
+        (BinOp::Lt, Value::Int(a), Value::Int(b)) => Value::Bool(a < b),
+        (BinOp::Le, Value::Int(a), Value::Int(b)) => Value::Bool(a <= b),
+        (BinOp::Gt, Value::Int(a), Value::Int(b)) => Value::Bool(a > b),
+        (BinOp::Ge, Value::Int(a), Value::Int(b)) => Value::Bool(a >= b),
+        (BinOp::Lt, Value::Str(a), Value::Str(b)) => Value::Bool(a < b),
+        (BinOp::Le, Value::Str(a), Value::Str(b)) => Value::Bool(a <= b),
+        (BinOp::Gt, Value::Str(a), Value::Str(b)) => Value::Bool(a > b),
+        (BinOp::Ge, Value::Str(a), Value::Str(b)) => Value::Bool(a >= b),
+        _ => return Err(script_error(format!("unsupported operation {:?} between {} and {}",
+                                             op, lhs.type_name(), rhs.type_name()))),
+    };
+    Ok(result)
+}
+
+fn call(name: &str, args: Vec<Value>) -> ioResult<Value> {
+    let result = match (name, args.as_slice()) {
+        ("contains", [haystack, needle]) => Value::Bool(contains(haystack, needle)?),
+        ("len", [Value::Str(s)]) => Value::Int(s.chars().count() as i64),
+        ("len", [Value::Array(items)]) => Value::Int(items.len() as i64),
+        ("lower", [Value::Str(s)]) => Value::Str(s.to_lowercase()),
+        ("upper", [Value::Str(s)]) => Value::Str(s.to_uppercase()),
+        ("starts_with", [Value::Str(s), Value::Str(prefix)]) => Value::Bool(s.starts_with(prefix.as_str())),
+        ("ends_with", [Value::Str(s), Value::Str(suffix)]) => Value::Bool(s.ends_with(suffix.as_str())),
+        ("join", [Value::Array(items), Value::Str(sep)]) => {
+            Value::Str(items.iter().map(|item| item.to_string()).collect::<Vec<_>>().join(sep))
+        }
+        ("pad", [value, Value::Int(width)]) => Value::Str(format!("{:<width$}", value.to_string(),
+                                                                 width = (*width).max(0) as usize)),
+        _ => return Err(script_error(format!("no function {}({})", name,
+                                             args.iter().map(Value::type_name).collect::<Vec<_>>().join(", ")))),
+    };
+    Ok(result)
+}
+
+/// A parsed user script, evaluated once per task to filter the listing or to render a custom report line.
+///
+/// The following variables are available to a script:
+/// `index` (1-based position in the journal), `id` (`()` for tasks without id), `name`, `tags` (array of strings), `state` (`"active"`,
+/// `"waiting"` or `"complete"`), `created_at`, `updated_at` and `due` (unix seconds, `()` for tasks without due date), `priority` (`"high"`, `"medium"`,
+/// `"low"` or `()`), `owner`, `assignee`, `delegated_to` and `goal` (`()` when not set), `related` (array of ids), `estimate` (in minutes, `()` when not set) and `age_days`.
+/// # Examples
+/// ```
+/// use rusty_journal_clap::script::Script;
+/// assert!(Script::parse(r#"state == "active" && "work" in tags"#).is_ok());
+/// assert!(Script::parse(r#"if age_days > 7 { "stale: " + name }"#).is_ok());
+/// assert!(Script::parse(r#"name == "#).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Script {
+    expr: Expr,
+}
+
+impl Script {
+    pub fn parse(source: &str) -> ioResult<Script> {
+        let mut parser = Parser { tokens: tokenize(source)?, pos: 0 };
+        let expr = parser.expr()?;
+        parser.eat_op(";");
+        if parser.peek().is_some() {
+            return Err(script_error(format!("unexpected {} after the end of the expression", parser.describe_next())));
+        }
+        Ok(Script { expr })
+    }
+
+    pub fn from_file(script_path: &Path) -> ioResult<Script> {
+        Self::parse(&fs::read_to_string(script_path).with_path("reading script", script_path)?)
+    }
+
+    /// Evaluates the script against the task at the given (1-based) position in the journal
+    pub fn eval(&self, index: usize, task: &Task) -> ioResult<Value> {
+        let created_at = task.created_at();
+        let vars = HashMap::from([
+            ("index", Value::Int(index as i64)),
+            ("id", task.id().map_or(Value::Unit, |id| Value::Int(id as i64))),
+            ("name", Value::Str(task.name().to_string())),
+            ("tags", Value::Array(task.tags()
+                                      .iter()
+                                      .map(|tag| Value::Str(tag.to_string()))
+                                      .collect())),
+            ("state", Value::Str(task.state_name().to_string())),
+            ("created_at", Value::Int(created_at.timestamp())),
+            ("updated_at", Value::Int(task.updated_at().timestamp())),
+            ("due", task.due().map_or(Value::Unit, |due| Value::Int(due.timestamp()))),
+            ("priority", task.priority().map_or(Value::Unit, |priority| Value::Str(priority.name().to_string()))),
+            ("owner", task.owner().map_or(Value::Unit, |owner| Value::Str(owner.to_string()))),
+            ("assignee", task.assignee().map_or(Value::Unit, |assignee| Value::Str(assignee.to_string()))),
+            ("delegated_to", task.delegated_to().map_or(Value::Unit, |person| Value::Str(person.to_string()))),
+            ("goal", task.goal().map_or(Value::Unit, |goal| Value::Str(goal.to_string()))),
+            ("related", Value::Array(task.related().iter().map(|&id| Value::Int(id as i64)).collect())),
+            ("estimate", task.estimate().map_or(Value::Unit, |estimate| Value::Int(estimate.num_minutes()))),
+            ("age_days", Value::Int((clock::now() - created_at).num_days())),
+        ]);
+
+        eval(&self.expr, &vars)
+    }
+}
@@ -0,0 +1,61 @@
+use chrono::Duration;
+use crate::i18n::tr;
+
+/// Parses a span of time written as numbers followed by their unit, `w`, `d`, `h` or `m`,
+/// e.g. `45m`, `1h30m`, `7d` or `2w`
+/// # Examples
+/// ```
+/// use rusty_journal_clap::span;
+/// use chrono::Duration;
+/// assert_eq!(span::parse("1h30m"), Ok(Duration::minutes(90)));
+/// assert_eq!(span::parse("2w"), Ok(Duration::days(14)));
+/// assert!(span::parse("90").is_err());
+/// ```
+pub fn parse(span: &str) -> Result<Duration, String> {
+    let invalid = || tr("error-invalid-span", &[("span", &span)]);
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in span.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let count = number.parse::<i64>().map_err(|_| invalid())?;
+        let seconds = match c {
+            'w' => 7 * 86_400,
+            'd' => 86_400,
+            'h' => 3_600,
+            'm' => 60,
+            _ => return Err(invalid()),
+        };
+        // Comment: Duration::seconds panics past i64::MAX milliseconds
+        let part = count.checked_mul(seconds)
+                        .filter(|&seconds| seconds <= i64::MAX / 1_000)
+                        .ok_or_else(invalid)?;
+        total = total.checked_add(&Duration::seconds(part)).ok_or_else(invalid)?;
+        number.clear();
+    }
+    // Comment: a trailing number without unit is refused rather than guessed, "90" could mean minutes or days
+    if !number.is_empty() || span.trim().is_empty() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+/// Formats a span of time in hours and minutes, the way `parse` reads it back
+/// # Examples
+/// ```
+/// use rusty_journal_clap::span;
+/// use chrono::Duration;
+/// assert_eq!(span::format(Duration::minutes(90)), "1h30m");
+/// assert_eq!(span::format(Duration::hours(8)), "8h");
+/// assert_eq!(span::format(Duration::zero()), "0m");
+/// ```
+pub fn format(span: Duration) -> String {
+    let (hours, minutes) = (span.num_hours(), span.num_minutes() % 60);
+    match (hours, minutes) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h{}m", hours, minutes.abs()),
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt::Write;
+use chrono::{DateTime, Duration, Utc};
+use crate::i18n::tr;
+use crate::task::Task;
+
+// how many of the oldest open tasks are shown
+const OLDEST: usize = 5;
+
+/// Renders the number of tasks in each state, how many active tasks went untouched for `stale_after`,
+/// and the open tasks left untouched the longest, to review or drop
+/// # Examples
+/// ```
+/// use rusty_journal_clap::stats::stats;
+/// use chrono::{DateTime, Duration};
+/// let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+/// assert!(stats(&[], Duration::days(30), now).starts_with("0 active, 0 waiting, 0 complete"));
+/// ```
+pub fn stats(tasks: &[Task], stale_after: Duration, now: DateTime<Utc>) -> String {
+    let count = |state: &str| tasks.iter().filter(|task| task.state_name() == state).count();
+    let stale = tasks.iter().filter(|task| task.is_stale(stale_after, now)).count();
+
+    let mut open = tasks.iter()
+                        .enumerate()
+                        .map(|(i, task)| (i + 1, task))
+                        .filter(|(_, task)| !task.is_complete())
+                        .collect::<Vec<_>>();
+    // the sort is stable, so of tasks touched at the same time the first in the journal comes first
+    open.sort_by_key(|(_, task)| task.updated_at());
+
+    let mut report = String::new();
+    // Comment: writing to a String can't fail
+    let _ = writeln!(report, "{}", tr("stats-states", &[("active", &count("active")),
+                                                        ("waiting", &count("waiting")),
+                                                        ("complete", &count("complete"))]));
+    let _ = writeln!(report, "{}", tr("stats-stale", &[("count", &stale), ("days", &stale_after.num_days())]));
+    if !open.is_empty() {
+        let _ = writeln!(report, "\n{}", tr("stats-oldest", &[]));
+        for (index, task) in open.into_iter().take(OLDEST) {
+            let untouched = tr("untouched-for", &[("days", &(now - task.updated_at()).num_days().max(0))]);
+            let _ = writeln!(report, "  {:>4}. {} ({})", index, task.name(), untouched);
+        }
+    }
+    report
+}
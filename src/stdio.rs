@@ -0,0 +1,64 @@
+use std::io::{self, Read, Result as ioResult, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Comment: the journal is read from stdin once and kept, so a command loading the journal several times,
+// e.g. a macro, sees its own changes. The content written last is sent to stdout when the command is done
+static JOURNAL: Mutex<Option<Vec<u8>>> = Mutex::new(None);
+static WRITTEN: AtomicBool = AtomicBool::new(false);
+static MESSAGES_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Whether the journal path is `-`, i.e. the journal is read from stdin and written to stdout
+/// # Examples
+/// ```
+/// use rusty_journal_clap::stdio;
+/// use std::path::Path;
+/// assert!(stdio::is_stdio(Path::new("-")));
+/// assert!(!stdio::is_stdio(Path::new("./-")));
+/// ```
+pub fn is_stdio(journal_path: &Path) -> bool {
+    journal_path.as_os_str() == "-"
+}
+
+/// The content of the journal, read from stdin on the first call
+pub(crate) fn read() -> ioResult<Vec<u8>> {
+    let mut journal = JOURNAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if journal.is_none() {
+        let mut content = Vec::new();
+        io::stdin().lock().read_to_end(&mut content)?;
+        *journal = Some(content);
+    }
+    Ok(journal.clone().unwrap_or_default())
+}
+
+/// Replaces the content of the journal, which `flush` writes to stdout
+pub(crate) fn write(content: Vec<u8>) {
+    *JOURNAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(content);
+    WRITTEN.store(true, Ordering::Relaxed);
+}
+
+/// Writes the journal to stdout if the command changed it, nothing otherwise
+pub fn flush() -> ioResult<()> {
+    if !WRITTEN.swap(false, Ordering::Relaxed) {
+        return Ok(());
+    }
+    let content = JOURNAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone().unwrap_or_default();
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&content)?;
+    stdout.flush()
+}
+
+/// Sends the messages of `say` to stderr, keeping stdout for the journal of a mutating command
+pub fn set_messages_to_stderr(to_stderr: bool) {
+    MESSAGES_TO_STDERR.store(to_stderr, Ordering::Relaxed);
+}
+
+/// Prints a message for the user, e.g. "2 task(s) removed", on stdout or on stderr when stdout carries the journal
+pub fn say(message: impl std::fmt::Display) {
+    if MESSAGES_TO_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
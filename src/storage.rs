@@ -0,0 +1,106 @@
+use std::io::Result as ioResult;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use crate::error::ParseFailure;
+use crate::toml;
+
+/// The file formats a journal can be kept in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    Json,
+    // the tasks as an array of tables named task
+    Toml,
+}
+
+// 0 when the backend is inferred from the file extension, or the backend given with --journal-format
+static BACKEND: AtomicU8 = AtomicU8::new(0);
+
+/// Sets for the whole process the backend of the journal, regardless of its extension
+pub fn set_backend(backend: Option<Backend>) {
+    BACKEND.store(match backend {
+        None => 0,
+        Some(Backend::Json) => 1,
+        Some(Backend::Toml) => 2,
+    }, Ordering::Relaxed);
+}
+
+impl Backend {
+    pub fn parse(backend: &str) -> Result<Backend, String> {
+        match backend {
+            "json" => Ok(Backend::Json),
+            "toml" => Ok(Backend::Toml),
+            _ => Err(format!("unknown journal format '{}', expected json or toml", backend)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Json => "json",
+            Backend::Toml => "toml",
+        }
+    }
+
+    /// The backend a journal file is named after, looking past the extension of its compression
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::storage::Backend;
+    /// use std::path::Path;
+    /// assert_eq!(Backend::from_extension(Path::new("todo.toml")), Some(Backend::Toml));
+    /// assert_eq!(Backend::from_extension(Path::new("archive.toml.gz")), Some(Backend::Toml));
+    /// assert_eq!(Backend::from_extension(Path::new("todo")), None);
+    /// ```
+    pub fn from_extension(path: &Path) -> Option<Backend> {
+        let path = match path.extension()?.to_str()? {
+            "gz" | "zst" => Path::new(path.file_stem()?),
+            _ => path,
+        };
+        match path.extension()?.to_str()? {
+            "json" => Some(Backend::Json),
+            "toml" => Some(Backend::Toml),
+            _ => None,
+        }
+    }
+
+    /// The backend of the journal, the one set for the process if any, then the one of its extension, and JSON otherwise
+    pub fn of_journal(journal_path: &Path) -> Backend {
+        match BACKEND.load(Ordering::Relaxed) {
+            1 => Backend::Json,
+            2 => Backend::Toml,
+            _ => Self::from_extension(journal_path).unwrap_or(Backend::Json),
+        }
+    }
+
+    /// Reads the tasks of a journal, as `Task`s or any other view of them, where an empty (or blank) file is an empty journal
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::{storage::Backend, task::Task};
+    /// let tasks: Vec<Task> = Backend::Toml.decode(b"[[task]]\nname = \"play\"\ncreted_at = 0\n[task.state]\ntype = \"Active\"\n").unwrap();
+    /// assert_eq!(tasks[0].name(), "play");
+    /// assert!(Backend::Toml.decode::<Vec<Task>>(b"").unwrap().is_empty());
+    /// ```
+    pub fn decode<T: DeserializeOwned>(self, content: &[u8]) -> ioResult<T> {
+        let invalid = ParseFailure::of;
+        match self {
+            Backend::Json if content.iter().all(u8::is_ascii_whitespace) => serde_json::from_slice(b"[]").map_err(invalid),
+            Backend::Json => serde_json::from_slice(content).map_err(invalid),
+            Backend::Toml => {
+                let document = std::str::from_utf8(content).map_err(ParseFailure::of)?;
+                let tasks = match toml::from_str(document)? {
+                    Value::Object(mut table) => table.remove("task").unwrap_or_else(|| json!([])),
+                    _ => json!([]),
+                };
+                serde_json::from_value(tasks).map_err(invalid)
+            }
+        }
+    }
+
+    /// Writes the tasks of a journal
+    pub fn encode<T: Serialize>(self, tasks: &T) -> ioResult<Vec<u8>> {
+        match self {
+            Backend::Json => Ok(serde_json::to_vec(tasks)?),
+            Backend::Toml => Ok(toml::to_string(&json!({"task": serde_json::to_value(tasks)?}))?.into_bytes()),
+        }
+    }
+}
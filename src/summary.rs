@@ -0,0 +1,80 @@
+use std::fmt::Write;
+use chrono::{DateTime, Duration, Utc};
+use crate::i18n::tr;
+use crate::output;
+use crate::task::Task;
+
+/// The span of time a plain report looks back on, ending now
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Daily,
+    Weekly,
+}
+
+impl Period {
+    pub fn parse(period: &str) -> Result<Period, String> {
+        match period {
+            "daily" => Ok(Period::Daily),
+            "weekly" => Ok(Period::Weekly),
+            _ => Err(tr("error-unknown-period", &[("period", &period)])),
+        }
+    }
+
+    fn length(self) -> Duration {
+        match self {
+            Period::Daily => Duration::days(1),
+            Period::Weekly => Duration::days(7),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Period::Daily => "daily",
+            Period::Weekly => "weekly",
+        }
+    }
+}
+
+/// Renders a plain text summary of the tasks added and completed in the period ending at `now`,
+/// followed by the overdue tasks and all the pending tasks. The output has no color and only depends on the tasks and `now`,
+/// so it can be mailed from cron as it is
+/// # Examples
+/// ```
+/// use rusty_journal_clap::summary::{plain, Period};
+/// use chrono::DateTime;
+/// let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+/// assert!(plain(&[], Period::Daily, now).contains("Added (0)"));
+/// assert!(plain(&[], Period::Daily, now).contains("Overdue (0)"));
+/// ```
+pub fn plain(tasks: &[Task], period: Period, now: DateTime<Utc>) -> String {
+    let since = now - period.length();
+    let indexed = tasks.iter().enumerate().map(|(i, task)| (i + 1, task));
+
+    let added = indexed.clone()
+                       .filter(|(_, task)| task.created_at() > since)
+                       .collect::<Vec<_>>();
+    let completed = indexed.clone()
+                           .filter(|(_, task)| task.completed_at().is_some_and(|completed_at| completed_at > since))
+                           .collect::<Vec<_>>();
+    let overdue = indexed.clone()
+                         .filter(|(_, task)| task.is_overdue(now))
+                         .collect::<Vec<_>>();
+    let pending = indexed.filter(|(_, task)| !task.is_complete())
+                         .collect::<Vec<_>>();
+
+    let format = "%Y-%m-%d %H:%M";
+    let mut report = String::new();
+    // Comment: writing to a String can't fail
+    let _ = writeln!(report, "{}", tr("report-title", &[("period", &period.name()),
+                                                         ("since", &output::format_time(since, format)),
+                                                         ("until", &output::format_time(now, format))]));
+    for (title, section) in [("section-added", &added), ("section-completed", &completed),
+                             ("section-overdue", &overdue), ("section-pending", &pending)] {
+        let _ = writeln!(report, "\n{}", tr(title, &[("count", &section.len())]));
+        for (index, task) in section {
+            let tags = if task.tags().is_empty() { String::new() } else { format!(" [{}]", task.tags().join(", ")) };
+            let _ = writeln!(report, "  {:>4}. {}{}", index, task.name(), tags);
+        }
+    }
+    report
+}
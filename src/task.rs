@@ -1,13 +1,124 @@
-use std::{path::PathBuf, fs::OpenOptions, io::{BufReader, Read, Write, BufWriter, Error, ErrorKind}};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{path::{Path, PathBuf}, fs::{self, File, OpenOptions}, io::{self, IsTerminal, Read, Write, BufWriter, Error, ErrorKind}};
 use std::io::Result as ioResult;
-use chrono::{DateTime, Utc, serde::ts_seconds, Local};
+use chrono::{DateTime, Datelike, Duration, Utc, serde::{ts_seconds, ts_seconds_option}};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::fmt::{Display, Formatter};
 use std::fmt::Result as fmtResult;
+use crate::clock;
+use crate::compress::{self, Compression};
+use crate::error::{Context, IndexError, JournalError, JournalResult, ParseFailure};
+use crate::export::{self, Format, GroupBy};
+use crate::filter::TaskFilter;
+use crate::fuzzy;
+use crate::goal;
+use crate::held;
+use crate::i18n::tr;
+use crate::color::{self, Color, ColorMode};
+use crate::import::{LINK_FIELD, UID_FIELD};
+use crate::info::{self, JournalFile};
+use crate::output::{self, OutputFormat};
+use crate::paths;
+use crate::picker::{self, Outcome};
+use crate::plan;
+use crate::recur::Recurrence;
+use crate::redact::Redacted;
+use crate::trace;
+use crate::url;
+use crate::watch::Watcher;
+use crate::prompt;
+use crate::score;
+use crate::span;
+use crate::stats;
+use crate::stdio;
+use crate::storage::Backend;
+use crate::summary::{self, Period};
+use crate::template::Template;
+use crate::trash;
+use crate::undo;
+#[cfg(feature = "scripting")]
+use crate::script::{Script, Value};
+#[cfg(feature = "test_util")]
+use crate::test_util::{Arbitrary, Rng};
 
-#[derive(Debug, Deserialize, Serialize)]
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Turns the read-only mode on or off for the whole process. In read-only mode the journal is
+/// opened for reading only, a missing journal reads as empty instead of being created,
+/// and any attempt to write it fails with `PermissionDenied`
+pub fn set_read_only(read_only: bool) {
+    READ_ONLY.store(read_only, Ordering::Relaxed);
+}
+
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+// true unless the config sets cascade
+static CASCADE: AtomicBool = AtomicBool::new(true);
+
+/// Sets for the whole process whether `done` and `remove` also complete or remove the subtasks of the tasks
+/// they are given. Without it the subtasks are left as they are, and those of a removed task become top-level tasks
+pub fn set_cascade(cascade: bool) {
+    CASCADE.store(cascade, Ordering::Relaxed);
+}
+
+pub fn is_cascade() -> bool {
+    CASCADE.load(Ordering::Relaxed)
+}
+
+// Comment: large enough that writing a journal of 100k tasks takes a few hundred syscalls instead of tens of thousands
+const WRITE_BUFFER_SIZE: usize = 256 * 1024;
+
+/// The hidden file next to the journal that writers lock while they read, change and write back the tasks.
+/// The journal itself can't be locked, as every write replaces it with a new file
+/// # Examples
+/// ```
+/// use rusty_journal_clap::task::lock_path;
+/// use std::path::{Path, PathBuf};
+/// assert_eq!(lock_path(Path::new("notes/todo.json")), PathBuf::from("notes/.todo.json.lock"));
+/// ```
+pub fn lock_path(journal_path: &Path) -> PathBuf {
+    let file_name = journal_path.file_name().unwrap_or_default().to_string_lossy();
+    journal_path.with_file_name(format!(".{}.lock", file_name))
+}
+
+/// An exclusive advisory lock on the journal, held until dropped
+struct JournalLock {
+    // Comment: the lock is released when the file is closed
+    _file: File,
+}
+
+impl JournalLock {
+    /// Waits until no other process is writing the journal
+    fn acquire(journal_path: &Path) -> ioResult<JournalLock> {
+        let f = paths::resolve(&lock_path(journal_path)).and_then(|resolved| OpenOptions::new()
+                                                                                      .write(true)
+                                                                                      .create(true)
+                                                                                      .truncate(false)
+                                                                                      .open(resolved))
+                                                        .and_then(|f| f.lock().map(|_| f))
+                                                        .with_path("locking journal", journal_path)?;
+        Ok(JournalLock { _file: f })
+    }
+
+    /// Whether another process is writing the journal right now
+    fn is_held(journal_path: &Path) -> bool {
+        match paths::resolve(&lock_path(journal_path)).and_then(File::open) {
+            Ok(f) => matches!(f.try_lock_shared(), Err(fs::TryLockError::WouldBlock)),
+            Err(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Task {
+    // a stable identifier, unlike the position in the journal which changes as tasks are removed or moved.
+    // Journals written before ids were introduced have no id until the tasks are renumbered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
     name: String,
     state: State,
     // the tags fields can be modelled either way. If modelled with 
@@ -19,9 +130,49 @@ pub struct Task {
     tags: Option<Vec<String>>,
     #[serde(with = "ts_seconds")]
     creted_at: DateTime<Utc>,
+    #[serde(default, with = "ts_seconds_option", skip_serializing_if = "Option::is_none")]
+    due: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<Priority>,
+    // the user who added the task, c.f. `Config::user`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    // the user the task is assigned to, in a journal shared by a team
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    assignee: Option<String>,
+    // the person the task was handed to, whose work needs following up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    delegated_to: Option<String>,
+    // the name of the goal the task contributes to, c.f. `goal::Goal`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    goal: Option<String>,
+    // the ids of the tasks related to this one, each of which refers back to it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    related: Vec<u64>,
+    // the id of the task this one is a subtask of, c.f. `add --parent`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<u64>,
+    // how often the task comes back, the rule moves on to the next occurrence once this one is completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    repeat: Option<Recurrence>,
+    // the day of the month a monthly repetition falls on, when a shorter month moved the due date before it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    repeat_day: Option<u32>,
+    // how long doing the task should take, in minutes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    estimate: Option<u32>,
+    // the last change to the task, c.f. `updated_at()`
+    #[serde(default, with = "ts_seconds_option", skip_serializing_if = "Option::is_none")]
+    updated_at: Option<DateTime<Utc>>,
+    // append only, entries are never edited or removed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<HistoryEntry>,
+    // fields carried over from the source a task was imported from, e.g. the UID of a calendar event
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    extra: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 enum State {
     Active,
@@ -33,258 +184,2336 @@ enum State {
     Complete{
     #[serde(with = "ts_seconds")]
     completed_at: DateTime<Utc>},
+    // hidden from the default views until the time has come, then active again
+    Waiting{
+    #[serde(with = "ts_seconds")]
+    until: DateTime<Utc>,
+    // what the task waits for, e.g. "Bob's reply"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reason: Option<String>},
+}
+
+/// A change recorded in the history of a task, or a note added with `annotate`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HistoryEntry {
+    #[serde(with = "ts_seconds")]
+    at: DateTime<Utc>,
+    kind: HistoryKind,
+    text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryKind {
+    // the task was completed
+    State,
+    // the name, tags, due date or priority changed
+    Edit,
+    Note,
+}
+
+impl HistoryKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            HistoryKind::State => "state",
+            HistoryKind::Edit => "edit",
+            HistoryKind::Note => "note",
+        }
+    }
+}
+
+impl HistoryEntry {
+    pub fn at(&self) -> DateTime<Utc> {
+        self.at
+    }
+
+    pub fn kind(&self) -> HistoryKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[cfg(feature = "test_util")]
+impl Arbitrary for HistoryEntry {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        HistoryEntry {
+            at: arbitrary_timestamp(rng),
+            kind: *rng.pick(&[HistoryKind::State, HistoryKind::Edit, HistoryKind::Note]),
+            text: String::arbitrary(rng),
+        }
+    }
+}
+
+/// The optional fields of the tasks added with `add_with` and `add_many`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskOptions {
+    pub priority: Option<Priority>,
+    pub owner: Option<String>,
+    pub assignee: Option<String>,
+    pub estimate: Option<Duration>,
+    pub goal: Option<String>,
+    pub due: Option<DateTime<Utc>>,
+    // the (1-based) index of the task the new tasks are subtasks of
+    pub parent: Option<usize>,
+    pub repeat: Option<Recurrence>,
+}
+
+impl TaskOptions {
+    pub(crate) fn apply(&self, task: &mut Task) {
+        task.priority = self.priority;
+        task.owner.clone_from(&self.owner);
+        task.assignee.clone_from(&self.assignee);
+        task.set_estimate(self.estimate);
+        task.goal.clone_from(&self.goal);
+        task.due = self.due;
+        task.repeat = self.repeat;
+    }
+}
+
+/// The changes `edit` makes to a task, the fields left at None or empty stay as they are
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskEdit {
+    pub name: Option<String>,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+    // Some(None) clears the due date
+    pub due: Option<Option<DateTime<Utc>>>,
+}
+
+impl TaskEdit {
+    pub(crate) fn apply(&self, task: &mut Task, now: DateTime<Utc>) {
+        if let Some(name) = &self.name {
+            task.rename(name.to_owned(), now);
+        }
+        for tag in &self.add_tags {
+            task.add_tag(tag.to_owned(), now);
+        }
+        for tag in &self.remove_tags {
+            task.remove_tag(tag, now);
+        }
+        if let Some(due) = self.due.filter(|&due| due != task.due) {
+            let text = match due {
+                Some(due) => format!("due {}", output::format_time(due, "%d/%m/%Y %H:%M")),
+                None => "due date cleared".to_string(),
+            };
+            task.record(HistoryKind::Edit, text, now);
+            task.set_due(due);
+        }
+    }
+}
+
+/// The order `list` prints the tasks in
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SortBy {
+    // journal order
+    #[default]
+    Index,
+    // most urgent first, with the completed tasks last
+    Urgency,
+    // soonest due first, then the tasks without due date, with the completed tasks last
+    Due,
+    // highest priority first, then the tasks without priority, with the completed tasks last
+    Priority,
+}
+
+impl SortBy {
+    pub fn parse(sort: &str) -> Result<SortBy, String> {
+        match sort {
+            "index" => Ok(SortBy::Index),
+            "urgency" => Ok(SortBy::Urgency),
+            "due" => Ok(SortBy::Due),
+            "priority" => Ok(SortBy::Priority),
+            _ => Err(format!("unknown sort order '{}', expected index, urgency, due or priority", sort)),
+        }
+    }
+}
+
+/// Which tasks `list_with` prints, in which order and how
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListOptions {
+    pub filter: TaskFilter,
+    pub sort: SortBy,
+    // prints the urgency score in front of every task
+    pub show_urgency: bool,
+    // lists the waiting tasks instead of the others
+    pub waiting: bool,
+    // masks the task names, see `redact::Redacted`
+    pub redact: bool,
+    // ends the listing with the number of active tasks untouched for that long, if any
+    pub stale_banner: Option<Duration>,
+    pub coefficients: score::Coefficients,
+    // prints the subtasks indented under their parent
+    pub tree: bool,
 }
 
+/// How important a task is, ordered from low to high
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub fn parse(priority: &str) -> Result<Priority, String> {
+        match priority {
+            "low" => Ok(Priority::Low),
+            "medium" => Ok(Priority::Medium),
+            "high" => Ok(Priority::High),
+            _ => Err(format!("unknown priority '{}', expected high, medium or low", priority)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
+    }
+}
+
+#[cfg(feature = "test_util")]
+impl Arbitrary for Priority {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        *rng.pick(&[Priority::Low, Priority::Medium, Priority::High])
+    }
+}
+
+#[cfg(feature = "test_util")]
+impl Arbitrary for State {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        match rng.below(3) {
+            0 => State::Active,
+            1 => State::Complete{completed_at: arbitrary_timestamp(rng)},
+            // a waiting task whose time has come wakes up when loaded, so the generated ones wait past 2100
+            _ => State::Waiting{until: arbitrary_timestamp(rng) + Duration::days(365 * 130), reason: Option::<String>::arbitrary(rng)},
+        }
+    }
+}
+
+#[cfg(feature = "test_util")]
+impl Arbitrary for Task {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        Task {
+            id: Option::<u64>::arbitrary(rng),
+            name: String::arbitrary(rng),
+            state: State::arbitrary(rng),
+            // an empty Vec is never stored, c.f. the comment on the tags field
+            tags: Option::<Vec<String>>::arbitrary(rng).filter(|tags| !tags.is_empty()),
+            creted_at: arbitrary_timestamp(rng),
+            due: if rng.one_in(2) { Some(arbitrary_timestamp(rng)) } else { None },
+            priority: Option::<Priority>::arbitrary(rng),
+            owner: Option::<String>::arbitrary(rng),
+            assignee: Option::<String>::arbitrary(rng),
+            delegated_to: Option::<String>::arbitrary(rng),
+            goal: Option::<String>::arbitrary(rng),
+            related: Vec::<u64>::arbitrary(rng),
+            parent: Option::<u64>::arbitrary(rng),
+            repeat: Option::<Recurrence>::arbitrary(rng),
+            repeat_day: Option::<u64>::arbitrary(rng).map(|day| (day % 31) as u32 + 1),
+            estimate: Option::<u64>::arbitrary(rng).map(|minutes| (minutes % 10_000) as u32),
+            updated_at: if rng.one_in(2) { None } else { Some(arbitrary_timestamp(rng)) },
+            history: Vec::<HistoryEntry>::arbitrary(rng),
+            extra: Option::<String>::arbitrary(rng).map(|uid| BTreeMap::from([(UID_FIELD.to_string(), uid)]))
+                                                   .unwrap_or_default(),
+        }
+    }
+}
+
+// Comment: timestamps are stored with a resolution of seconds, so the generated ones are whole seconds
+// between 1970 and 2100
+#[cfg(feature = "test_util")]
+fn arbitrary_timestamp(rng: &mut Rng) -> DateTime<Utc> {
+    DateTime::from_timestamp(rng.below(4_102_444_800) as i64, 0).unwrap()
+}
+
+/// The part of a task that `count` reads, all other fields are skipped without being parsed into values
+#[derive(Deserialize)]
+struct CountedTask {
+    state: CountedStateType,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+    #[serde(default, with = "ts_seconds_option")]
+    due: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct CountedStateType {
+    #[serde(rename = "type")]
+    kind: CountedState,
+    #[serde(default, with = "ts_seconds_option")]
+    until: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, PartialEq)]
+enum CountedState {
+    Active,
+    Complete,
+    Waiting,
+}
 
 impl Display for Task {
     fn fmt (&self, f: &mut Formatter) -> fmtResult {
         // format syntax c.f.
         // learn.microsoft.com/en-us/training/modules/rust-create-command-line-program/7-list-tasks-function
         // https://doc.rust-lang.org/std/fmt/index.html#fillalignment
-        write!(f, "Task: {:<50} Created at: {}", self.name, self.creted_at.with_timezone(&Local).format("%d/%m/%Y %H:%M"))
+        write!(f, "{} {:<50} {} {}", tr("label-task", &[]), self.name, tr("label-created", &[]), output::format_time(self.creted_at, "%d/%m/%Y %H:%M"))?;
+        if let Some(due) = self.due {
+            write!(f, " {} {}", tr("label-due", &[]), output::format_time(due, "%d/%m/%Y %H:%M"))?;
+        }
+        if let Some(repeat) = self.repeat {
+            write!(f, " {} {}", tr("label-repeats", &[]), repeat)?;
+        }
+        if let State::Complete{completed_at} = self.state {
+            write!(f, " {} {}", tr("label-done", &[]), output::format_time(completed_at, "%d/%m/%Y %H:%M"))?;
+        }
+        if let State::Waiting{until, reason} = &self.state {
+            write!(f, " {} {}", tr("label-waiting", &[]), output::format_time(*until, "%d/%m/%Y %H:%M"))?;
+            if let Some(reason) = reason {
+                write!(f, " ({})", reason)?;
+            }
+        }
+        Ok(())
     }
 }
 
+/// Parses the content of a journal file, where an empty (or blank) file is an empty journal.
+/// Malformed content, e.g. invalid UTF-8, out of range numbers or timestamps, or nesting
+/// deeper than serde_json's recursion limit, is an InvalidData error and never a panic
+/// # Examples
+/// ```
+/// use rusty_journal_clap::task::parse_journal;
+/// assert_eq!(parse_journal(b"").unwrap().len(), 0);
+/// assert_eq!(parse_journal(br#"[{"name": "play", "state": {"type": "Active"}, "tags": null, "creted_at": 0}]"#).unwrap().len(), 1);
+/// assert!(parse_journal(b"[{\"name\": \"\xff\"}]").is_err());
+/// assert!(parse_journal(br#"[{"name": "pla"#).is_err());
+/// ```
+pub fn parse_journal(content: &[u8]) -> ioResult<Vec<Task>> {
+    // It's interesting that the Deserialize trait is ONLY implemented for Task struct
+    // But serde_json::from_slice call can deserialize to a Vec of Task, which 
+    // presumably requires Vec<Task>: DeserializeOwned
+    // https://docs.rs/serde_json/latest/serde_json/de/fn.from_slice.html
+    // Comment: only a blank file counts as empty, a journal cut short is an error as well since
+    // treating it as empty would make the next save wipe the tasks
+    if content.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Vec::new());
+    }
+
+    // Comment: serde_json reports a journal cut short as UnexpectedEof, which is no different from any other malformed content here
+    serde_json::from_slice(content).map_err(ParseFailure::of)
+}
+
 impl Task {
-    fn new(task_name: String, task_tags: Option<Vec<String>>) -> Self {
+    pub(crate) fn new(task_name: String, task_tags: Option<Vec<String>>, now: DateTime<Utc>) -> Self {
         Task {
+            id: None,
             name: task_name,
             state: State::Active,
             tags: task_tags,
-            creted_at: Utc::now(),
+            creted_at: now,
+            due: None,
+            priority: None,
+            owner: None,
+            assignee: None,
+            delegated_to: None,
+            goal: None,
+            related: Vec::new(),
+            parent: None,
+            repeat: None,
+            repeat_day: None,
+            estimate: None,
+            updated_at: None,
+            history: Vec::new(),
+            extra: BTreeMap::new(),
         }
     }
 
-    fn completed(&mut self) {
-        self.state = State::Complete{completed_at: Utc::now()};
+    // Comment: every change of a task goes through its history, which makes this the one place to keep updated_at
+    fn record(&mut self, kind: HistoryKind, text: String, at: DateTime<Utc>) {
+        self.history.push(HistoryEntry { at, kind, text });
+        self.updated_at = Some(self.updated_at.map_or(at, |updated_at| updated_at.max(at)));
     }
 
-    // fn _get_tasks(file: impl Read) -> ioResult<Vec<Task>>  {
-    fn _get_tasks(file: impl Read) -> serde_json::Result<Vec<Task>> {
-        // It's interesting that the Deserialize trait is ONLY implemented for Task struct
-        // But serde_json::from_reader call can deserialize to a Vec of Task, which 
-        // presumably requires Vec<Task>: DeserializeOwned
-        // https://docs.rs/serde_json/latest/serde_json/de/fn.from_reader.html
-        let tasks = match serde_json::from_reader(file)  {
-            Ok(tasks) => tasks,
-            Err(err) if err.is_eof() => Vec::new(),
-            Err(err) => Err(err)?,
+    pub(crate) fn completed(&mut self, now: DateTime<Utc>) {
+        if !self.is_complete() {
+            self.record(HistoryKind::State, "completed".to_string(), now);
+        }
+        self.state = State::Complete{completed_at: now};
+    }
+
+    pub(crate) fn wait_until(&mut self, until: DateTime<Utc>, reason: Option<String>, now: DateTime<Utc>) {
+        let text = match &reason {
+            Some(reason) => format!("waiting until {} for {}", output::format_time(until, "%d/%m/%Y %H:%M"), reason),
+            None => format!("waiting until {}", output::format_time(until, "%d/%m/%Y %H:%M")),
         };
+        self.record(HistoryKind::State, text, now);
+        self.state = State::Waiting{until, reason};
+    }
 
-        Ok(tasks)
+    /// Makes a waiting task active again once its time has come, as of the time it came
+    fn wake(&mut self, now: DateTime<Utc>) {
+        if let State::Waiting{until, ..} = self.state {
+            if until <= now {
+                self.record(HistoryKind::State, "woke up".to_string(), until);
+                self.state = State::Active;
+            }
+        }
     }
 
-    fn _write_tasks(tasks: &Vec<Task>, file: impl Write) -> ioResult<()> {
-        serde_json::to_writer(file, tasks)?;
-        Ok(())
+    pub(crate) fn add_note(&mut self, note: String, now: DateTime<Utc>) {
+        self.record(HistoryKind::Note, note, now);
     }
 
+    pub(crate) fn set_due(&mut self, due: Option<DateTime<Utc>>) {
+        self.due = due;
+        // a new due date sets the day of the month anew
+        self.repeat_day = None;
+    }
 
-    /// The method fetches the current tasks as a vec from the Json
-    /// and add a new task by pushing to the vec and write back to the Json
-    /// # Examples
-    /// ```
-    /// use rusty_journal_clap::task;
-    /// use std::path::PathBuf;
-    /// task::Task::add(PathBuf::from("todo.json"), "play".to_string(), Some(vec!["good first issue".to_string()]));
-    /// ```
-    pub fn add(journal_path: PathBuf, name: String, tags: Option<Vec<String>>) -> ioResult<()> {
-        let f = OpenOptions::new()
-                                .write(true)
-                                .create(true)
-                                .read(true)
-                                .open(&journal_path)?;
+    pub(crate) fn set_priority(&mut self, priority: Option<Priority>) {
+        self.priority = priority;
+    }
+
+    pub(crate) fn set_estimate(&mut self, estimate: Option<Duration>) {
+        self.estimate = estimate.map(|estimate| u32::try_from(estimate.num_minutes().max(0)).unwrap_or(u32::MAX));
+    }
+
+    pub(crate) fn set_extra(&mut self, key: &str, value: String) {
+        self.extra.insert(key.to_string(), value);
+    }
+
+    pub(crate) fn rename(&mut self, name: String, now: DateTime<Utc>) {
+        if name != self.name {
+            self.record(HistoryKind::Edit, format!("renamed from '{}'", self.name), now);
+        }
+        self.name = name;
+    }
 
-        let f = BufReader::new(f);
+    pub(crate) fn add_tag(&mut self, tag: String, now: DateTime<Utc>) {
+        if !self.has_tag(&tag) {
+            self.record(HistoryKind::Edit, format!("tagged {}", tag), now);
+            self.tags.get_or_insert_with(Vec::new).push(tag);
+        }
+    }
 
-        let mut tasks = Self::_get_tasks(f)?;
+    pub(crate) fn remove_tag(&mut self, tag: &str, now: DateTime<Utc>) {
+        if self.has_tag(tag) {
+            self.record(HistoryKind::Edit, format!("untagged {}", tag), now);
+        }
+        if let Some(tags) = self.tags.as_mut() {
+            tags.retain(|t| t != tag);
+            // keep a single representation for a task without tag, c.f. the comment on the tags field
+            if tags.is_empty() {
+                self.tags = None;
+            }
+        }
+    }
 
-        let new_task = Self::new(name, tags);
+    pub fn id(&self) -> Option<u64> {
+        self.id
+    }
 
-        tasks.push(new_task);
+    pub(crate) fn set_id(&mut self, id: u64) {
+        self.id = Some(id);
+    }
 
-        let f = OpenOptions::new()
-                            // technically not stricted needed as overwritten data 
-                            // is larger than what was in the file at this point of the add operation
-                            .truncate(true) 
-                            .write(true)
-                            .open(&journal_path)?;
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 
-        let f = BufWriter::new(f);
+    /// The tags of the task, where a task without tags yields an empty slice
+    pub fn tags(&self) -> &[String] {
+        self.tags.as_deref().unwrap_or_default()
+    }
 
-        Self::_write_tasks(&tasks, f)?;
+    pub fn is_complete(&self) -> bool {
+        matches!(self.state, State::Complete{..})
+    }
 
-        Ok(())
+    pub fn is_waiting(&self) -> bool {
+        matches!(self.state, State::Waiting{..})
     }
 
-    /// The method fetches the current tasks into a vec from the Json
-    /// and removes the task identified by the user input index (with validation)
-    /// # Examples
-    /// ```
-    /// use rusty_journal_clap::task;
-    /// use std::path::PathBuf;
-    /// task::Task::remove(PathBuf::from("todo.json"), 1);
-    /// ```      
-    pub fn remove(journal_path: PathBuf, index: usize) -> ioResult<()> {
-        let f = OpenOptions::new()
-                            .read(true)
-                            .open(&journal_path)?;
-        
-        let f = BufReader::new(f);
+    /// The name of the state, as used by filters, templates and scripts: `active`, `waiting` or `complete`
+    pub fn state_name(&self) -> &'static str {
+        match self.state {
+            State::Active => "active",
+            State::Waiting{..} => "waiting",
+            State::Complete{..} => "complete",
+        }
+    }
 
-        let mut tasks = Self::_get_tasks(f)?;
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.creted_at
+    }
 
+    /// When the task was last changed, or created if it never was
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at.unwrap_or(self.creted_at)
+    }
 
-        // Thinking from the user input perspective:
-        // User is expected to put in an index from 0 to the number of tasks (task.len())
-        // Hence that expectaion is combined with index bound check and error reporting as following
-        if index == 0 || index > tasks.len() {
-            return Err(Error::new(ErrorKind::InvalidInput, "Invalid Task ID"));
+    /// Whether the task is active and hasn't been touched for at least the given time
+    pub fn is_stale(&self, after: Duration, now: DateTime<Utc>) -> bool {
+        !self.is_complete() && !self.is_waiting() && now - self.updated_at() >= after
+    }
+
+    pub fn completed_at(&self) -> Option<DateTime<Utc>> {
+        match self.state {
+            State::Complete{completed_at} => Some(completed_at),
+            State::Active | State::Waiting{..} => None,
+        }
+    }
 
+    /// When a waiting task becomes active again
+    pub fn waiting_until(&self) -> Option<DateTime<Utc>> {
+        match self.state {
+            State::Waiting{until, ..} => Some(until),
+            _ => None,
         }
-        // With the check above in place, this remove call is certain to NOT PANIC
-        tasks.remove(index-1);
+    }
+
+    /// What a waiting task waits for
+    pub fn waiting_for(&self) -> Option<&str> {
+        match &self.state {
+            State::Waiting{reason, ..} => reason.as_deref(),
+            _ => None,
+        }
+    }
 
+    pub fn due(&self) -> Option<DateTime<Utc>> {
+        self.due
+    }
 
-        let f = OpenOptions::new()
-                    // Required as otherwise we overwrite the file with data smaller than
-                    // the current content presumably from the start of file as seek position
-                    // which corrupts the data
-                    .truncate(true)
-                    .write(true)
-                    .open(&journal_path)?;
+    /// Whether the task is still open past its due date
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        !self.is_complete() && self.due.is_some_and(|due| due < now)
+    }
 
-        let f = BufWriter::new(f);
+    pub fn priority(&self) -> Option<Priority> {
+        self.priority
+    }
 
-        Self::_write_tasks(&tasks, f)?;
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
 
-        Ok(())
+    pub fn assignee(&self) -> Option<&str> {
+        self.assignee.as_deref()
     }
 
-    /// The method fetches the current tasks into a vec from the Json
-    /// and update the task identified by the user input index (with validation) to completed state
-    /// # Examples
-    /// ```
-    /// use rusty_journal_clap::task;
-    /// use std::path::PathBuf;
-    /// task::Task::complete(PathBuf::from("todo.json"), 1);    
-    pub fn complete(journal_path: PathBuf, index: usize) -> ioResult<()> {
-        let f = OpenOptions::new()
-                            .read(true)
-                            .open(&journal_path)?;
-        
-        let f = BufReader::new(f);
+    pub fn delegated_to(&self) -> Option<&str> {
+        self.delegated_to.as_deref()
+    }
 
-        let mut tasks = Self::_get_tasks(f)?;
+    pub fn goal(&self) -> Option<&str> {
+        self.goal.as_deref()
+    }
 
+    /// The ids of the related tasks, c.f. `relate`
+    pub fn related(&self) -> &[u64] {
+        &self.related
+    }
 
-        // Thinking from the user input perspective:
-        // User is expected to put in an index from 0 to the number of tasks (task.len())
-        // Hence that expectaion is combined with index bound check and error reporting as following
-        if index == 0 || index > tasks.len() {
-            return Err(Error::new(ErrorKind::InvalidInput, "Invalid Task ID"));
+    /// The id of the parent task, for a subtask
+    pub fn parent(&self) -> Option<u64> {
+        self.parent
+    }
 
-        }
-        // With the check above in place, this access by index call is certain to NOT PANIC
-        let task_to_complete = &mut tasks[index-1];
-        task_to_complete.completed();
+    /// How often the task comes back, for a recurring task
+    pub fn repeat(&self) -> Option<Recurrence> {
+        self.repeat
+    }
+
+    pub fn estimate(&self) -> Option<Duration> {
+        self.estimate.map(|minutes| Duration::minutes(minutes.into()))
+    }
+
+    /// The recorded changes and notes, oldest first
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+
+    /// The value of a field carried over from the source the task was imported from
+    pub fn extra(&self, key: &str) -> Option<&str> {
+        self.extra.get(key).map(String::as_str)
+    }
 
-        let f = OpenOptions::new()
-                    // Required as otherwise we overwrite the file with data smaller than
-                    // the current content presumably from the start of file as seek position
-                    // which corrupts the data
-                    .truncate(true)
-                    .write(true)
-                    .open(&journal_path)?;
+    pub(crate) fn has_tag(&self, tag: &str) -> bool {
+        self.tags().iter().any(|t| t == tag)
+    }
+
+    // fn _get_tasks(file: impl Read) -> ioResult<Vec<Task>>  {
+    fn _get_tasks(mut file: impl Read, size_hint: usize, backend: Backend) -> ioResult<Vec<Task>> {
+        // Comment: reading the whole journal into one preallocated buffer and parsing the slice is several times
+        // faster than serde_json::from_reader, which goes through the reader byte by byte
+        let mut span = trace::span("journal.read", &[]);
+        let mut content = Vec::with_capacity(size_hint);
+        file.read_to_end(&mut content)?;
+        span.record("bytes", content.len());
+        let content = compress::decompress(content)?;
 
-        let f = BufWriter::new(f);
+        let mut tasks = match backend {
+            Backend::Json => parse_journal(&content)?,
+            _ => backend.decode(&content)?,
+        };
+        span.record("tasks", tasks.len());
+        // Comment: the waiting tasks whose time has come are active for every command, and stay so with the next save
+        let now = clock::now();
+        for task in &mut tasks {
+            task.wake(now);
+        }
+        Ok(tasks)
+    }
 
-        Self::_write_tasks(&tasks, f)?;
+    fn _write_tasks(tasks: &Vec<Task>, mut file: impl Write, compression: Option<Compression>, backend: Backend) -> ioResult<()> {
+        let _span = trace::span("journal.write", &[("tasks", &tasks.len())]);
+        if compression.is_some() || backend != Backend::Json {
+            let content = backend.encode(tasks)?;
+            let content = match compression {
+                Some(compression) => compress::compress(compression, content)?,
+                None => content,
+            };
+            file.write_all(&content)?;
+            return file.flush();
+        }
+        let mut f = BufWriter::with_capacity(WRITE_BUFFER_SIZE, file);
+        serde_json::to_writer(&mut f, tasks)?;
+        // Comment: flushing explicitly, as dropping a BufWriter swallows the error of the final write
+        f.flush()
+    }
 
+    pub(crate) fn _check_writable(journal_path: &Path) -> ioResult<()> {
+        if is_read_only() {
+            return Err(Error::new(ErrorKind::PermissionDenied, tr("error-read-only", &[]))).with_path("writing journal", journal_path);
+        }
         Ok(())
-    }    
+    }
 
-    /// The method fetches the current tasks into a vec from the Json
-    /// and prints them out. Empty tasks is specifically handled within
-    /// # Examples
-    /// ```
-    /// use rusty_journal_clap::task;
-    /// use std::path::PathBuf;
-    /// task::Task::list(PathBuf::from("todo.json"));
-    /// ```    
-    pub fn list(journal_path: PathBuf, tag: Option<&String>) -> ioResult<()> {        
-        let f = OpenOptions::new()
-                            // write must be set first to enable create
-                            // https://doc.rust-lang.org/std/fs/struct.OpenOptions.html#method.create
-                            .write(true)
-                            .create(true)
-                            .read(true)
-                            .open(&journal_path)?;
+    fn _open_journal(journal_path: &Path) -> ioResult<(File, usize)> {
+        Self::_check_writable(journal_path)?;
+        let f = paths::resolve(journal_path).and_then(|resolved| OpenOptions::new()
+                                                                          .write(true)
+                                                                          .create(true)
+                                                                          .truncate(false)
+                                                                          .read(true)
+                                                                          .open(resolved))
+                                            .with_path("opening journal", journal_path)?;
+        let size = f.metadata().with_path("opening journal", journal_path)?.len() as usize;
+        Ok((f, size))
+    }
+
+    /// Reads all the tasks of the journal, creating an empty journal file if there isn't one yet
+    pub(crate) fn _load_tasks(journal_path: &Path) -> ioResult<Vec<Task>> {
+        let _span = trace::span("journal.load", &[("path", &journal_path.display())]);
+        if stdio::is_stdio(journal_path) {
+            let content = stdio::read().with_path("reading journal", journal_path)?;
+            return Self::_get_tasks(content.as_slice(), content.len(), Backend::of_journal(journal_path)).with_path("reading journal", journal_path);
+        }
+        if is_read_only() || held::is_held(journal_path) {
+            return Self::_read_tasks(journal_path);
+        }
+        let (f, size) = Self::_open_journal(journal_path)?;
+
+        Self::_get_tasks(f, size, Backend::of_journal(journal_path)).with_path("reading journal", journal_path)
+    }
 
+    /// Reads the tasks of a journal file without creating it, a missing journal has no tasks
+    pub(crate) fn _read_tasks(journal_path: &Path) -> ioResult<Vec<Task>> {
+        if held::is_held(journal_path) {
+            let content = held::read();
+            return Self::_get_tasks(content.as_slice(), content.len(), Backend::of_journal(journal_path)).with_path("reading journal", journal_path);
+        }
+        let f = match paths::resolve(journal_path).and_then(File::open) {
+            Ok(f) => f,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err).with_path("opening journal", journal_path),
+        };
+        let size = f.metadata().with_path("opening journal", journal_path)?.len() as usize;
+        Self::_get_tasks(f, size, Backend::of_journal(journal_path)).with_path("reading journal", journal_path)
+    }
 
-        let f = BufReader::new(f);
+    /// Reads the tasks, lets the update change them and writes them back, opening the journal only once.
+    /// Nothing is written when the update fails
+    pub(crate) fn _update_tasks<T>(journal_path: &Path, update: impl FnOnce(&mut Vec<Task>) -> ioResult<T>) -> JournalResult<T> {
+        Self::_update_tasks_changing(journal_path, |tasks| update(tasks).map(|updated| (updated, true)))
+    }
 
-        let tasks = Self::_get_tasks(f)?;
+    /// Like `_update_tasks`, for an update that may end up changing nothing, e.g. when no task matches or the user
+    /// declines: the update tells whether it changed the tasks, and the journal is only written, and an undo step
+    /// only recorded, when it did
+    pub(crate) fn _update_tasks_if(journal_path: &Path, update: impl FnOnce(&mut Vec<Task>) -> ioResult<bool>) -> JournalResult<()> {
+        Self::_update_tasks_changing(journal_path, |tasks| update(tasks).map(|changed| ((), changed)))
+    }
 
-        if tasks.is_empty() {
-            println!("Empty to-do list");
-        } else {
-            match tag {
-                Some(tag) => {
-                    for task in tasks.iter()
-                                            .filter(|&task| task.tags.as_ref()
-                                                // the family of mapping methods (e.g. map, is_some_and) on Option type would consume the ownership of the Option
-                                                // here the task.tags is a field of the Task struct, of Option<Vec<String>> type
-                                                // If directly followed by a is_some_and call, the ownership of the field would move out of the Task struct
-                                                // which obviously is a violation as it wouldn't be allowed by the compiler either
-                                                // The as_ref method of Option type is handy here since it creates another owned Option instance to be CONSUMED
-                                                // plus with the same refereced data inside the Option for further ops 
-                                                .is_some_and(|tags| tags
-                                                    .contains(&tag))) {
-                                                        println!("{}", task);
-                                                    }
-                         
-                },
-                None => {
-                    for task in tasks {
-                        println!("{}", task);
-                    }
-                }
+    fn _update_tasks_changing<T>(journal_path: &Path, update: impl FnOnce(&mut Vec<Task>) -> ioResult<(T, bool)>) -> JournalResult<T> {
+        let _span = trace::span("journal.update", &[("path", &journal_path.display())]);
+        if stdio::is_stdio(journal_path) || held::is_held(journal_path) {
+            let mut tasks = Self::_load_tasks(journal_path)?;
+            let (updated, changed) = update(&mut tasks)?;
+            // Comment: stdout carries the journal, changed or not, so it is always written
+            if changed || stdio::is_stdio(journal_path) {
+                Self::_orphan(&mut tasks);
+                Self::_save_tasks(journal_path, &tasks)?;
+            }
+            return Ok(updated);
+        }
+        Self::_check_writable(journal_path)?;
+        // Comment: the lock is held from the read to the write, so two commands running at once can't both
+        // read the same tasks and have the second write drop the changes of the first
+        let _lock = JournalLock::acquire(journal_path)?;
+        let trash = undo::TrashBackup::take(journal_path)?;
+        let backend = Backend::of_journal(journal_path);
+        let mut tasks = match paths::resolve(journal_path).and_then(File::open) {
+            Ok(f) => {
+                let size = f.metadata().with_path("opening journal", journal_path)?.len() as usize;
+                Self::_get_tasks(f, size, backend).with_path("reading journal", journal_path)?
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err).with_path("opening journal", journal_path).map_err(Into::into),
+        };
+
+        let (updated, changed) = update(&mut tasks)?;
+        if changed {
+            Self::_orphan(&mut tasks);
+            Self::_replace_journal(journal_path, &tasks, &trash)?;
+        }
+        Ok(updated)
+    }
+
+    /// Writes the tasks to a temporary file next to the journal and renames it over the journal, so the journal
+    /// is at any time either the old tasks or the new ones, and never half written when the process dies or the disk fills up.
+    /// Readers need no lock for the same reason
+    fn _replace_journal(journal_path: &Path, tasks: &Vec<Task>, trash: &undo::TrashBackup) -> ioResult<()> {
+        let target = Self::_journal_target(journal_path)?;
+        let file_name = target.file_name().unwrap_or_default().to_string_lossy();
+        let temp_path = target.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+        let written = File::create(&temp_path).and_then(|f| {
+            if let Ok(metadata) = fs::metadata(&target) {
+                f.set_permissions(metadata.permissions())?;
             }
+            Self::_write_tasks(tasks, &f, Compression::of_journal(journal_path), Backend::of_journal(journal_path))?;
+            f.sync_all()
+        }).with_path("writing journal", journal_path)
+          .and_then(|_| undo::record(&target, trash, clock::now()))
+          .and_then(|_| fs::rename(&temp_path, &target).with_path("writing journal", journal_path));
+        if written.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        written
+    }
+
+    /// The file the journal resolves to, which a journal that is a symlink points to
+    fn _journal_target(journal_path: &Path) -> ioResult<PathBuf> {
+        let resolved = paths::resolve(journal_path).with_path("writing journal", journal_path)?;
+        // Comment: a journal that is a symlink stays one, the rename replaces the file it points to
+        Ok(fs::canonicalize(&resolved).unwrap_or(resolved))
+    }
+
+    /// Appends the task to the tasks, giving it a fresh id
+    pub(crate) fn _push(tasks: &mut Vec<Task>, task: Task) {
+        Self::_push_all(tasks, [task]);
+    }
+
+    /// Appends all the new tasks to the tasks, giving them fresh ids.
+    /// Unlike repeated `_push` calls, this looks for the highest id in use only once
+    pub(crate) fn _push_all(tasks: &mut Vec<Task>, new_tasks: impl IntoIterator<Item = Task>) {
+        let next_id = tasks.iter()
+                           .filter_map(|task| task.id)
+                           .max()
+                           .map_or(1, |max| max + 1);
+        for (id, mut task) in (next_id..).zip(new_tasks) {
+            task.id = Some(id);
+            tasks.push(task);
         }
+    }
 
+    /// Checks a user input index, which goes from 1 to the number of tasks
+    pub(crate) fn _check_index(tasks: &[Task], index: usize) -> ioResult<()> {
+        if index == 0 || index > tasks.len() {
+            return Err(IndexError::out_of_range(index, tasks.len()));
+        }
         Ok(())
-    }    
+    }
 
-    /// This method helps with testing by clearing all the data
-    /// # Examples:
-    /// ```
-    /// use rusty_journal_clap::task;
-    /// use std::path::PathBuf;
-    /// task::Task::clear(PathBuf::from("todo.json"));
-    /// ```
-    pub fn clear(journal_path: PathBuf) -> ioResult<()> {
-        let f = OpenOptions::new()
-                                    .write(true)
-                                    .create(true)
-                                    .read(true)
-                                    .open(&journal_path)?;
+    fn _check_indices(tasks: &[Task], indices: &[usize]) -> ioResult<()> {
+        indices.iter().try_for_each(|&index| Self::_check_index(tasks, index))
+    }
 
-        let f = BufReader::new(f);
+    /// The (1-based) indices, sorted, along with the indices of all the subtasks of those tasks, theirs too and so on,
+    /// when `done` and `remove` cascade, c.f. `set_cascade`
+    pub(crate) fn _cascade(tasks: &[Task], indices: &[usize]) -> Vec<usize> {
+        let mut selected = indices.to_vec();
+        if is_cascade() {
+            let mut ids = indices.iter().filter_map(|&index| tasks[index - 1].id).collect::<HashSet<_>>();
+            // Comment: each pass takes in one more level of subtasks, until a pass finds none
+            loop {
+                let found = tasks.iter()
+                                 .filter(|task| task.parent.is_some_and(|parent| ids.contains(&parent)))
+                                 .filter_map(|task| task.id)
+                                 .filter(|id| !ids.contains(id))
+                                 .collect::<Vec<_>>();
+                if found.is_empty() {
+                    break;
+                }
+                ids.extend(found);
+            }
+            selected.extend(tasks.iter()
+                                 .enumerate()
+                                 .filter(|(_, task)| task.parent.is_some_and(|parent| ids.contains(&parent)))
+                                 .map(|(i, _)| i + 1));
+        }
+        selected.sort_unstable();
+        selected.dedup();
+        selected
+    }
 
-        let mut tasks = Self::_get_tasks(f)?;
+    /// Makes top-level tasks of the subtasks whose parent is no longer in the journal,
+    /// so that a new task which happens to get the id of the parent doesn't take them over
+    pub(crate) fn _orphan(tasks: &mut [Task]) {
+        let ids = tasks.iter().filter_map(|task| task.id).collect::<HashSet<_>>();
+        for task in tasks {
+            if task.parent.is_some_and(|parent| !ids.contains(&parent)) {
+                task.parent = None;
+            }
+        }
+    }
 
-        tasks.clear();
+    /// Adds the next occurrence of every completed recurring task, which hands its repetition over to it,
+    /// and returns how many were added. The next occurrence is due one period after the completed one was due,
+    /// or was completed when it had no due date, on the same day of the month as the first one, c.f. `Recurrence::next_due`
+    pub(crate) fn _regenerate(tasks: &mut Vec<Task>, now: DateTime<Utc>) -> usize {
+        let next = tasks.iter_mut()
+                        .filter(|task| task.is_complete())
+                        .filter_map(|task| {
+                            let repeat = task.repeat.take()?;
+                            let since = task.due.or(task.completed_at()).unwrap_or(now);
+                            let day = task.repeat_day.take().unwrap_or(since.day());
+                            let mut next = Self::new(task.name.clone(), task.tags.clone(), now);
+                            let due = repeat.next_due(since, day, now);
+                            next.due = Some(due);
+                            next.repeat_day = Some(day).filter(|&day| day != due.day());
+                            next.priority = task.priority;
+                            next.owner.clone_from(&task.owner);
+                            next.assignee.clone_from(&task.assignee);
+                            next.goal.clone_from(&task.goal);
+                            next.estimate = task.estimate;
+                            next.parent = task.parent;
+                            next.repeat = Some(repeat);
+                            Some(next)
+                        })
+                        .collect::<Vec<_>>();
+        let count = next.len();
+        Self::_push_all(tasks, next);
+        count
+    }
 
-        let f = OpenOptions::new()
-                            .truncate(true)
-                            .write(true)
-                            .open(&journal_path)?;
+    /// The id of the task at the index the options give as the parent of new tasks, if any
+    pub(crate) fn _parent_id(tasks: &mut [Task], options: &TaskOptions) -> ioResult<Option<u64>> {
+        options.parent.map(|index| {
+            Self::_check_index(tasks, index)?;
+            Ok(Self::_ensure_id(tasks, index))
+        }).transpose()
+    }
 
-        let f = BufWriter::new(f); 
-        Self::_write_tasks(&tasks, f)?;
+    /// Splits the tasks into the ones at the (1-based) indices and the others, both in journal order
+    pub(crate) fn _split_at_indices(tasks: Vec<Task>, indices: &[usize]) -> (Vec<Task>, Vec<Task>) {
+        let (selected, rest): (Vec<_>, Vec<_>) = tasks.into_iter()
+                                                      .enumerate()
+                                                      .partition(|(i, _)| indices.contains(&(i + 1)));
+        (selected.into_iter().map(|(_, task)| task).collect(),
+         rest.into_iter().map(|(_, task)| task).collect())
+    }
 
-        Ok(())        
+    /// Adds a new task with the options at the end of the tasks, giving it a fresh id, and returns its index.
+    /// This is what `add` and `Journal::add_task_with` do
+    pub(crate) fn _add(tasks: &mut Vec<Task>, name: String, tags: Option<Vec<String>>, options: &TaskOptions, now: DateTime<Utc>) -> ioResult<usize> {
+        let parent = Self::_parent_id(tasks, options)?;
+        let mut task = Self::new(name, tags, now);
+        options.apply(&mut task);
+        task.parent = parent;
+        Self::_push(tasks, task);
+        Ok(tasks.len())
+    }
+
+    /// Marks the tasks at the (1-based) indices as completed, along with their subtasks when `done` cascades, and adds
+    /// the next occurrence of the recurring ones. This is what `done` and `Journal::complete_task` do
+    pub(crate) fn _complete_at(tasks: &mut Vec<Task>, indices: &[usize], now: DateTime<Utc>) -> ioResult<()> {
+        Self::_check_indices(tasks, indices)?;
+
+        // Comment: the subtasks completed already keep the time they were completed at
+        for index in Self::_cascade(tasks, indices) {
+            if indices.contains(&index) || !tasks[index - 1].is_complete() {
+                tasks[index - 1].completed(now);
+            }
+        }
+        Self::_regenerate(tasks, now);
+        Ok(())
+    }
+
+    /// Takes the tasks at the (1-based) indices out of the tasks, along with their subtasks when `remove` cascades,
+    /// and returns them for the trash. This is what `remove` and `Journal::remove_task` do
+    pub(crate) fn _remove_at(tasks: &mut Vec<Task>, indices: &[usize]) -> ioResult<Vec<Task>> {
+        Self::_check_indices(tasks, indices)?;
+
+        let indices = Self::_cascade(tasks, indices);
+        let (removed, kept) = Self::_split_at_indices(std::mem::take(tasks), &indices);
+        *tasks = kept;
+        Self::_orphan(tasks);
+        Ok(removed)
+    }
+
+    /// Overwrites the journal with the given tasks
+    pub(crate) fn _save_tasks(journal_path: &Path, tasks: &Vec<Task>) -> ioResult<()> {
+        Self::_save_tasks_removing(journal_path, tasks, Vec::new())
+    }
+
+    /// Overwrites the journal with the given tasks, after moving the removed tasks to the trash
+    pub(crate) fn _save_tasks_removing(journal_path: &Path, tasks: &Vec<Task>, removed: Vec<Task>) -> ioResult<()> {
+        let _span = trace::span("journal.save", &[("path", &journal_path.display())]);
+        Self::_check_writable(journal_path)?;
+        if stdio::is_stdio(journal_path) || held::is_held(journal_path) {
+            let mut content = Vec::new();
+            Self::_write_tasks(tasks, &mut content, None, Backend::of_journal(journal_path)).with_path("writing journal", journal_path)?;
+            // the tasks removed from a held journal are kept until it is written, c.f. `hold`
+            trash::put(journal_path, removed, tasks, clock::now())?;
+            match stdio::is_stdio(journal_path) {
+                true => stdio::write(content),
+                false => held::write(content),
+            }
+            return Ok(());
+        }
+        let _lock = JournalLock::acquire(journal_path)?;
+        let trash = undo::TrashBackup::take(journal_path)?;
+        // Comment: the tasks go to the trash before they leave the journal, as with the `remove` command
+        trash::put(journal_path, removed, tasks, clock::now())?;
+        Self::_replace_journal(journal_path, tasks, &trash)
+    }
+
+    /// Applies the update to the tasks kept in memory, e.g. by the daemon, and writes them to the journal under its lock.
+    /// The tasks are read again first only when the watcher tells the journal changed on disk, and they are read again
+    /// when the update fails, so that they never differ from the journal
+    pub(crate) fn _update_resident<T>(journal_path: &Path, tasks: &mut Vec<Task>, watcher: &mut Watcher,
+                                      update: impl FnOnce(&mut Vec<Task>) -> ioResult<T>) -> JournalResult<T> {
+        Self::_check_writable(journal_path)?;
+        let _lock = JournalLock::acquire(journal_path)?;
+        if watcher.changed() {
+            *tasks = Self::_load_tasks(journal_path)?;
+        }
+        let trash = undo::TrashBackup::take(journal_path)?;
+
+        let updated = update(tasks).and_then(|updated| {
+            Self::_orphan(tasks);
+            Self::_replace_journal(journal_path, tasks, &trash).map(|_| updated)
+        });
+        if updated.is_err() {
+            *tasks = Self::_load_tasks(journal_path)?;
+        }
+        // Comment: still under the lock, so that only the write of this update is taken for seen, not one of another process
+        watcher.changed();
+        Ok(updated?)
+    }
+
+    /// Runs the steps, e.g. those of a macro, on the journal held in memory, c.f. `held`: the journal is read once
+    /// before the first step and written once after the last, as a single change to undo, and left as it was when
+    /// a step fails. It stays locked all along, so the commands changing it meanwhile wait for the steps to be done
+    pub(crate) fn hold<E: From<Error>>(journal_path: &Path, steps: impl FnOnce() -> Result<(), E>) -> Result<(), E> {
+        // Comment: a journal read from stdin is kept in memory already
+        if stdio::is_stdio(journal_path) {
+            return steps();
+        }
+        let _lock = match is_read_only() {
+            true => None,
+            false => Some(JournalLock::acquire(journal_path)?),
+        };
+        let trash = undo::TrashBackup::take(journal_path)?;
+        let content = match paths::resolve(journal_path).and_then(fs::read) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err).with_path("reading journal", journal_path).map_err(Into::into),
+        };
+
+        held::hold(journal_path, content);
+        let result = steps();
+        match (result, held::release()) {
+            (Ok(()), Some(held)) if held.written => {
+                let tasks = Self::_get_tasks(held.content.as_slice(), held.content.len(), Backend::of_journal(journal_path))
+                                .with_path("reading journal", journal_path)?;
+                trash::put(journal_path, held.removed, &tasks, clock::now())?;
+                Ok(Self::_replace_journal(journal_path, &tasks, &trash)?)
+            }
+            (result, _) => result,
+        }
+    }
+
+
+    /// The method fetches the current tasks as a vec from the Json
+    /// and add a new task by pushing to the vec and write back to the Json
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::add(journal.path(), "play".to_string(), Some(vec!["good first issue".to_string()])).unwrap();
+    /// ```
+    pub fn add(journal_path: PathBuf, name: String, tags: Option<Vec<String>>) -> JournalResult<()> {
+        Self::add_with(journal_path, name, tags, &TaskOptions::default())
+    }
+
+    /// The method adds a new task like `add`, with the given priority, owner and assignee
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task::{self, Priority, TaskOptions};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// let options = TaskOptions { priority: Some(Priority::High), assignee: Some("alice".to_string()), ..TaskOptions::default() };
+    /// task::Task::add_with(journal.path(), "pay rent".to_string(), None, &options).unwrap();
+    /// ```
+    pub fn add_with(journal_path: PathBuf, name: String, tags: Option<Vec<String>>, options: &TaskOptions) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            Self::_add(tasks, name, tags, options, clock::now())?;
+            Ok(())
+        })
+    }
+
+    /// The method adds several new tasks at once, reading and writing the Json a single time
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::add_many(journal.path(), vec![("read".to_string(), None), ("write".to_string(), None)], &task::TaskOptions::default()).unwrap();
+    /// ```
+    pub fn add_many(journal_path: PathBuf, new_tasks: Vec<(String, Option<Vec<String>>)>, options: &TaskOptions) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            let now = clock::now();
+            let parent = Self::_parent_id(tasks, options)?;
+            Self::_push_all(tasks, new_tasks.into_iter().map(|(name, tags)| {
+                let mut task = Self::new(name, tags, now);
+                options.apply(&mut task);
+                task.parent = parent;
+                task
+            }));
+            Ok(())
+        })
+    }
+
+    /// Drops the new tasks whose import uid is already in the journal, returning how many were dropped
+    fn _skip_imported(tasks: &[Task], new_tasks: &mut Vec<Task>) -> usize {
+        let known = tasks.iter()
+                         .filter_map(|task| task.extra(UID_FIELD))
+                         .collect::<std::collections::HashSet<_>>();
+        let before = new_tasks.len();
+        new_tasks.retain(|task| task.extra(UID_FIELD).is_none_or(|uid| !known.contains(uid)));
+        before - new_tasks.len()
+    }
+
+    /// The method appends imported tasks to the journal, or with dry_run only prints them as a preview.
+    /// Tasks imported before, as told by their uid, are skipped
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::{import, normalize::{Normalizer, Rules}, task};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// let normalizer = Normalizer::new(&Rules::default()).unwrap();
+    /// let tasks = import::from_csv("name,tags\nwater plants,home\n", &import::ColumnMap::default(), &normalizer).unwrap();
+    /// task::Task::import(journal.path(), tasks, true).unwrap();
+    /// ```
+    pub fn import(journal_path: PathBuf, mut new_tasks: Vec<Task>, dry_run: bool) -> JournalResult<()> {
+        let skipped_message = |skipped: usize| if skipped > 0 { tr("import-skipped", &[("count", &skipped)]) } else { String::new() };
+
+        if dry_run {
+            // Comment: a preview doesn't create the journal, which _load_tasks would
+            let tasks = if journal_path.exists() { Self::_load_tasks(&journal_path)? } else { Vec::new() };
+            let skipped = Self::_skip_imported(&tasks, &mut new_tasks);
+            for task in &new_tasks {
+                stdio::say(task);
+            }
+            stdio::say(tr("tasks-would-import", &[("count", &new_tasks.len())]) + &skipped_message(skipped));
+            return Ok(());
+        }
+
+        let (imported, skipped) = Self::_update_tasks(&journal_path, |tasks| {
+            let skipped = Self::_skip_imported(tasks, &mut new_tasks);
+            let imported = new_tasks.len();
+            Self::_push_all(tasks, new_tasks);
+            Ok((imported, skipped))
+        })?;
+        stdio::say(tr("tasks-imported", &[("count", &imported)]) + &skipped_message(skipped));
+
+        Ok(())
+    }
+
+    /// The method appends all the tasks of another journal to this journal, giving them fresh ids.
+    /// The other journal is left untouched
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// let other = TempJournal::seeded().unwrap();
+    /// task::Task::append(journal.path(), other.path()).unwrap();
+    /// assert_eq!(journal.tasks().unwrap().len(), 2 * TempJournal::FIXTURE.len());
+    /// ```
+    pub fn append(journal_path: PathBuf, other_path: PathBuf) -> JournalResult<()> {
+        let other_tasks = match stdio::is_stdio(&other_path) {
+            true => Self::_load_tasks(&other_path)?,
+            false => paths::resolve(&other_path).and_then(File::open)
+                                                .and_then(|f| {
+                                                    let size = f.metadata()?.len() as usize;
+                                                    Self::_get_tasks(f, size, Backend::from_extension(&other_path).unwrap_or(Backend::Json))
+                                                })
+                                                .with_path("reading journal", &other_path)?,
+        };
+
+        let appended = other_tasks.len();
+        Self::_update_tasks(&journal_path, |tasks| {
+            Self::_push_all(tasks, other_tasks);
+            Ok(())
+        })?;
+        stdio::say(tr("tasks-appended", &[("count", &appended)]));
+
+        Ok(())
+    }
+
+    /// The method fetches the current tasks into a vec from the Json
+    /// and moves the task identified by the user input index (with validation) to the trash
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::remove(journal.path(), 1).unwrap();
+    /// ```      
+    pub fn remove(journal_path: PathBuf, index: usize) -> JournalResult<()> {
+        Self::remove_many(journal_path, &[index])
+    }
+
+    /// The method adds and removes tags on every task matching the filter, with a single read and write of the Json.
+    /// With dry_run, the matching tasks are only printed and the journal is left untouched
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::{filter::TaskFilter, task};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// let filter = TaskFilter::parse("tag:old-project").unwrap();
+    /// task::Task::edit_where(journal.path(), &filter, &["archive".to_string()], &["old-project".to_string()], true).unwrap();
+    /// ```
+    pub fn edit_where(journal_path: PathBuf, filter: &TaskFilter, add_tags: &[String], remove_tags: &[String], dry_run: bool) -> JournalResult<()> {
+        let now = clock::now();
+        let edit = |tasks: &mut Vec<Task>| {
+            let matching = filter.matching(tasks);
+            for (index, task) in tasks.iter_mut()
+                                      .enumerate()
+                                      .filter(|(i, _)| matching.binary_search(i).is_ok()) {
+                if !dry_run {
+                    for tag in add_tags {
+                        task.add_tag(tag.to_owned(), now);
+                    }
+                    for tag in remove_tags {
+                        task.remove_tag(tag, now);
+                    }
+                }
+                stdio::say(format!("{}: {}", index + 1, task));
+            }
+            Ok(matching.len())
+        };
+
+        if dry_run {
+            let affected = edit(&mut Self::_load_tasks(&journal_path)?)?;
+            stdio::say(tr("tasks-would-update", &[("count", &affected)]));
+            return Ok(());
+        }
+
+        let affected = Self::_update_tasks(&journal_path, edit)?;
+        stdio::say(tr("tasks-updated", &[("count", &affected)]));
+
+        Ok(())
+    }
+
+    /// The method changes the tasks at the indices in place, e.g. to fix a typo in a name, keeping their creation time and history.
+    /// Every change is recorded in the history of the task. Only a single task can be renamed at a time
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task::{self, TaskEdit};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// let edit = TaskEdit { name: Some("Buy oat milk".to_string()), add_tags: vec!["errands".to_string()], ..TaskEdit::default() };
+    /// task::Task::edit(journal.path(), &[1], &edit).unwrap();
+    /// ```
+    pub fn edit(journal_path: PathBuf, indices: &[usize], edit: &TaskEdit) -> JournalResult<()> {
+        if edit.name.as_ref().is_some_and(|name| name.trim().is_empty()) {
+            return Err(Error::new(ErrorKind::InvalidInput, tr("error-empty-name", &[])).into());
+        }
+        if edit.name.is_some() && indices.len() > 1 {
+            return Err(Error::new(ErrorKind::InvalidInput, tr("error-rename-several", &[])).into());
+        }
+        let edited = Self::_update_tasks(&journal_path, |tasks| {
+            Self::_check_indices(tasks, indices)?;
+            let now = clock::now();
+            Ok(indices.iter()
+                      .map(|&index| {
+                          edit.apply(&mut tasks[index - 1], now);
+                          format!("{}: {}", index, tasks[index - 1])
+                      })
+                      .collect::<Vec<_>>())
+        })?;
+        for line in edited {
+            stdio::say(line);
+        }
+
+        Ok(())
+    }
+
+    /// The method moves the task at the index to the new position, shifting the tasks in between
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::move_to(journal.path(), 3, 1).unwrap();
+    /// ```
+    pub fn move_to(journal_path: PathBuf, index: usize, position: usize) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            Self::_check_index(tasks, index)?;
+            if position == 0 || position > tasks.len() {
+                return Err(Error::new(ErrorKind::InvalidInput, tr("error-invalid-position", &[])));
+            }
+
+            let task = tasks.remove(index - 1);
+            tasks.insert(position - 1, task);
+            Ok(())
+        })
+    }
+
+    /// The method moves the task at the index right after the task at the other index
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::move_after(journal.path(), 1, 3).unwrap();
+    /// ```
+    pub fn move_after(journal_path: PathBuf, index: usize, after: usize) -> JournalResult<()> {
+        if index == after {
+            return Err(Error::new(ErrorKind::InvalidInput, tr("error-move-after-itself", &[])).into());
+        }
+        // once the task is taken out, the tasks after it shift up by one
+        let position = if after > index { after } else { after + 1 };
+        Self::move_to(journal_path, index, position)
+    }
+
+    /// The method moves the tasks at the indices to the top of the journal, keeping their relative order
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::bump(journal.path(), &[1]).unwrap();
+    /// ```
+    pub fn bump(journal_path: PathBuf, indices: &[usize]) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            Self::_check_indices(tasks, indices)?;
+
+            let (mut selected, rest) = Self::_split_at_indices(std::mem::take(tasks), indices);
+            selected.extend(rest);
+            *tasks = selected;
+            Ok(())
+        })
+    }
+
+    /// The method moves the tasks at the indices to the bottom of the journal, keeping their relative order
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::sink(journal.path(), &[1]).unwrap();
+    /// ```
+    pub fn sink(journal_path: PathBuf, indices: &[usize]) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            Self::_check_indices(tasks, indices)?;
+
+            let (selected, mut rest) = Self::_split_at_indices(std::mem::take(tasks), indices);
+            rest.extend(selected);
+            *tasks = rest;
+            Ok(())
+        })
+    }
+
+    /// The method exchanges the positions of two tasks
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::swap(journal.path(), 1, 2).unwrap();
+    /// ```
+    pub fn swap(journal_path: PathBuf, index: usize, other: usize) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            Self::_check_index(tasks, index)?;
+            Self::_check_index(tasks, other)?;
+
+            tasks.swap(index - 1, other - 1);
+            Ok(())
+        })
+    }
+
+    /// The method marks every active task matching the filter as completed, with a single write of the Json.
+    /// The affected tasks are printed first, then the user is asked for confirmation unless assume_yes is set.
+    /// With dry_run, the journal is left untouched
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::{filter::TaskFilter, task};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::complete_where(journal.path(), &TaskFilter::tagged("errand"), false, true).unwrap();
+    /// ```
+    pub fn complete_where(journal_path: PathBuf, filter: &TaskFilter, dry_run: bool, assume_yes: bool) -> JournalResult<()> {
+        let select = |tasks: &[Task]| {
+            let matching = filter.matching(tasks).into_iter().map(|i| i + 1).collect::<Vec<_>>();
+            let affected = Self::_cascade(tasks, &matching)
+                                 .into_iter()
+                                 .map(|index| index - 1)
+                                 .filter(|&i| !tasks[i].is_complete())
+                                 .collect::<Vec<_>>();
+            for &i in &affected {
+                stdio::say(format!("{}: {}", i + 1, tasks[i]));
+            }
+            affected
+        };
+
+        if dry_run {
+            let affected = select(&Self::_load_tasks(&journal_path)?);
+            stdio::say(tr("tasks-would-complete", &[("count", &affected.len())]));
+            return Ok(());
+        }
+
+        // Comment: the journal stays locked while the user confirms, so the indices shown can't shift in the meantime
+        Self::_update_tasks_if(&journal_path, |tasks| {
+            let affected = select(tasks);
+            if affected.is_empty() {
+                stdio::say(tr("nothing-to-complete", &[]));
+                return Ok(false);
+            }
+            if !assume_yes && !prompt::confirm(&tr("confirm-complete", &[("count", &affected.len())]))? {
+                stdio::say(tr("aborted", &[]));
+                return Ok(false);
+            }
+
+            Self::_complete_at(tasks, &affected.iter().map(|i| i + 1).collect::<Vec<_>>(), clock::now())?;
+            stdio::say(tr("tasks-completed", &[("count", &affected.len())]));
+            Ok(true)
+        })
+    }
+
+    /// The method removes every task matching the filter, with a single write of the Json.
+    /// The affected tasks are printed first, then the user is asked for confirmation unless assume_yes is set.
+    /// With dry_run, the journal is left untouched
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::{filter::TaskFilter, task};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::remove_where(journal.path(), &TaskFilter::parse("tag:scratch").unwrap(), false, true).unwrap();
+    /// ```
+    pub fn remove_where(journal_path: PathBuf, filter: &TaskFilter, dry_run: bool, assume_yes: bool) -> JournalResult<()> {
+        let select = |tasks: &[Task]| {
+            let matching = filter.matching(tasks).into_iter().map(|i| i + 1).collect::<Vec<_>>();
+            let matching = Self::_cascade(tasks, &matching).into_iter().map(|index| index - 1).collect::<Vec<_>>();
+            for &i in &matching {
+                stdio::say(format!("{}: {}", i + 1, tasks[i]));
+            }
+            matching
+        };
+
+        if dry_run {
+            let matching = select(&Self::_load_tasks(&journal_path)?);
+            stdio::say(tr("tasks-would-remove", &[("count", &matching.len())]));
+            return Ok(());
+        }
+
+        // Comment: the journal stays locked while the user confirms, so the indices shown can't shift in the meantime
+        Self::_update_tasks_if(&journal_path, |tasks| {
+            let matching = select(tasks);
+            if matching.is_empty() {
+                stdio::say(tr("nothing-to-remove", &[]));
+                return Ok(false);
+            }
+            if !assume_yes && !prompt::confirm(&tr("confirm-remove", &[("count", &matching.len())]))? {
+                stdio::say(tr("aborted", &[]));
+                return Ok(false);
+            }
+
+            let removed = Self::_remove_at(tasks, &matching.iter().map(|i| i + 1).collect::<Vec<_>>())?;
+            // Comment: the tasks go to the trash before they leave the journal, so a failure in between duplicates them rather than loses them
+            trash::put(&journal_path, removed, tasks, clock::now())?;
+            stdio::say(tr("tasks-removed", &[("count", &matching.len())]));
+            Ok(true)
+        })
+    }
+
+    /// The method rewrites the journal in a canonical order, active tasks before completed ones
+    /// and then oldest first, giving an id to the tasks without one. The mapping from the old to the
+    /// new positions is printed so that index based scripts can be updated
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::renumber(journal.path()).unwrap();
+    /// ```
+    pub fn renumber(journal_path: PathBuf) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            let mut numbered = std::mem::take(tasks).into_iter()
+                                                    .enumerate()
+                                                    .collect::<Vec<_>>();
+            // the sort is stable, so tasks created at the same second keep their relative order
+            numbered.sort_by_key(|(_, task)| (task.is_complete(), task.creted_at));
+
+            let mut next_id = numbered.iter()
+                                      .filter_map(|(_, task)| task.id)
+                                      .max()
+                                      .unwrap_or(0);
+            for (new_index, (old_index, mut task)) in numbered.into_iter().enumerate() {
+                let id = *task.id.get_or_insert_with(|| {
+                    next_id += 1;
+                    next_id
+                });
+                stdio::say(format!("{:>4} -> {:<4} id {:<4} {}", old_index + 1, new_index + 1, id, task.name));
+                tasks.push(task);
+            }
+            Ok(())
+        })
+    }
+
+    /// The method removes all the tasks at the indices at once, after checking that every index is valid
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::remove_many(journal.path(), &[1, 2]).unwrap();
+    /// ```
+    pub fn remove_many(journal_path: PathBuf, indices: &[usize]) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            let removed = Self::_remove_at(tasks, indices)?;
+            trash::put(&journal_path, removed, tasks, clock::now())
+        })
+    }
+
+    /// The method marks all the tasks at the indices as completed at once, after checking that every index is valid
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::complete_many(journal.path(), &[1, 2]).unwrap();
+    /// ```
+    pub fn complete_many(journal_path: PathBuf, indices: &[usize]) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| Self::_complete_at(tasks, indices, clock::now()))
+    }
+
+    /// The method fetches the current tasks into a vec from the Json
+    /// and update the task identified by the user input index (with validation) to completed state
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::complete(journal.path(), 1).unwrap();
+    /// ```
+    pub fn complete(journal_path: PathBuf, index: usize) -> JournalResult<()> {
+        Self::complete_many(journal_path, &[index])
+    }    
+
+    /// The method fetches the current tasks into a vec from the Json
+    /// and prints them out. Empty tasks is specifically handled within
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::list(journal.path(), None).unwrap();
+    /// ```    
+    pub fn list(journal_path: PathBuf, tag: Option<&String>) -> JournalResult<()> {
+        // URLs are underlined on a terminal only, so piped output stays plain text
+        let mode = if io::stdout().is_terminal() { ColorMode::Ansi } else { ColorMode::Never };
+        let filter = tag.map(|tag| TaskFilter::tagged(tag)).unwrap_or_default();
+        // Comment: println! locks and flushes stdout for every line, which dominates the time of listing a large journal
+        Ok(Self::_list_to(journal_path, &ListOptions { filter, ..ListOptions::default() }, BufWriter::new(io::stdout().lock()), mode)?)
+    }
+
+    /// The method lists the tasks like `list`, but into the given writer instead of stdout.
+    /// Listing a journal of 100k tasks stays within 250ms in a release build, c.f. `cargo bench`
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// let mut listed = Vec::new();
+    /// task::Task::list_to(journal.path(), None, &mut listed).unwrap();
+    /// ```
+    pub fn list_to(journal_path: PathBuf, tag: Option<&String>, out: impl Write) -> JournalResult<()> {
+        let filter = tag.map(|tag| TaskFilter::tagged(tag)).unwrap_or_default();
+        Ok(Self::_list_to(journal_path, &ListOptions { filter, ..ListOptions::default() }, out, ColorMode::Never)?)
+    }
+
+    /// The method lists the tasks matching the filter of the options, like `list` does with a tag,
+    /// sorted and optionally prefixed with their urgency
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::{filter::TaskFilter, task::{self, ListOptions, SortBy}};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// let options = ListOptions { filter: TaskFilter::assigned("alice"), sort: SortBy::Urgency, ..ListOptions::default() };
+    /// task::Task::list_with(journal.path(), &options).unwrap();
+    /// ```
+    pub fn list_with(journal_path: PathBuf, options: &ListOptions) -> JournalResult<()> {
+        let mode = if io::stdout().is_terminal() { ColorMode::Ansi } else { ColorMode::Never };
+        Ok(Self::_list_to(journal_path, options, BufWriter::new(io::stdout().lock()), mode)?)
+    }
+
+    fn _write_listed(out: &mut impl Write, task: &Task, mode: ColorMode, now: DateTime<Utc>) -> ioResult<()> {
+        match mode {
+            ColorMode::Never => writeln!(out, "{}", task),
+            // Comment: overdue tasks are shown in red, only when colored, so piped listings stay as they are
+            _ if task.is_overdue(now) => writeln!(out, "{}", color::paint(&url::underline(&task.to_string(), mode), Color::Red, mode)),
+            _ => writeln!(out, "{}", url::underline(&task.to_string(), mode)),
+        }
+    }
+
+    fn _list_to(journal_path: PathBuf, options: &ListOptions, mut out: impl Write, mode: ColorMode) -> ioResult<()> {
+        let tasks = Self::_load_tasks(&journal_path)?;
+
+        let format = output::format();
+        if tasks.is_empty() && format == OutputFormat::Human {
+            writeln!(out, "{}", tr("empty-list", &[]))?;
+            return out.flush();
+        }
+
+        // Comment: scoring is skipped when the urgency isn't needed, to keep listing a large journal fast
+        let now = clock::now();
+        let with_urgency = options.sort == SortBy::Urgency || options.show_urgency;
+        let mut listed = tasks.iter()
+                              .enumerate()
+                              .filter(|(_, task)| task.is_waiting() == options.waiting && options.filter.matches(task))
+                              .map(|(i, task)| (i + 1, task, if with_urgency { score::score(task, now, &options.coefficients).total() } else { 0.0 }))
+                              .collect::<Vec<_>>();
+        // the sorts are stable, so equal tasks keep the journal order
+        match options.sort {
+            SortBy::Index => {},
+            SortBy::Urgency => listed.sort_by(|(_, a, a_urgency), (_, b, b_urgency)| a.is_complete().cmp(&b.is_complete())
+                                                                                     .then(b_urgency.total_cmp(a_urgency))),
+            SortBy::Due => listed.sort_by_key(|(_, task, _)| (task.is_complete(), task.due.is_none(), task.due)),
+            SortBy::Priority => listed.sort_by_key(|(_, task, _)| (task.is_complete(), std::cmp::Reverse(task.priority))),
+        }
+
+        match format {
+            OutputFormat::Json => output::write_json(&mut out, &Self::_indexed(&listed), options.redact)?,
+            OutputFormat::Porcelain => output::write_porcelain(&mut out, &Self::_indexed(&listed), options.redact)?,
+            OutputFormat::Human => {
+                let order = if options.tree { Self::_tree(&listed) } else { (0..listed.len()).map(|position| (position, 0)).collect() };
+                for (position, depth) in order {
+                    let (_, task, urgency) = listed[position];
+                    if options.show_urgency {
+                        write!(out, "{:>6.2}  ", urgency)?;
+                    }
+                    write!(out, "{}", "  ".repeat(depth))?;
+                    if options.redact {
+                        writeln!(out, "{}", Redacted(task))?;
+                    } else {
+                        Self::_write_listed(&mut out, task, mode, now)?;
+                    }
+                }
+                if let Some(after) = options.stale_banner {
+                    let stale = tasks.iter().filter(|task| task.is_stale(after, now)).count();
+                    if stale > 0 {
+                        writeln!(out, "\n{}", tr("stale-banner", &[("count", &stale), ("days", &after.num_days()), ("span", &format!("{}d", after.num_days()))]))?;
+                    }
+                }
+            }
+        }
+
+        out.flush()
+    }
+
+    /// The positions of the listed tasks with their depth, each task followed by its subtasks one level deeper.
+    /// A subtask whose parent isn't listed is shown at the top level, in the listed order like the others
+    fn _tree(listed: &[(usize, &Task, f64)]) -> Vec<(usize, usize)> {
+        let positions = listed.iter()
+                              .enumerate()
+                              .filter_map(|(position, (_, task, _))| task.id.map(|id| (id, position)))
+                              .collect::<std::collections::HashMap<_, _>>();
+        let mut children = vec![Vec::new(); listed.len()];
+        let mut roots = Vec::new();
+        for (position, (_, task, _)) in listed.iter().enumerate() {
+            match task.parent.and_then(|parent| positions.get(&parent)) {
+                Some(&parent) if parent != position => children[parent].push(position),
+                _ => roots.push(position),
+            }
+        }
+
+        // Comment: a journal edited by hand can have subtasks that are their own ancestors, which no root leads to,
+        // so the tasks not reached from the roots are taken as roots too, and each task is visited only once
+        let mut visited = vec![false; listed.len()];
+        let mut order = Vec::with_capacity(listed.len());
+        for root in roots.into_iter().chain(0..listed.len()) {
+            let mut stack = vec![(root, 0)];
+            while let Some((position, depth)) = stack.pop() {
+                if std::mem::replace(&mut visited[position], true) {
+                    continue;
+                }
+                order.push((position, depth));
+                stack.extend(children[position].iter().rev().map(|&child| (child, depth + 1)));
+            }
+        }
+        order
+    }
+
+    fn _indexed<'a>(listed: &[(usize, &'a Task, f64)]) -> Vec<(usize, &'a Task)> {
+        listed.iter().map(|&(index, task, _)| (index, task)).collect()
+    }
+
+    /// The method lists the tasks through a user script: a script evaluating to a bool filters the tasks,
+    /// while a script evaluating to any other value prints that value in place of the task
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::{filter::TaskFilter, script::Script, task};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// let script = Script::parse(r#"!("done" in tags)"#).unwrap();
+    /// task::Task::list_scripted(journal.path(), &TaskFilter::default(), &script).unwrap();
+    /// ```
+    #[cfg(feature = "scripting")]
+    pub fn list_scripted(journal_path: PathBuf, filter: &TaskFilter, script: &Script) -> JournalResult<()> {
+        let tasks = Self::_load_tasks(&journal_path)?;
+
+        if tasks.is_empty() {
+            println!("{}", tr("empty-list", &[]));
+            return Ok(());
+        }
+
+        // the index is taken before the filtering so that scripts see the same
+        // position as the one expected by e.g. the remove subcommand
+        for (index, task) in tasks.iter()
+                                  .enumerate()
+                                  .map(|(i, task)| (i + 1, task))
+                                  .filter(|(_, task)| filter.matches(task)) {
+            match script.eval(index, task)? {
+                Value::Bool(true) => println!("{}", task),
+                Value::Bool(false) | Value::Unit => {},
+                line => println!("{}", line),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The method counts the active tasks, optionally only those with the tag, leaving out the waiting ones. Meant for shell prompts,
+    /// it only reads the state, tags and due date of each task, and doesn't create a missing journal but counts it as empty.
+    /// Counting a journal of 1k tasks stays under a millisecond in a release build, c.f. `cargo bench`
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// let pending = task::Task::count(journal.path(), Some("work")).unwrap();
+    /// ```
+    pub fn count(journal_path: PathBuf, tag: Option<&str>) -> JournalResult<usize> {
+        Ok(Self::count_with_overdue(journal_path, tag)?.0)
+    }
+
+    /// The method counts the active tasks like `count`, along with the open tasks past their due date, c.f. `is_overdue`,
+    /// reading the journal only once
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// assert_eq!(task::Task::count_with_overdue(journal.path(), None).unwrap(), (3, 0));
+    /// ```
+    pub fn count_with_overdue(journal_path: PathBuf, tag: Option<&str>) -> JournalResult<(usize, usize)> {
+        let read = match (stdio::is_stdio(&journal_path), held::is_held(&journal_path)) {
+            (true, _) => stdio::read(),
+            (false, true) => Ok(held::read()),
+            (false, false) => paths::resolve(&journal_path).and_then(fs::read),
+        };
+        let content = match read.and_then(compress::decompress) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok((0, 0)),
+            Err(err) => return Err(err).with_path("reading journal", &journal_path).map_err(Into::into),
+        };
+        if content.iter().all(u8::is_ascii_whitespace) {
+            return Ok((0, 0));
+        }
+
+        let tasks: Vec<CountedTask> = Backend::of_journal(&journal_path).decode(&content)
+                                                                        .with_path("reading journal", &journal_path)?;
+        let now = clock::now();
+        let tagged = tasks.iter()
+                          .filter(|task| tag.is_none_or(|tag| task.tags.as_ref().is_some_and(|tags| tags.iter().any(|t| t == tag))));
+        let pending = tagged.clone()
+                            .filter(|task| match task.state.kind {
+                                CountedState::Active => true,
+                                CountedState::Waiting => task.state.until.is_some_and(|until| until <= now),
+                                CountedState::Complete => false,
+                            })
+                            .count();
+        let overdue = tagged.filter(|task| task.state.kind != CountedState::Complete && task.due.is_some_and(|due| due < now))
+                            .count();
+        Ok((pending, overdue))
+    }
+
+    /// The method renders all the tasks through a report template and prints the result
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::{task, template::Template};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// let template = Template::parse("{{ tasks | length }} tasks", false).unwrap();
+    /// task::Task::report(journal.path(), &template).unwrap();
+    /// ```
+    pub fn report(journal_path: PathBuf, template: &Template) -> JournalResult<()> {
+        let tasks = Self::_load_tasks(&journal_path)?;
+
+        print!("{}", template.render(&tasks)?);
+
+        Ok(())
+    }
+
+    /// The method prints a plain text summary of the tasks added and completed in the period, and of the overdue
+    /// and pending tasks. It fails with `JournalError::Overdue` once the summary is printed when any task is overdue
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::{summary::Period, task};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::report_plain(journal.path(), Period::Weekly).unwrap();
+    /// ```
+    pub fn report_plain(journal_path: PathBuf, period: Period) -> JournalResult<()> {
+        let tasks = Self::_load_tasks(&journal_path)?;
+
+        let now = clock::now();
+        print!("{}", summary::plain(&tasks, period, now));
+
+        match tasks.iter().filter(|task| task.is_overdue(now)).count() {
+            0 => Ok(()),
+            count => Err(JournalError::Overdue { count }),
+        }
+    }
+
+    /// The method prints the plan of the days of the horizon starting today, c.f. `plan::plan`
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use chrono::Duration;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::plan(journal.path(), Duration::days(7), Duration::hours(8)).unwrap();
+    /// ```
+    pub fn plan(journal_path: PathBuf, horizon: Duration, capacity: Duration) -> JournalResult<()> {
+        let tasks = Self::_load_tasks(&journal_path)?;
+
+        // a horizon of 36h covers today and tomorrow
+        let days = (horizon.num_minutes() + 24 * 60 - 1) / (24 * 60);
+        print!("{}", plan::plan(&tasks, days, capacity, clock::now()));
+
+        Ok(())
+    }
+
+    /// The method puts the tasks at the indices on hold until the given time, optionally noting what they wait for.
+    /// Waiting tasks are left out of `list`, `next` and `count` until then, `list --waiting` shows them meanwhile
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::{date, task};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::wait(journal.path(), &[1], date::parse("friday").unwrap(), Some("Bob's reply".to_string())).unwrap();
+    /// ```
+    pub fn wait(journal_path: PathBuf, indices: &[usize], until: DateTime<Utc>, reason: Option<String>) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            Self::_check_indices(tasks, indices)?;
+            let now = clock::now();
+            for &index in indices {
+                tasks[index - 1].wait_until(until, reason.clone(), now);
+            }
+            Ok(())
+        })
+    }
+
+    /// The method prints how many tasks are in each state and how many are stale, and the open tasks
+    /// left untouched the longest, c.f. `stats::stats`
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use chrono::Duration;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::stats(journal.path(), Duration::days(30)).unwrap();
+    /// ```
+    pub fn stats(journal_path: PathBuf, stale_after: Duration) -> JournalResult<()> {
+        let tasks = Self::_load_tasks(&journal_path)?;
+
+        print!("{}", stats::stats(&tasks, stale_after, clock::now()));
+
+        Ok(())
+    }
+
+    /// The method prints an overview of the journal, c.f. `info::info`. Unlike listing, it doesn't create a missing journal
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::info(journal.path()).unwrap();
+    /// ```
+    pub fn info(journal_path: PathBuf) -> JournalResult<()> {
+        #[cfg(unix)]
+        let daemon = crate::daemon::Client::connect(&journal_path).map(|_| crate::daemon::socket_path(&journal_path));
+        #[cfg(not(unix))]
+        let daemon = None;
+
+        let (file, tasks) = if stdio::is_stdio(&journal_path) {
+            (JournalFile { path: None, backend: Backend::of_journal(&journal_path), compression: None, size: None, daemon: None, locked: false }, Self::_load_tasks(&journal_path)?)
+        } else {
+            let path = paths::resolve(&journal_path).with_path("reading journal", &journal_path)?;
+            let locked = JournalLock::is_held(&journal_path);
+            match fs::metadata(&path) {
+                Ok(metadata) => (JournalFile { backend: Backend::of_journal(&journal_path), compression: Compression::of_journal(&journal_path), size: Some(metadata.len()), path: Some(path), daemon, locked },
+                                 Self::_load_tasks(&journal_path)?),
+                Err(err) if err.kind() == ErrorKind::NotFound => (JournalFile { backend: Backend::of_journal(&journal_path), compression: Compression::from_extension(&journal_path), size: None, path: Some(path), daemon, locked },
+                                                                  Vec::new()),
+                Err(err) => return Err(err).with_path("reading journal", &journal_path).map_err(Into::into),
+            }
+        };
+
+        print!("{}", info::info(&file, &tasks));
+
+        Ok(())
+    }
+
+    /// The method exports the tasks matching the filter to a file in the given format, e.g. a single page HTML view
+    /// to share, a standalone journal holding only the tasks of one client, or a CSV or Markdown table
+    /// # Examples
+    /// ```no_run
+    /// use rusty_journal_clap::{export::{Format, GroupBy}, filter::TaskFilter, task};
+    /// use std::path::PathBuf;
+    /// task::Task::export(PathBuf::from("todo.json"), Format::Html, GroupBy::Tag, &TaskFilter::default(), PathBuf::from("report.html"));
+    /// ```
+    pub fn export(journal_path: PathBuf, format: Format, group_by: GroupBy, filter: &TaskFilter, out_path: PathBuf) -> JournalResult<()> {
+        let mut tasks = Self::_load_tasks(&journal_path)?;
+        tasks.retain(|task| filter.matches(task));
+
+        let written = match format {
+            Format::Html => fs::write(&out_path, export::html(&tasks, group_by)).with_path("writing export", &out_path),
+            // Comment: written like any journal, so a .json.gz export is compressed, but without the read-only check,
+            // exporting doesn't change the journal
+            Format::Json => File::create(&out_path).and_then(|f| Self::_write_tasks(&tasks, f, Compression::from_extension(&out_path), Backend::Json))
+                                                   .with_path("writing export", &out_path),
+            Format::Csv => fs::write(&out_path, export::csv(&tasks)).with_path("writing export", &out_path),
+            Format::Markdown => fs::write(&out_path, export::markdown(&tasks)).with_path("writing export", &out_path),
+        };
+        Ok(written?)
+    }
+
+    /// The method prints the active task to do now, i.e. the one with the highest score, with the earlier one
+    /// winning a tie. With explain, the parts of the score are printed as well
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::{score, task};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::next(journal.path(), true, &score::Coefficients::default()).unwrap();
+    /// ```
+    pub fn next(journal_path: PathBuf, explain: bool, coefficients: &score::Coefficients) -> JournalResult<()> {
+        let tasks = Self::_load_tasks(&journal_path)?;
+
+        let now = clock::now();
+        let best = tasks.iter()
+                        .enumerate()
+                        .filter(|(_, task)| !task.is_complete() && !task.is_waiting())
+                        .map(|(i, task)| (i + 1, task, score::score(task, now, coefficients)))
+                        // Comment: max_by returns the last of equal elements, so the comparison is reversed on the index
+                        .max_by(|(a_index, _, a), (b_index, _, b)| a.total().total_cmp(&b.total()).then(b_index.cmp(a_index)));
+
+        match best {
+            None => println!("{}", tr("nothing-to-do", &[])),
+            Some((index, task, score)) => {
+                println!("{}: {}", index, task);
+                if explain {
+                    print!("{}", score);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The method adds a timestamped note to the history of the tasks at the indices, e.g. "called vendor"
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::annotate(journal.path(), &[1, 2], "called vendor".to_string()).unwrap();
+    /// ```
+    pub fn annotate(journal_path: PathBuf, indices: &[usize], note: String) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            Self::_check_indices(tasks, indices)?;
+            let now = clock::now();
+            for &index in indices {
+                tasks[index - 1].add_note(note.clone(), now);
+            }
+            Ok(())
+        })
+    }
+
+    /// The method records that the tasks at the indices were handed to the person, c.f. `list --delegated`
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::delegate(journal.path(), &[1], "bob".to_string()).unwrap();
+    /// ```
+    pub fn delegate(journal_path: PathBuf, indices: &[usize], person: String) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            Self::_check_indices(tasks, indices)?;
+            let now = clock::now();
+            for &index in indices {
+                let task = &mut tasks[index - 1];
+                if task.delegated_to() != Some(&person) {
+                    task.record(HistoryKind::Edit, format!("delegated to {}", person), now);
+                }
+                task.delegated_to = Some(person.clone());
+            }
+            Ok(())
+        })
+    }
+
+    /// The method creates the goal, or sets its target date, and makes the tasks at the indices contribute to it
+    /// # Examples
+    /// ```no_run
+    /// use rusty_journal_clap::{date, task};
+    /// use std::path::PathBuf;
+    /// task::Task::set_goal(PathBuf::from("todo.json"), "Launch", date::parse("2024-07-01").ok(), &[1, 2]);
+    /// ```
+    pub fn set_goal(journal_path: PathBuf, name: &str, target: Option<DateTime<Utc>>, indices: &[usize]) -> JournalResult<()> {
+        if !indices.is_empty() {
+            Self::_update_tasks(&journal_path, |tasks| {
+                Self::_check_indices(tasks, indices)?;
+                let now = clock::now();
+                for &index in indices {
+                    let task = &mut tasks[index - 1];
+                    if task.goal() != Some(name) {
+                        task.record(HistoryKind::Edit, format!("goal {}", name), now);
+                        task.goal = Some(name.to_string());
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        Ok(goal::upsert(&journal_path, name, target)?)
+    }
+
+    /// The method prints every goal with how many of its tasks are done and the days left, c.f. `goal::progress`
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::goals(journal.path()).unwrap();
+    /// ```
+    pub fn goals(journal_path: PathBuf) -> JournalResult<()> {
+        let tasks = Self::_load_tasks(&journal_path)?;
+        let goals = goal::load(&journal_path)?;
+
+        print!("{}", goal::progress(&goals, &tasks, clock::now()));
+
+        Ok(())
+    }
+
+    /// The method prints the tasks whose name matches the query, best match first, c.f. `fuzzy::score`
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::search(journal.path(), "qrep").unwrap();
+    /// ```
+    pub fn search(journal_path: PathBuf, query: &str) -> JournalResult<()> {
+        Self::search_with(journal_path, query, &TaskFilter::default())
+    }
+
+    /// The method prints the tasks matching the filter whose name matches the query, best match first,
+    /// e.g. the tasks tagged work created this year whose name matches a regular expression
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::{filter::TaskFilter, task};
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// let filter = TaskFilter::any_tag(&["work".to_string(), "home".to_string()]).and(TaskFilter::name_matching("^(?i)review").unwrap());
+    /// task::Task::search_with(journal.path(), "", &filter).unwrap();
+    /// ```
+    pub fn search_with(journal_path: PathBuf, query: &str, filter: &TaskFilter) -> JournalResult<()> {
+        let tasks = Self::_load_tasks(&journal_path)?;
+
+        let matching = filter.matching(&tasks);
+        // Comment: an empty query ranks every task the same, which keeps them in journal order
+        let found = fuzzy::rank(query, matching.iter().map(|&i| tasks[i].name()))
+                        .into_iter()
+                        .map(|rank| (matching[rank - 1] + 1, &tasks[matching[rank - 1]]))
+                        .collect::<Vec<_>>();
+
+        let mut out = BufWriter::new(io::stdout().lock());
+        match output::format() {
+            OutputFormat::Json => output::write_json(&mut out, &found, false)?,
+            OutputFormat::Porcelain => output::write_porcelain(&mut out, &found, false)?,
+            OutputFormat::Human => for (index, task) in found {
+                writeln!(out, "{}: {}", index, task)?;
+            },
+        }
+        out.flush()?;
+
+        Ok(())
+    }
+
+    /// The method lets the user pick a task by typing, refiltering the tasks on every key, c.f. `picker::pick`.
+    /// The picked task is printed with its index, or completed when complete is set
+    /// # Examples
+    /// ```no_run
+    /// use rusty_journal_clap::task;
+    /// use std::path::PathBuf;
+    /// task::Task::search_interactive(PathBuf::from("todo.json"), "", false);
+    /// ```
+    pub fn search_interactive(journal_path: PathBuf, query: &str, complete: bool) -> JournalResult<()> {
+        let tasks = Self::_load_tasks(&journal_path)?;
+
+        let index = match picker::pick(&tasks, query)? {
+            Outcome::Picked(index) => index,
+            Outcome::Cancelled => return Ok(()),
+        };
+        if complete {
+            return Self::complete(journal_path, index);
+        }
+        println!("{}: {}", index, tasks[index - 1]);
+
+        Ok(())
+    }
+
+    /// The method prints the removed tasks still in the trash, with their id and when they were removed
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::trash_list(journal.path()).unwrap();
+    /// ```
+    pub fn trash_list(journal_path: PathBuf) -> JournalResult<()> {
+        let trashed = trash::load(&journal_path, clock::now())?;
+
+        if trashed.is_empty() {
+            println!("{}", tr("trash-empty", &[]));
+        }
+        for trashed in &trashed {
+            println!("{}", tr("trashed-task", &[("id", &trashed.task.id().unwrap_or_default()),
+                                                ("name", &trashed.task.name),
+                                                ("date", &output::format_time(trashed.deleted_at, "%d/%m/%Y %H:%M"))]));
+        }
+
+        Ok(())
+    }
+
+    /// The method moves the task with the id from the trash back to the end of the journal. It keeps its id,
+    /// unless another task took it in the meantime
+    /// # Examples
+    /// ```no_run
+    /// use rusty_journal_clap::task;
+    /// use std::path::PathBuf;
+    /// task::Task::restore(PathBuf::from("todo.json"), 3);
+    /// ```
+    pub fn restore(journal_path: PathBuf, id: u64) -> JournalResult<()> {
+        let mut trashed = trash::load(&journal_path, clock::now())?;
+        let position = trashed.iter()
+                              .position(|trashed| trashed.task.id() == Some(id))
+                              .ok_or_else(|| Error::new(ErrorKind::NotFound, tr("error-not-in-trash", &[("id", &id)])))?;
+        let task = trashed.remove(position).task;
+        let name = task.name.clone();
+
+        let index = Self::_update_tasks(&journal_path, |tasks| {
+            if tasks.iter().any(|other| other.id() == Some(id)) {
+                Self::_push(tasks, task);
+            } else {
+                tasks.push(task);
+            }
+            Ok(tasks.len())
+        })?;
+        trash::save(&journal_path, &trashed)?;
+        stdio::say(tr("task-restored", &[("name", &name), ("index", &index)]));
+
+        Ok(())
+    }
+
+    /// The method puts back the journal as it was before its last changes, e.g. to bring back a task removed by mistake,
+    /// c.f. `undo::depth` for how many changes can be undone. The trash is put back as it was before them too
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::undo(journal.path(), 1).unwrap();
+    /// ```
+    pub fn undo(journal_path: PathBuf, count: usize) -> JournalResult<()> {
+        Self::_check_writable(&journal_path)?;
+        if stdio::is_stdio(&journal_path) {
+            return Err(Error::new(ErrorKind::InvalidInput, tr("error-undo-stdio", &[])).into());
+        }
+        let _lock = JournalLock::acquire(&journal_path)?;
+        let target = Self::_journal_target(&journal_path)?;
+
+        for step in undo::undo(&target, &trash::trash_path(&journal_path), count)? {
+            let at = output::format_time(step.at, "%d/%m/%Y %H:%M");
+            match &step.command {
+                Some(command) => stdio::say(tr("undone", &[("command", command), ("time", &at)])),
+                None => stdio::say(tr("undone-change", &[("time", &at)])),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The method lists the changes of the journal that can be undone, most recent first
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::undo_list(journal.path()).unwrap();
+    /// ```
+    pub fn undo_list(journal_path: PathBuf) -> JournalResult<()> {
+        let steps = match stdio::is_stdio(&journal_path) {
+            true => Vec::new(),
+            false => undo::steps(&Self::_journal_target(&journal_path)?)?,
+        };
+
+        if steps.is_empty() {
+            println!("{}", tr("undo-empty", &[]));
+        }
+        for (n, step) in steps.iter().enumerate() {
+            println!("{:>3}  {:<12} {}", n + 1, step.command.as_deref().unwrap_or("-"), output::format_time(step.at, "%d/%m/%Y %H:%M"));
+        }
+
+        Ok(())
+    }
+
+    /// The method adds the next occurrence of the completed recurring tasks which have none yet, e.g. the ones completed
+    /// through `append` or in the journal file by hand. Completing any task, e.g. with `done`, adds them as well
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::tick(journal.path()).unwrap();
+    /// ```
+    pub fn tick(journal_path: PathBuf) -> JournalResult<()> {
+        let added = Self::_update_tasks(&journal_path, |tasks| Ok(Self::_regenerate(tasks, clock::now())))?;
+        stdio::say(tr("ticked", &[("count", &added)]));
+        Ok(())
+    }
+
+    /// The method links the tasks at the two indices as related, each referring to the other by id, c.f. `list --related-to`.
+    /// A task without id, from a journal written before ids, is given one
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::relate(journal.path(), 1, 2).unwrap();
+    /// ```
+    pub fn relate(journal_path: PathBuf, index: usize, other: usize) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            Self::_check_indices(tasks, &[index, other])?;
+            if index == other {
+                return Err(Error::new(ErrorKind::InvalidInput, tr("error-relate-itself", &[])));
+            }
+            let ids = [index, other].map(|index| Self::_ensure_id(tasks, index));
+            let now = clock::now();
+            for (index, other_id) in [(index, ids[1]), (other, ids[0])] {
+                let task = &mut tasks[index - 1];
+                if !task.related.contains(&other_id) {
+                    task.record(HistoryKind::Edit, format!("related to {}", other_id), now);
+                    task.related.push(other_id);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// The id of the task at the (1-based) index, giving it one past the ids in use if it has none
+    fn _ensure_id(tasks: &mut [Task], index: usize) -> u64 {
+        if let Some(id) = tasks[index - 1].id {
+            return id;
+        }
+        let id = tasks.iter().filter_map(|task| task.id).max().map_or(1, |max| max + 1);
+        tasks[index - 1].id = Some(id);
+        id
+    }
+
+    /// The method sets how long doing each of the tasks at the indices should take, e.g. for `plan`
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use chrono::Duration;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::estimate_at(journal.path(), &[1], Duration::minutes(90)).unwrap();
+    /// ```
+    pub fn estimate_at(journal_path: PathBuf, indices: &[usize], estimate: Duration) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            Self::_check_indices(tasks, indices)?;
+            let now = clock::now();
+            for &index in indices {
+                let task = &mut tasks[index - 1];
+                if task.estimate() != Some(estimate) {
+                    task.record(HistoryKind::Edit, format!("estimated {}", span::format(estimate)), now);
+                }
+                task.set_estimate(Some(estimate));
+            }
+            Ok(())
+        })
+    }
+
+    /// The method prints all the details of the tasks at the indices, each followed by its history
+    /// # Examples
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::show(journal.path(), &[1, 3]).unwrap();
+    /// ```
+    pub fn show(journal_path: PathBuf, indices: &[usize]) -> JournalResult<()> {
+        let tasks = Self::_load_tasks(&journal_path)?;
+        Self::_check_indices(&tasks, indices)?;
+
+        let details = indices.iter().map(|&index| tasks[index - 1].details(index)).collect::<Vec<_>>();
+        // a blank line sets the tasks apart
+        print!("{}", details.join("\n"));
+        Ok(())
+    }
+
+    /// The details `show` prints for the task at the (1-based) index
+    pub fn details(&self, index: usize) -> String {
+        let format = "%d/%m/%Y %H:%M";
+        let mut details = tr("task-heading", &[("index", &index), ("name", &self.name)]) + "\n";
+        let mut field = |label: &str, value: String| details.push_str(&format!("  {:<12} {}\n", tr(label, &[]), value));
+
+        if let Some(id) = self.id {
+            field("label-id", id.to_string());
+        }
+        field("label-state", match &self.state {
+            State::Complete{completed_at} => tr("state-complete-since", &[("date", &output::format_time(*completed_at, format))]),
+            State::Waiting{until, ..} => tr("state-waiting-until", &[("date", &output::format_time(*until, format))]),
+            State::Active => tr("state-active", &[]),
+        });
+        if let Some(reason) = self.waiting_for() {
+            field("label-waiting-for", reason.to_string());
+        }
+        if !self.tags().is_empty() {
+            field("label-tags", self.tags().join(", "));
+        }
+        field("label-created", output::format_time(self.creted_at, format));
+        if let Some(updated_at) = self.updated_at {
+            field("label-updated", output::format_time(updated_at, format));
+        }
+        if let Some(due) = self.due {
+            field("label-due", output::format_time(due, format));
+        }
+        if let Some(priority) = self.priority {
+            field("label-priority", priority.name().to_string());
+        }
+        if let Some(estimate) = self.estimate() {
+            field("label-estimate", span::format(estimate));
+        }
+        if let Some(person) = &self.delegated_to {
+            field("label-delegated", person.to_string());
+        }
+        if let Some(goal) = &self.goal {
+            field("label-goal", goal.to_string());
+        }
+        if !self.related.is_empty() {
+            field("label-related", self.related.iter().map(u64::to_string).collect::<Vec<_>>().join(", "));
+        }
+        if let Some(parent) = self.parent {
+            field("label-parent", parent.to_string());
+        }
+        if let Some(repeat) = self.repeat {
+            field("label-repeats", repeat.to_string());
+        }
+        if let Some(owner) = &self.owner {
+            field("label-owner", owner.to_string());
+        }
+        if let Some(assignee) = &self.assignee {
+            field("label-assignee", assignee.to_string());
+        }
+        if let Some(link) = self.extra(LINK_FIELD) {
+            field("label-link", link.to_string());
+        }
+
+        if !self.history.is_empty() {
+            details.push_str(&(tr("history", &[]) + "\n"));
+            for entry in &self.history {
+                details.push_str(&format!("  {}  {:<5}  {}\n", output::format_time(entry.at, format), entry.kind.name(), entry.text));
+            }
+        }
+        details
+    }
+
+    /// The URL `open` launches for the task: the first URL in its name, or else its link
+    pub fn url(&self) -> Option<&str> {
+        url::find(&self.name).first().copied().or_else(|| self.extra(LINK_FIELD))
+    }
+
+    /// The method opens the first URL of each of the tasks at the indices with the system opener, e.g. in the browser
+    /// # Examples
+    /// ```no_run
+    /// use rusty_journal_clap::task;
+    /// use std::path::PathBuf;
+    /// task::Task::open(PathBuf::from("todo.json"), &[1]);
+    /// ```
+    pub fn open(journal_path: PathBuf, indices: &[usize]) -> JournalResult<()> {
+        let tasks = Self::_load_tasks(&journal_path)?;
+        Self::_check_indices(&tasks, indices)?;
+
+        // Comment: every task is checked for a URL before any is opened, so that a typo opens nothing rather than some
+        let links = indices.iter()
+                           .map(|&index| tasks[index - 1].url()
+                                                         .ok_or_else(|| Error::new(ErrorKind::NotFound, tr("error-no-url", &[("index", &index)]))))
+                           .collect::<ioResult<Vec<_>>>()?;
+        for link in links {
+            url::open(link)?;
+        }
+        Ok(())
+    }
+
+    /// This method helps with testing by clearing all the data
+    /// # Examples:
+    /// ```
+    /// use rusty_journal_clap::task;
+    /// use rusty_journal_clap::test_util::TempJournal;
+    /// let journal = TempJournal::seeded().unwrap();
+    /// task::Task::clear(journal.path()).unwrap();
+    /// ```
+    pub fn clear(journal_path: PathBuf) -> JournalResult<()> {
+        Self::_update_tasks(&journal_path, |tasks| {
+            tasks.clear();
+            Ok(())
+        })
     }
 
 
@@ -0,0 +1,576 @@
+use std::{fs, path::Path};
+use std::io::{Error, ErrorKind, Result as ioResult};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+use crate::{clock, output};
+use crate::error::Context;
+use crate::task::Task;
+
+// Comment: the template syntax is the commonly used subset of Tera/Jinja2, so that existing
+// knowledge (and editor syntax highlighting) carries over:
+//   {{ task.name | upper }}                      output, with filters
+//   {% for task in tasks %} ... {% endfor %}     loops, with loop.index / loop.index0 / loop.first / loop.last
+//   {% if cond %} ... {% elif cond %} ... {% else %} ... {% endif %}
+//   {# comment #}
+// A `-` right inside the delimiters ({{- -}}, {%- -%}) trims the whitespace on that side, as in Tera.
+// Same as Tera, output is HTML-escaped when the template file is .html/.htm/.xml unless the `safe` filter is used.
+
+fn template_error(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, format!("Template error: {}", msg))
+}
+
+#[derive(Debug)]
+enum Segment {
+    Text(String),
+    Output(String),
+    Tag(String),
+}
+
+#[derive(Debug)]
+struct Filter {
+    name: String,
+    args: Vec<(String, Expr)>,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Lit(Value),
+    Path(Vec<String>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(String, Box<Expr>, Box<Expr>),
+    Filtered(Box<Expr>, Filter),
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Output(Expr),
+    For { var: String, iterable: Expr, body: Vec<Node> },
+    If { branches: Vec<(Expr, Vec<Node>)>, otherwise: Vec<Node> },
+}
+
+fn split_segments(source: &str) -> ioResult<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut rest = source;
+    let mut trim_next = false;
+
+    while !rest.is_empty() {
+        let open = ["{{", "{%", "{#"].iter()
+                                     .filter_map(|delim| rest.find(delim).map(|pos| (pos, *delim)))
+                                     .min();
+        let (pos, open) = match open {
+            Some(found) => found,
+            None => (rest.len(), ""),
+        };
+
+        let mut text = &rest[..pos];
+        if trim_next {
+            text = text.trim_start();
+        }
+        rest = &rest[pos..];
+        if open.is_empty() {
+            segments.push(Segment::Text(text.to_string()));
+            break;
+        }
+
+        let close = match open {
+            "{{" => "}}",
+            "{%" => "%}",
+            _ => "#}",
+        };
+        let end = rest.find(close)
+                      .ok_or_else(|| template_error(format!("'{}' is never closed by '{}'", open, close)))?;
+        let mut inner = &rest[2..end];
+        rest = &rest[end + 2..];
+
+        if let Some(stripped) = inner.strip_prefix('-') {
+            text = text.trim_end();
+            inner = stripped;
+        }
+        trim_next = false;
+        if let Some(stripped) = inner.strip_suffix('-') {
+            trim_next = true;
+            inner = stripped;
+        }
+
+        segments.push(Segment::Text(text.to_string()));
+        match open {
+            "{{" => segments.push(Segment::Output(inner.trim().to_string())),
+            "{%" => segments.push(Segment::Tag(inner.trim().to_string())),
+            _ => {},
+        }
+    }
+
+    Ok(segments)
+}
+
+fn tokenize(source: &str) -> ioResult<Vec<String>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            // string tokens keep their opening quote so they can be told apart from identifiers
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err(template_error(format!("unterminated string in '{}'", source)));
+            }
+            tokens.push(chars[start..i].iter().collect());
+            i += 1;
+        } else if c.is_alphanumeric() || c == '_' || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(chars[start..i].iter().collect());
+        } else if "=!<>".contains(c) && chars.get(i + 1) == Some(&'=') {
+            tokens.push(chars[i..i + 2].iter().collect());
+            i += 2;
+        } else if "|()=,<>".contains(c) {
+            tokens.push(c.to_string());
+            i += 1;
+        } else {
+            return Err(template_error(format!("unexpected character '{}' in '{}'", c, source)));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct ExprParser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn parse(source: &str) -> ioResult<Expr> {
+        let mut parser = ExprParser { tokens: tokenize(source)?, pos: 0 };
+        let expr = parser.or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(template_error(format!("unexpected '{}' in '{}'", parser.tokens[parser.pos], source)));
+        }
+        Ok(expr)
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> ioResult<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(template_error(format!("expected '{}' but found '{}'", token, self.peek().unwrap_or("end of expression"))))
+        }
+    }
+
+    fn or(&mut self) -> ioResult<Expr> {
+        let mut lhs = self.and()?;
+        while self.eat("or") {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> ioResult<Expr> {
+        let mut lhs = self.not()?;
+        while self.eat("and") {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.not()?));
+        }
+        Ok(lhs)
+    }
+
+    fn not(&mut self) -> ioResult<Expr> {
+        if self.eat("not") {
+            Ok(Expr::Not(Box::new(self.not()?)))
+        } else {
+            self.comparison()
+        }
+    }
+
+    fn comparison(&mut self) -> ioResult<Expr> {
+        let lhs = self.filtered()?;
+        match self.peek() {
+            Some(op @ ("==" | "!=" | "<" | "<=" | ">" | ">=" | "in")) => {
+                let op = op.to_string();
+                self.pos += 1;
+                Ok(Expr::Compare(op, Box::new(lhs), Box::new(self.filtered()?)))
+            }
+            _ => Ok(lhs),
+        }
+    }
+
+    fn filtered(&mut self) -> ioResult<Expr> {
+        let mut expr = self.primary()?;
+        while self.eat("|") {
+            let name = self.tokens.get(self.pos)
+                                  .cloned()
+                                  .ok_or_else(|| template_error("expected a filter name after '|'".to_string()))?;
+            self.pos += 1;
+            let mut args = Vec::new();
+            if self.eat("(") {
+                while !self.eat(")") {
+                    let arg_name = self.tokens.get(self.pos)
+                                              .cloned()
+                                              .ok_or_else(|| template_error(format!("unclosed arguments of filter '{}'", name)))?;
+                    self.pos += 1;
+                    self.expect("=")?;
+                    args.push((arg_name, self.primary()?));
+                    self.eat(",");
+                }
+            }
+            expr = Expr::Filtered(Box::new(expr), Filter { name, args });
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> ioResult<Expr> {
+        let token = self.tokens.get(self.pos)
+                               .cloned()
+                               .ok_or_else(|| template_error("unexpected end of expression".to_string()))?;
+        self.pos += 1;
+
+        if token == "(" {
+            let inner = self.or()?;
+            self.expect(")")?;
+            return Ok(inner);
+        }
+        if let Some(s) = token.strip_prefix('"').or_else(|| token.strip_prefix('\'')) {
+            return Ok(Expr::Lit(Value::String(s.to_string())));
+        }
+        if let Ok(n) = token.parse::<i64>() {
+            return Ok(Expr::Lit(json!(n)));
+        }
+        match token.as_str() {
+            "true" => Ok(Expr::Lit(Value::Bool(true))),
+            "false" => Ok(Expr::Lit(Value::Bool(false))),
+            _ if token.starts_with(|c: char| c.is_alphabetic() || c == '_') => {
+                Ok(Expr::Path(token.split('.').map(str::to_string).collect()))
+            }
+            _ => Err(template_error(format!("unexpected '{}'", token))),
+        }
+    }
+}
+
+fn parse_nodes(segments: &mut std::iter::Peekable<std::vec::IntoIter<Segment>>, end_tags: &[&str]) -> ioResult<(Vec<Node>, Option<String>)> {
+    let mut nodes = Vec::new();
+
+    while let Some(segment) = segments.next() {
+        match segment {
+            Segment::Text(text) => nodes.push(Node::Text(text)),
+            Segment::Output(expr) => nodes.push(Node::Output(ExprParser::parse(&expr)?)),
+            Segment::Tag(tag) => {
+                let keyword = tag.split_whitespace().next().unwrap_or_default();
+                if end_tags.contains(&keyword) {
+                    return Ok((nodes, Some(tag)));
+                }
+                match keyword {
+                    "for" => {
+                        let rest = tag["for".len()..].trim();
+                        let (var, iterable) = rest.split_once(" in ")
+                                                  .ok_or_else(|| template_error(format!("expected 'for <name> in <expr>' in '{}'", tag)))?;
+                        let (body, _) = parse_block(segments, &["endfor"], &tag)?;
+                        nodes.push(Node::For { var: var.trim().to_string(), iterable: ExprParser::parse(iterable)?, body });
+                    }
+                    "if" => {
+                        let mut branches = Vec::new();
+                        let mut otherwise = Vec::new();
+                        let mut cond = ExprParser::parse(&tag["if".len()..])?;
+                        loop {
+                            let (body, end) = parse_block(segments, &["elif", "else", "endif"], &tag)?;
+                            branches.push((cond, body));
+                            if let Some(elif) = end.strip_prefix("elif") {
+                                cond = ExprParser::parse(elif)?;
+                                continue;
+                            }
+                            if end == "else" {
+                                otherwise = parse_block(segments, &["endif"], &tag)?.0;
+                            }
+                            break;
+                        }
+                        nodes.push(Node::If { branches, otherwise });
+                    }
+                    _ => return Err(template_error(format!("unknown tag '{}'", tag))),
+                }
+            }
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+fn parse_block(segments: &mut std::iter::Peekable<std::vec::IntoIter<Segment>>, end_tags: &[&str], opening: &str) -> ioResult<(Vec<Node>, String)> {
+    match parse_nodes(segments, end_tags)? {
+        (nodes, Some(end)) => Ok((nodes, end)),
+        (_, None) => Err(template_error(format!("'{{% {} %}}' is never closed by '{{% {} %}}'", opening, end_tags.last().unwrap_or(&"end")))),
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.to_owned(),
+        other => other.to_string(),
+    }
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
+
+fn lookup(path: &[String], scope: &Map<String, Value>) -> ioResult<Value> {
+    let mut value = scope.get(&path[0])
+                         .ok_or_else(|| template_error(format!("variable '{}' not found", path[0])))?;
+    for key in &path[1..] {
+        value = match value {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        }.ok_or_else(|| template_error(format!("variable '{}' not found", path.join("."))))?;
+    }
+    Ok(value.clone())
+}
+
+fn apply_filter(value: Value, filter: &Filter, scope: &Map<String, Value>) -> ioResult<Value> {
+    let arg = |name: &str| -> ioResult<Option<Value>> {
+        filter.args.iter()
+                   .find(|(arg_name, _)| arg_name == name)
+                   .map(|(_, expr)| eval(expr, scope))
+                   .transpose()
+    };
+
+    let result = match (filter.name.as_str(), &value) {
+        ("upper", _) => Value::String(to_text(&value).to_uppercase()),
+        ("lower", _) => Value::String(to_text(&value).to_lowercase()),
+        ("trim", _) => Value::String(to_text(&value).trim().to_string()),
+        ("escape", _) => Value::String(escape_html(&to_text(&value))),
+        // marking the output as safe is handled when rendering, c.f. Node::Output
+        ("safe", _) => value,
+        ("length", Value::Array(items)) => json!(items.len()),
+        ("length", Value::Object(map)) => json!(map.len()),
+        ("length", _) => json!(to_text(&value).chars().count()),
+        ("join", Value::Array(items)) => {
+            let sep = arg("sep")?.map(|sep| to_text(&sep)).unwrap_or_default();
+            Value::String(items.iter().map(to_text).collect::<Vec<_>>().join(&sep))
+        }
+        ("default", _) if !truthy(&value) => arg("value")?.unwrap_or(Value::Null),
+        ("default", _) => value,
+        ("date", Value::Number(n)) => {
+            let format = arg("format")?.map(|f| to_text(&f)).unwrap_or_else(|| "%Y-%m-%d".to_string());
+            let timestamp = DateTime::<Utc>::from_timestamp(n.as_i64().unwrap_or_default(), 0)
+                                             .ok_or_else(|| template_error(format!("{} is not a valid timestamp", n)))?;
+            Value::String(output::format_time(timestamp, &format))
+        }
+        ("date", Value::Null) => Value::Null,
+        (name, value) => return Err(template_error(format!("filter '{}' cannot be applied to {}", name, value))),
+    };
+    Ok(result)
+}
+
+fn compare(op: &str, lhs: &Value, rhs: &Value) -> ioResult<bool> {
+    let ordering = match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+
+    let result = match op {
+        "==" => lhs == rhs,
+        "!=" => lhs != rhs,
+        "in" => match rhs {
+            Value::Array(items) => items.contains(lhs),
+            Value::String(s) => s.contains(&to_text(lhs)),
+            Value::Object(map) => map.contains_key(&to_text(lhs)),
+            _ => false,
+        },
+        _ => {
+            let ordering = ordering.ok_or_else(|| template_error(format!("cannot compare {} and {}", lhs, rhs)))?;
+            match op {
+                "<" => ordering.is_lt(),
+                "<=" => ordering.is_le(),
+                ">" => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }
+        }
+    };
+    Ok(result)
+}
+
+fn eval(expr: &Expr, scope: &Map<String, Value>) -> ioResult<Value> {
+    match expr {
+        Expr::Lit(value) => Ok(value.clone()),
+        Expr::Path(path) => lookup(path, scope),
+        Expr::Not(inner) => Ok(Value::Bool(!truthy(&eval(inner, scope)?))),
+        Expr::And(lhs, rhs) => Ok(Value::Bool(truthy(&eval(lhs, scope)?) && truthy(&eval(rhs, scope)?))),
+        Expr::Or(lhs, rhs) => Ok(Value::Bool(truthy(&eval(lhs, scope)?) || truthy(&eval(rhs, scope)?))),
+        Expr::Compare(op, lhs, rhs) => Ok(Value::Bool(compare(op, &eval(lhs, scope)?, &eval(rhs, scope)?)?)),
+        Expr::Filtered(inner, filter) => apply_filter(eval(inner, scope)?, filter, scope),
+    }
+}
+
+fn is_safe(expr: &Expr) -> bool {
+    matches!(expr, Expr::Filtered(_, filter) if filter.name == "safe" || filter.name == "escape")
+}
+
+fn render_nodes(nodes: &[Node], scope: &Map<String, Value>, autoescape: bool, out: &mut String) -> ioResult<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Output(expr) => {
+                let text = to_text(&eval(expr, scope)?);
+                if autoescape && !is_safe(expr) {
+                    out.push_str(&escape_html(&text));
+                } else {
+                    out.push_str(&text);
+                }
+            }
+            Node::For { var, iterable, body } => {
+                let items = match eval(iterable, scope)? {
+                    Value::Array(items) => items,
+                    Value::Null => Vec::new(),
+                    other => return Err(template_error(format!("cannot iterate over {}", other))),
+                };
+                let mut inner_scope = scope.clone();
+                let len = items.len();
+                for (i, item) in items.into_iter().enumerate() {
+                    inner_scope.insert(var.to_owned(), item);
+                    inner_scope.insert("loop".to_string(), json!({
+                        "index": i + 1,
+                        "index0": i,
+                        "first": i == 0,
+                        "last": i + 1 == len,
+                    }));
+                    render_nodes(body, &inner_scope, autoescape, out)?;
+                }
+            }
+            Node::If { branches, otherwise } => {
+                let mut taken = None;
+                for (cond, body) in branches {
+                    if truthy(&eval(cond, scope)?) {
+                        taken = Some(body);
+                        break;
+                    }
+                }
+                render_nodes(taken.unwrap_or(otherwise), scope, autoescape, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A report template using the common subset of the Tera template syntax.
+///
+/// Templates are rendered with a `tasks` array, where each task has `index` (1-based), `id`, `name`, `tags`,
+/// `state` (`"active"`, `"waiting"` or `"complete"`), `created_at`, `updated_at`, `completed_at`, `waiting_until` and `due` (unix seconds, use the `date` filter),
+/// `priority` (`"high"`, `"medium"`, `"low"` or null), `owner`, `assignee`, `delegated_to`, `goal`, `related` (an array of ids), `estimate` (in minutes or null) and `history` (an array of `at`, `kind` and `text`),
+/// as well as `now` (unix seconds).
+/// # Examples
+/// ```
+/// use rusty_journal_clap::template::Template;
+/// let template = Template::parse("{% for task in tasks %}- {{ task.name | upper }}\n{% endfor %}", false).unwrap();
+/// assert_eq!(template.render(&[]).unwrap(), "");
+/// assert!(Template::parse("{% for task in tasks %}", false).is_err());
+/// ```
+#[derive(Debug)]
+pub struct Template {
+    nodes: Vec<Node>,
+    autoescape: bool,
+}
+
+impl Template {
+    pub fn parse(source: &str, autoescape: bool) -> ioResult<Template> {
+        let mut segments = split_segments(source)?.into_iter().peekable();
+        match parse_nodes(&mut segments, &[])? {
+            (nodes, None) => Ok(Template { nodes, autoescape }),
+            (_, Some(tag)) => Err(template_error(format!("unexpected '{{% {} %}}'", tag))),
+        }
+    }
+
+    /// Reads a template file, autoescaping its output if it is an html or xml template
+    pub fn from_file(template_path: &Path) -> ioResult<Template> {
+        let autoescape = template_path.extension()
+                                      .and_then(|ext| ext.to_str())
+                                      .is_some_and(|ext| ["html", "htm", "xml"].contains(&ext));
+        Self::parse(&fs::read_to_string(template_path).with_path("reading template", template_path)?, autoescape)
+    }
+
+    pub fn render(&self, tasks: &[Task]) -> ioResult<String> {
+        let mut scope = Map::new();
+        scope.insert("tasks".to_string(), Value::Array(tasks.iter()
+                                                             .enumerate()
+                                                             .map(|(i, task)| task_context(i + 1, task))
+                                                             .collect()));
+        scope.insert("now".to_string(), json!(clock::now().timestamp()));
+
+        let mut out = String::new();
+        render_nodes(&self.nodes, &scope, self.autoescape, &mut out)?;
+        Ok(out)
+    }
+}
+
+fn task_context(index: usize, task: &Task) -> Value {
+    json!({
+        "index": index,
+        "id": task.id(),
+        "name": task.name(),
+        "tags": task.tags(),
+        "state": task.state_name(),
+        "created_at": task.created_at().timestamp(),
+        "updated_at": task.updated_at().timestamp(),
+        "completed_at": task.completed_at().map(|at| at.timestamp()),
+        "waiting_until": task.waiting_until().map(|at| at.timestamp()),
+        "due": task.due().map(|at| at.timestamp()),
+        "priority": task.priority().map(|priority| priority.name()),
+        "owner": task.owner(),
+        "assignee": task.assignee(),
+        "delegated_to": task.delegated_to(),
+        "goal": task.goal(),
+        "related": task.related(),
+        "estimate": task.estimate().map(|estimate| estimate.num_minutes()),
+        "history": task.history()
+                       .iter()
+                       .map(|entry| json!({ "at": entry.at().timestamp(), "kind": entry.kind().name(), "text": entry.text() }))
+                       .collect::<Vec<_>>(),
+    })
+}
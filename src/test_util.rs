@@ -0,0 +1,195 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{env, fs, process};
+use std::io::Result as ioResult;
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+use crate::clock;
+use crate::task::Task;
+
+// Comment: makes the directory names unique among the journals of one process, the pid takes care of
+// concurrently running test binaries
+static NEXT_JOURNAL: AtomicUsize = AtomicUsize::new(0);
+
+/// A journal file in its own temporary directory, which is deleted together with the journal when dropped.
+/// Use it instead of `todo.json` in the working directory wherever a test needs a journal.
+/// # Examples
+/// ```
+/// use rusty_journal_clap::task::Task;
+/// use rusty_journal_clap::test_util::TempJournal;
+/// let journal = TempJournal::seeded().unwrap();
+/// Task::remove_many(journal.path(), &[1]).unwrap();
+/// assert_eq!(journal.tasks().unwrap().len(), TempJournal::FIXTURE.len() - 1);
+/// ```
+pub struct TempJournal {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl TempJournal {
+    /// The fixture tasks of a seeded journal as (name, tags, complete), in journal order
+    pub const FIXTURE: &'static [(&'static str, &'static [&'static str], bool)] = &[
+        ("Buy milk", &["groceries"], false),
+        ("Write the quarterly report", &["work"], false),
+        ("File the taxes", &["home", "admin"], true),
+        ("Book the dentist", &[], false),
+        ("Review pull requests", &["work"], true),
+    ];
+
+    /// Creates an empty journal, the journal file itself is only created by the first command writing to it
+    pub fn new() -> ioResult<Self> {
+        let dir = env::temp_dir().join(format!("rusty-journal-{}-{}",
+                                               process::id(),
+                                               NEXT_JOURNAL.fetch_add(1, Ordering::Relaxed)));
+        // Comment: a directory left over by a crashed run with a recycled pid would otherwise leak its journal into the test
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("todo.json");
+        Ok(TempJournal { dir, path })
+    }
+
+    /// Creates a journal holding the `FIXTURE` tasks
+    pub fn seeded() -> ioResult<Self> {
+        let tasks = Self::FIXTURE.iter()
+                                 .map(|(name, tags, complete)| (name.to_string(),
+                                                                tags.iter().map(|tag| tag.to_string()).collect(),
+                                                                *complete))
+                                 .collect::<Vec<(String, Vec<String>, bool)>>();
+        Self::with_tasks(tasks)
+    }
+
+    /// Creates a journal holding tasks built from (name, tags, complete)
+    pub fn with_tasks(tasks: Vec<(String, Vec<String>, bool)>) -> ioResult<Self> {
+        let journal = Self::new()?;
+        let now = clock::now();
+        let new_tasks = tasks.into_iter().map(|(name, tags, complete)| {
+            let tags = if tags.is_empty() { None } else { Some(tags) };
+            let mut task = Task::new(name, tags, now);
+            if complete {
+                task.completed(now);
+            }
+            task
+        });
+        let mut journal_tasks = Vec::new();
+        Task::_push_all(&mut journal_tasks, new_tasks);
+        Task::_save_tasks(&journal.path, &journal_tasks)?;
+        Ok(journal)
+    }
+
+    /// The path of the journal file, to be passed to the `Task` methods
+    pub fn path(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    /// The directory holding the journal, for tests that need more files next to it
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Overwrites the journal with the tasks
+    pub fn write(&self, tasks: &Vec<Task>) -> ioResult<()> {
+        Task::_save_tasks(&self.path, tasks)
+    }
+
+    /// Reads back the tasks currently in the journal
+    pub fn tasks(&self) -> ioResult<Vec<Task>> {
+        Task::_load_tasks(&self.path)
+    }
+}
+
+impl Drop for TempJournal {
+    fn drop(&mut self) {
+        // Comment: failing to clean up the temp dir shouldn't fail the test using it
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// A small deterministic random generator (xorshift64*) for generating test cases,
+/// the same seed always yields the same cases
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Comment: xorshift never leaves the all zero state, so the seed is mixed with a non zero constant
+        Rng { state: seed ^ 0x9E37_79B9_7F4A_7C15 }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A number in `0..bound`, `bound` must not be 0
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// True with a probability of one in `n`
+    pub fn one_in(&mut self, n: u64) -> bool {
+        self.below(n) == 0
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+}
+
+/// Types that can generate random values of themselves for property tests
+pub trait Arbitrary: Sized {
+    fn arbitrary(rng: &mut Rng) -> Self;
+}
+
+// Comment: skewed towards what tends to break serialization: quotes, escapes, separators, non ascii and astral chars
+const CHARS: &[char] = &['a', 'Z', '0', ' ', '-', ',', ';', '"', '\'', '\\', '\n', '\t', '{', '}', '<', '&', 'é', 'ß', 'Ω', '中', '文', '😀', '\u{0}'];
+
+impl Arbitrary for u64 {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        rng.next_u64()
+    }
+}
+
+impl Arbitrary for String {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let len = rng.below(24);
+        (0..len).map(|_| *rng.pick(CHARS)).collect()
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Vec<T> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let len = rng.below(6);
+        (0..len).map(|_| T::arbitrary(rng)).collect()
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Option<T> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        if rng.one_in(3) { None } else { Some(T::arbitrary(rng)) }
+    }
+}
+
+/// Checks the property against `cases` generated values, the seeds run from 0 so failures are reproducible.
+/// A failing case is reported together with its seed.
+/// # Examples
+/// ```
+/// use rusty_journal_clap::task::Task;
+/// use rusty_journal_clap::test_util::for_all;
+/// for_all(100, |task: &Task| {
+///     let json = serde_json::to_string(task).unwrap();
+///     assert_eq!(&serde_json::from_str::<Task>(&json).unwrap(), task);
+/// });
+/// ```
+pub fn for_all<T: Arbitrary + Debug>(cases: u64, property: impl Fn(&T)) {
+    for seed in 0..cases {
+        let value = T::arbitrary(&mut Rng::new(seed));
+        if panic::catch_unwind(AssertUnwindSafe(|| property(&value))).is_err() {
+            panic!("property failed for seed {}: {:?}", seed, value);
+        }
+    }
+}
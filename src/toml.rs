@@ -0,0 +1,442 @@
+use std::fmt::Write;
+use std::io::{Error, ErrorKind, Result as ioResult};
+use serde_json::{Map, Number, Value};
+use crate::error::ParseFailure;
+
+// Comment: like the regular expressions, TOML is handled here rather than through a crate. Documents are read
+// into and written from serde_json values, so serde maps them onto the tasks as it does for JSON.
+// Dates and times of TOML aren't supported, the journal keeps its timestamps as seconds
+
+fn invalid(line: usize, msg: impl std::fmt::Display) -> Error {
+    ParseFailure::of(format!("Invalid TOML at line {}: {}", line, msg))
+}
+
+/// Writes a table as a TOML document: the plain values of a table come first, then its tables and arrays of tables,
+/// each under its header. Null values are left out, TOML has no null
+/// # Examples
+/// ```
+/// use rusty_journal_clap::toml;
+/// use serde_json::json;
+/// let document = toml::to_string(&json!({"task": [{"name": "Buy \"milk\"", "tags": ["groceries"], "state": {"type": "Active"}, "due": null}]})).unwrap();
+/// assert_eq!(document, "[[task]]\nname = \"Buy \\\"milk\\\"\"\ntags = [\"groceries\"]\n\n[task.state]\ntype = \"Active\"\n");
+/// assert!(toml::to_string(&json!({"id": u64::MAX})).is_err());
+/// ```
+pub fn to_string(table: &Value) -> ioResult<String> {
+    let Value::Object(table) = table else {
+        return Err(Error::new(ErrorKind::InvalidInput, "A TOML document is a table"));
+    };
+    let mut document = String::new();
+    write_table(&mut document, &[], table, false)?;
+    Ok(document)
+}
+
+fn is_table_array(value: &Value) -> bool {
+    matches!(value, Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object))
+}
+
+fn write_table(out: &mut String, path: &[String], table: &Map<String, Value>, array_item: bool) -> ioResult<()> {
+    let has_values = table.values().any(|value| !value.is_null() && !value.is_object() && !is_table_array(value));
+    if !path.is_empty() && (array_item || has_values || table.is_empty()) {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let header = path.iter().map(|key| format_key(key)).collect::<Vec<_>>().join(".");
+        // Comment: writing to a String can't fail
+        let _ = if array_item { writeln!(out, "[[{}]]", header) } else { writeln!(out, "[{}]", header) };
+    }
+    for (key, value) in table {
+        if !value.is_null() && !value.is_object() && !is_table_array(value) {
+            let _ = writeln!(out, "{} = {}", format_key(key), format_value(value)?);
+        }
+    }
+    for (key, value) in table {
+        let path = [path, &[key.to_string()]].concat();
+        match value {
+            Value::Object(sub_table) => write_table(out, &path, sub_table, false)?,
+            Value::Array(items) if is_table_array(value) => {
+                for item in items {
+                    if let Value::Object(item) = item {
+                        write_table(out, &path, item, true)?;
+                    }
+                }
+            }
+            _ => {},
+        }
+    }
+    Ok(())
+}
+
+fn format_key(key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        key.to_string()
+    } else {
+        format_string(key)
+    }
+}
+
+fn format_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04X}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A value on the right of `=`, where the tables nested in arrays are written inline
+fn format_value(value: &Value) -> ioResult<String> {
+    Ok(match value {
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) if n.is_f64() => format!("{:?}", n.as_f64().unwrap_or_default()),
+        Value::Number(n) => match n.as_i64() {
+            Some(n) => n.to_string(),
+            None => return Err(Error::new(ErrorKind::InvalidInput, format!("{} is out of the range of TOML integers", n))),
+        },
+        Value::String(text) => format_string(text),
+        Value::Array(items) => {
+            let items = items.iter().filter(|item| !item.is_null()).map(format_value).collect::<ioResult<Vec<_>>>()?;
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(table) => {
+            let pairs = table.iter()
+                             .filter(|(_, value)| !value.is_null())
+                             .map(|(key, value)| format_value(value).map(|value| format!("{} = {}", format_key(key), value)))
+                             .collect::<ioResult<Vec<_>>>()?;
+            format!("{{{}}}", pairs.join(", "))
+        }
+        Value::Null => String::new(),
+    })
+}
+
+/// Reads a TOML document into a table: tables, arrays of tables, dotted and quoted keys, basic and literal strings,
+/// integers, floats, booleans, arrays and inline tables. Dates, times and multi-line strings are refused
+/// # Examples
+/// ```
+/// use rusty_journal_clap::toml;
+/// use serde_json::json;
+/// let document = "# my tasks\n[[task]]\nname = 'Buy milk'\ntags = [\"groceries\",\n        \"errands\"]\n[task.state]\ntype = \"Complete\"\ncompleted_at = 1_700_000_000\n";
+/// assert_eq!(toml::from_str(document).unwrap(),
+///            json!({"task": [{"name": "Buy milk", "tags": ["groceries", "errands"], "state": {"type": "Complete", "completed_at": 1_700_000_000}}]}));
+/// assert!(toml::from_str("name = 1979-05-27").is_err());
+/// assert!(toml::from_str("name = \"a\"\nname = \"b\"").is_err());
+/// ```
+pub fn from_str(document: &str) -> ioResult<Value> {
+    let mut parser = Parser { chars: document.chars().collect(), position: 0, line: 1 };
+    let mut root = Map::new();
+    // the keys leading from the root to the table the key/value pairs go to
+    let mut current: Vec<String> = Vec::new();
+
+    loop {
+        parser.skip_blank_lines();
+        let Some(c) = parser.peek() else {
+            break;
+        };
+        if c == '[' {
+            parser.position += 1;
+            let array = parser.eat('[');
+            parser.skip_spaces();
+            let path = parser.key()?;
+            parser.skip_spaces();
+            if !parser.eat(']') || (array && !parser.eat(']')) {
+                return Err(invalid(parser.line, "unterminated table header"));
+            }
+            let line = parser.line;
+            let (last, parents) = path.split_last().ok_or_else(|| invalid(line, "empty table header"))?;
+            let parent = table_at(&mut root, parents, line)?;
+            if array {
+                match parent.entry(last.to_string()).or_insert_with(|| Value::Array(Vec::new())) {
+                    Value::Array(items) => items.push(Value::Object(Map::new())),
+                    _ => return Err(invalid(line, format!("'{}' isn't an array of tables", last))),
+                }
+            } else {
+                match parent.entry(last.to_string()).or_insert_with(|| Value::Object(Map::new())) {
+                    Value::Object(_) => {},
+                    _ => return Err(invalid(line, format!("'{}' isn't a table", last))),
+                }
+            }
+            current = path;
+        } else {
+            let path = parser.key()?;
+            parser.skip_spaces();
+            if !parser.eat('=') {
+                return Err(invalid(parser.line, "expected '=' after the key"));
+            }
+            parser.skip_spaces();
+            let value = parser.value()?;
+            let line = parser.line;
+            let (last, parents) = path.split_last().ok_or_else(|| invalid(line, "empty key"))?;
+            let table = table_at(&mut root, &[current.as_slice(), parents].concat(), line)?;
+            if table.insert(last.to_string(), value).is_some() {
+                return Err(invalid(line, format!("'{}' is defined twice", last)));
+            }
+        }
+        parser.end_of_line()?;
+    }
+
+    Ok(Value::Object(root))
+}
+
+/// The table at the path, created if missing, where an array of tables stands for its last table
+fn table_at<'a>(root: &'a mut Map<String, Value>, path: &[String], line: usize) -> ioResult<&'a mut Map<String, Value>> {
+    let mut table = root;
+    for key in path {
+        let value = table.entry(key.to_string()).or_insert_with(|| Value::Object(Map::new()));
+        table = match value {
+            Value::Object(table) => table,
+            Value::Array(items) => match items.last_mut() {
+                Some(Value::Object(table)) => table,
+                _ => return Err(invalid(line, format!("'{}' isn't a table", key))),
+            },
+            _ => return Err(invalid(line, format!("'{}' isn't a table", key))),
+        };
+    }
+    Ok(table)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.position += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.position += 1;
+            }
+        }
+    }
+
+    /// Skips spaces, comments and line breaks, also within arrays
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') => self.line += 1,
+                Some('\r') => {},
+                _ => return,
+            }
+            self.position += 1;
+        }
+    }
+
+    fn end_of_line(&mut self) -> ioResult<()> {
+        self.skip_spaces();
+        self.skip_comment();
+        self.eat('\r');
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.position += 1;
+                self.line += 1;
+                Ok(())
+            }
+            Some(c) => Err(invalid(self.line, format!("unexpected '{}' at the end of the line", c))),
+        }
+    }
+
+    /// A key, made of dot separated parts, each either bare or quoted
+    fn key(&mut self) -> ioResult<Vec<String>> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_spaces();
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.position;
+                    while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                        self.position += 1;
+                    }
+                    if start == self.position {
+                        return Err(invalid(self.line, "expected a key"));
+                    }
+                    self.chars[start..self.position].iter().collect()
+                }
+            };
+            parts.push(part);
+            self.skip_spaces();
+            if !self.eat('.') {
+                return Ok(parts);
+            }
+        }
+    }
+
+    fn value(&mut self) -> ioResult<Value> {
+        match self.peek() {
+            Some('"') if self.chars[self.position..].starts_with(&['"', '"', '"']) => Err(invalid(self.line, "multi-line strings aren't supported")),
+            Some('"') => Ok(Value::String(self.basic_string()?)),
+            Some('\'') => Ok(Value::String(self.literal_string()?)),
+            Some('[') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_blank_lines();
+                    if self.eat(']') {
+                        return Ok(Value::Array(items));
+                    }
+                    items.push(self.value()?);
+                    self.skip_blank_lines();
+                    if !self.eat(',') {
+                        self.skip_blank_lines();
+                        if self.eat(']') {
+                            return Ok(Value::Array(items));
+                        }
+                        return Err(invalid(self.line, "expected ',' or ']' in the array"));
+                    }
+                }
+            }
+            Some('{') => {
+                self.position += 1;
+                let mut table = Map::new();
+                self.skip_spaces();
+                if self.eat('}') {
+                    return Ok(Value::Object(table));
+                }
+                loop {
+                    let path = self.key()?;
+                    self.skip_spaces();
+                    if !self.eat('=') {
+                        return Err(invalid(self.line, "expected '=' after the key"));
+                    }
+                    self.skip_spaces();
+                    let value = self.value()?;
+                    let line = self.line;
+                    let (last, parents) = path.split_last().ok_or_else(|| invalid(line, "empty key"))?;
+                    if table_at(&mut table, parents, line)?.insert(last.to_string(), value).is_some() {
+                        return Err(invalid(line, format!("'{}' is defined twice", last)));
+                    }
+                    self.skip_spaces();
+                    if self.eat('}') {
+                        return Ok(Value::Object(table));
+                    }
+                    if !self.eat(',') {
+                        return Err(invalid(self.line, "expected ',' or '}' in the inline table"));
+                    }
+                    self.skip_spaces();
+                }
+            }
+            _ => {
+                let start = self.position;
+                while matches!(self.peek(), Some(c) if !matches!(c, ',' | ']' | '}' | '#' | '\n' | '\r' | ' ' | '\t')) {
+                    self.position += 1;
+                }
+                let word = self.chars[start..self.position].iter().collect::<String>();
+                self.scalar(&word)
+            }
+        }
+    }
+
+    fn scalar(&self, word: &str) -> ioResult<Value> {
+        match word {
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            "" => return Err(invalid(self.line, "expected a value")),
+            _ => {},
+        }
+        let digits = word.replace('_', "");
+        if let Ok(n) = digits.parse::<i64>() {
+            return Ok(Value::Number(n.into()));
+        }
+        // Comment: a dash past the sign and out of the exponent is a date, and inf and nan, which Rust parses
+        // as well, can't be held by JSON numbers
+        let is_date = digits.char_indices().any(|(i, c)| c == ':' || c == '-' && i > 0 && !digits[..i].ends_with(['e', 'E']));
+        if !is_date {
+            if let Some(n) = digits.parse::<f64>().ok().and_then(Number::from_f64) {
+                return Ok(Value::Number(n));
+            }
+        }
+        Err(invalid(self.line, format!("unsupported value '{}'", word)))
+    }
+
+    fn basic_string(&mut self) -> ioResult<String> {
+        self.position += 1;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err(invalid(self.line, "unterminated string")),
+                Some('"') => {
+                    self.position += 1;
+                    return Ok(text);
+                }
+                Some('\\') => {
+                    self.position += 1;
+                    let escaped = self.peek().ok_or_else(|| invalid(self.line, "unterminated string"))?;
+                    self.position += 1;
+                    match escaped {
+                        '"' => text.push('"'),
+                        '\\' => text.push('\\'),
+                        'n' => text.push('\n'),
+                        't' => text.push('\t'),
+                        'r' => text.push('\r'),
+                        'b' => text.push('\u{8}'),
+                        'f' => text.push('\u{c}'),
+                        'u' | 'U' => {
+                            let length = if escaped == 'u' { 4 } else { 8 };
+                            let hex = self.chars.get(self.position..self.position + length)
+                                                .map(|hex| hex.iter().collect::<String>())
+                                                .ok_or_else(|| invalid(self.line, "unterminated escape"))?;
+                            self.position += length;
+                            let c = u32::from_str_radix(&hex, 16).ok()
+                                                                 .and_then(char::from_u32)
+                                                                 .ok_or_else(|| invalid(self.line, format!("invalid escape '\\{}{}'", escaped, hex)))?;
+                            text.push(c);
+                        }
+                        other => return Err(invalid(self.line, format!("invalid escape '\\{}'", other))),
+                    }
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.position += 1;
+                }
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> ioResult<String> {
+        self.position += 1;
+        let start = self.position;
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err(invalid(self.line, "unterminated string")),
+                Some('\'') => {
+                    let text = self.chars[start..self.position].iter().collect();
+                    self.position += 1;
+                    return Ok(text);
+                }
+                Some(_) => self.position += 1,
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::cell::Cell;
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Result as ioResult, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+use crate::error::Context;
+
+// Comment: a small stand-in for the tracing crate: spans are timed and reported when they close,
+// events are reported right away. Nothing is formatted unless a sink is installed, so the
+// instrumentation costs a single atomic load when tracing is off
+
+enum Sink {
+    // human readable lines, indented by the span depth
+    Stderr,
+    // one JSON object per line
+    File(BufWriter<File>),
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SINK: Mutex<Option<Sink>> = Mutex::new(None);
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Reports spans and events as human readable lines on stderr
+pub fn to_stderr() {
+    install(Sink::Stderr);
+}
+
+/// Reports spans and events as JSON lines appended to the log file
+pub fn to_file(path: &Path) -> ioResult<()> {
+    let f = OpenOptions::new()
+                        .append(true)
+                        .create(true)
+                        .open(path)
+                        .with_path("opening log file", path)?;
+    install(Sink::File(BufWriter::new(f)));
+    Ok(())
+}
+
+fn install(sink: Sink) {
+    *SINK.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(sink);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Writes out whatever the log file sink still buffers, done once the command is over
+pub fn flush() {
+    if let Some(Sink::File(f)) = SINK.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_mut() {
+        let _ = f.flush();
+    }
+}
+
+/// A timed section of work, reported with its fields and duration when dropped
+/// # Examples
+/// ```
+/// use rusty_journal_clap::trace;
+/// let mut span = trace::span("journal.load", &[("path", &"todo.json")]);
+/// span.record("tasks", 3);
+/// ```
+pub struct Span {
+    name: &'static str,
+    fields: Vec<(&'static str, String)>,
+    start: Instant,
+    enabled: bool,
+}
+
+/// Opens a span, which closes when the returned value is dropped
+pub fn span(name: &'static str, fields: &[(&'static str, &dyn Display)]) -> Span {
+    let enabled = is_enabled();
+    let fields = if enabled {
+        DEPTH.with(|depth| depth.set(depth.get() + 1));
+        fields.iter().map(|(key, value)| (*key, value.to_string())).collect()
+    } else {
+        Vec::new()
+    };
+    Span { name, fields, start: Instant::now(), enabled }
+}
+
+impl Span {
+    /// Adds a field that is only known once the work is done, e.g. the number of tasks loaded
+    pub fn record(&mut self, key: &'static str, value: impl Display) {
+        if self.enabled {
+            self.fields.push((key, value.to_string()));
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        let depth = DEPTH.with(|depth| {
+            depth.set(depth.get() - 1);
+            depth.get()
+        });
+        emit("span", self.name, depth, Some(self.start.elapsed().as_micros()), &self.fields);
+    }
+}
+
+/// Reports a single occurrence, e.g. an error, inside the current span
+pub fn event(name: &'static str, fields: &[(&'static str, &dyn Display)]) {
+    if !is_enabled() {
+        return;
+    }
+    let fields = fields.iter()
+                       .map(|(key, value)| (*key, value.to_string()))
+                       .collect::<Vec<_>>();
+    emit("event", name, DEPTH.with(Cell::get), None, &fields);
+}
+
+fn emit(kind: &str, name: &str, depth: usize, elapsed_us: Option<u128>, fields: &[(&'static str, String)]) {
+    let mut sink = SINK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // Comment: failing to write a trace shouldn't fail the command being traced
+    let _ = match sink.as_mut() {
+        Some(Sink::Stderr) => {
+            let elapsed = elapsed_us.map(|us| format!("{:>10.3}ms", us as f64 / 1000.0))
+                                    .unwrap_or_else(|| format!("{:>12}", ""));
+            let fields = fields.iter()
+                               .map(|(key, value)| format!(" {}={}", key, value))
+                               .collect::<String>();
+            writeln!(io::stderr(), "[trace] {} {}{}{}", elapsed, "  ".repeat(depth), name, fields)
+        }
+        Some(Sink::File(f)) => {
+            let fields = fields.iter()
+                               .map(|(key, value)| (key.to_string(), Value::String(value.to_owned())))
+                               .collect::<Map<_, _>>();
+            // Comment: the log is about when things actually happened, so this is the system time and not `clock::now`
+            let mut line = json!({
+                "time": Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+                "kind": kind,
+                "name": name,
+                "depth": depth,
+                "fields": fields,
+            });
+            if let Some(elapsed_us) = elapsed_us {
+                line["elapsed_us"] = json!(elapsed_us);
+            }
+            writeln!(f, "{}", line)
+        }
+        None => Ok(()),
+    };
+}
@@ -0,0 +1,104 @@
+use std::fs;
+use std::io::{Error, ErrorKind, Result as ioResult};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, Ordering};
+use chrono::{DateTime, Duration, Utc, serde::ts_seconds};
+use serde::{Deserialize, Serialize};
+use crate::error::{Context, ParseFailure};
+use crate::held;
+use crate::paths;
+use crate::stdio;
+use crate::task::{self, Task};
+
+// in seconds, 30 days unless the config sets trash_retention
+static RETENTION: AtomicI64 = AtomicI64::new(30 * 86_400);
+
+/// Sets for the whole process how long removed tasks stay in the trash before it is emptied of them
+pub fn set_retention(retention: Duration) {
+    RETENTION.store(retention.num_seconds(), Ordering::Relaxed);
+}
+
+pub fn retention() -> Duration {
+    Duration::seconds(RETENTION.load(Ordering::Relaxed))
+}
+
+/// A removed task, kept until it is restored or its time in the trash is up
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Trashed {
+    #[serde(with = "ts_seconds")]
+    pub deleted_at: DateTime<Utc>,
+    pub task: Task,
+}
+
+/// The trash is kept next to the journal, e.g. `todo.trash.json` for `todo.json`
+/// # Examples
+/// ```
+/// use rusty_journal_clap::trash;
+/// use std::path::{Path, PathBuf};
+/// assert_eq!(trash::trash_path(Path::new("work/todo.json")), PathBuf::from("work/todo.trash.json"));
+/// ```
+pub fn trash_path(journal_path: &Path) -> PathBuf {
+    journal_path.with_extension("trash.json")
+}
+
+/// Reads the tasks in the trash, leaving out the ones past the retention, which are dropped from
+/// the trash file for good. A missing trash file means an empty trash
+pub fn load(journal_path: &Path, now: DateTime<Utc>) -> ioResult<Vec<Trashed>> {
+    // Comment: a journal piped through stdin has no trash, its removed tasks are still in the input
+    if stdio::is_stdio(journal_path) {
+        return Ok(Vec::new());
+    }
+    let path = trash_path(journal_path);
+    let content = match paths::resolve(&path).and_then(fs::read) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_path("reading trash", &path),
+    };
+    let mut trashed: Vec<Trashed> = serde_json::from_slice(&content).map_err(ParseFailure::of)
+                                                                    .with_path("reading trash", &path)?;
+
+    let count = trashed.len();
+    let oldest_kept = now - retention();
+    trashed.retain(|trashed| trashed.deleted_at > oldest_kept);
+    // Comment: emptying the trash is left for the next run in read-only mode, rather than failing a mere listing
+    if trashed.len() < count && !task::is_read_only() {
+        save(journal_path, &trashed)?;
+    }
+    Ok(trashed)
+}
+
+/// Overwrites the trash with the given tasks
+pub(crate) fn save(journal_path: &Path, trashed: &[Trashed]) -> ioResult<()> {
+    let path = trash_path(journal_path);
+    Task::_check_writable(&path)?;
+    let content = serde_json::to_vec(trashed).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+    paths::resolve(&path).and_then(|resolved| fs::write(resolved, content))
+                         .with_path("writing trash", &path)
+}
+
+/// Moves the removed tasks to the trash. A task without id gets one past the ids in use, in the journal
+/// and in the trash, so it can be restored by id
+pub(crate) fn put(journal_path: &Path, removed: Vec<Task>, kept: &[Task], now: DateTime<Utc>) -> ioResult<()> {
+    if removed.is_empty() || stdio::is_stdio(journal_path) {
+        return Ok(());
+    }
+    if held::is_held(journal_path) {
+        held::put(removed);
+        return Ok(());
+    }
+    let mut trashed = load(journal_path, now)?;
+    let mut next_id = kept.iter()
+                          .chain(trashed.iter().map(|trashed| &trashed.task))
+                          .chain(&removed)
+                          .filter_map(Task::id)
+                          .max()
+                          .map_or(1, |max| max + 1);
+    for mut task in removed {
+        if task.id().is_none() {
+            task.set_id(next_id);
+            next_id += 1;
+        }
+        trashed.push(Trashed { deleted_at: now, task });
+    }
+    save(journal_path, &trashed)
+}
@@ -0,0 +1,181 @@
+use std::fs;
+use std::io::{Error, ErrorKind, Result as ioResult};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use chrono::{DateTime, Utc, serde::ts_seconds};
+use serde::{Deserialize, Serialize};
+use crate::error::{Context, ParseFailure};
+use crate::i18n::tr;
+use crate::paths;
+use crate::trash;
+
+// Comment: every change keeps the journal as it was before as a backup next to it, a hard link to the old file
+// which the atomic write leaves behind anyway, so keeping it costs no copy however large the journal.
+// The backups roll over: `.todo.json.undo-1` is the journal before the latest change, `.todo.json.undo-2` the one before,
+// and so on, while `.todo.json.undo` lists the commands that made the changes, most recent first.
+// The trash as it was before each change is kept as well, `.todo.json.undo-1.trash` and so on, so that undoing
+// a `remove` takes its tasks out of the trash again and undoing a `restore` puts them back in
+
+// 10 unless the config sets undo_depth
+static DEPTH: AtomicUsize = AtomicUsize::new(10);
+static COMMAND: Mutex<Option<String>> = Mutex::new(None);
+
+/// Sets for the whole process how many changes of the journal can be undone, 0 keeps no backups
+pub fn set_depth(depth: usize) {
+    DEPTH.store(depth, Ordering::Relaxed);
+}
+
+pub fn depth() -> usize {
+    DEPTH.load(Ordering::Relaxed)
+}
+
+/// Sets for the whole process the command the next changes of the journal are recorded under, e.g. `remove`
+pub fn set_command(command: Option<&str>) {
+    // Comment: a poisoned lock only means another thread panicked while naming its command, the name is still usable
+    *COMMAND.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = command.map(str::to_string);
+}
+
+/// A change of the journal that can be undone
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Step {
+    /// The command that made the change, None when it was made through the library
+    pub command: Option<String>,
+    #[serde(with = "ts_seconds")]
+    pub at: DateTime<Utc>,
+}
+
+/// The list of the changes that can be undone, hidden next to the journal
+/// # Examples
+/// ```
+/// use rusty_journal_clap::undo;
+/// use std::path::{Path, PathBuf};
+/// assert_eq!(undo::log_path(Path::new("work/todo.json")), PathBuf::from("work/.todo.json.undo"));
+/// assert_eq!(undo::backup_path(Path::new("work/todo.json"), 2), PathBuf::from("work/.todo.json.undo-2"));
+/// ```
+pub fn log_path(journal_path: &Path) -> PathBuf {
+    let file_name = journal_path.file_name().unwrap_or_default().to_string_lossy();
+    journal_path.with_file_name(format!(".{}.undo", file_name))
+}
+
+/// The journal as it was before the nth most recent change, from 1
+pub fn backup_path(journal_path: &Path, n: usize) -> PathBuf {
+    let file_name = journal_path.file_name().unwrap_or_default().to_string_lossy();
+    journal_path.with_file_name(format!(".{}.undo-{}", file_name, n))
+}
+
+/// The trash as it was before the nth most recent change, from 1
+pub fn trash_backup_path(journal_path: &Path, n: usize) -> PathBuf {
+    let file_name = journal_path.file_name().unwrap_or_default().to_string_lossy();
+    journal_path.with_file_name(format!(".{}.undo-{}.trash", file_name, n))
+}
+
+/// The trash of a journal as it was before a change, read with the journal locked before the change
+/// and kept by `record` along with the journal
+pub(crate) struct TrashBackup {
+    // None for a journal without a trash file yet
+    content: Option<Vec<u8>>,
+}
+
+impl TrashBackup {
+    pub(crate) fn take(journal_path: &Path) -> ioResult<TrashBackup> {
+        let path = trash::trash_path(journal_path);
+        let content = match paths::resolve(&path).and_then(fs::read) {
+            Ok(content) => Some(content),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(err).with_path("reading trash", &path),
+        };
+        Ok(TrashBackup { content })
+    }
+}
+
+/// The changes of the journal file that can be undone, most recent first
+pub fn steps(journal_path: &Path) -> ioResult<Vec<Step>> {
+    let path = log_path(journal_path);
+    let content = match fs::read(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_path("reading undo log", &path),
+    };
+    serde_json::from_slice(&content).map_err(ParseFailure::of)
+                                    .with_path("reading undo log", &path)
+}
+
+fn save_steps(journal_path: &Path, steps: &[Step]) -> ioResult<()> {
+    let path = log_path(journal_path);
+    let content = serde_json::to_vec(steps).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+    fs::write(&path, content).with_path("writing undo log", &path)
+}
+
+/// Keeps the journal file as it is, and the trash as it was, as the backup of the latest change, before it is replaced.
+/// Called with the journal locked, on the file the journal resolves to
+pub(crate) fn record(journal_path: &Path, trash: &TrashBackup, now: DateTime<Utc>) -> ioResult<()> {
+    let depth = depth();
+    if depth == 0 {
+        return Ok(());
+    }
+    let mut steps = steps(journal_path)?;
+    steps.truncate(depth - 1);
+
+    let _ = fs::remove_file(backup_path(journal_path, depth));
+    let _ = fs::remove_file(trash_backup_path(journal_path, depth));
+    for n in (1..=steps.len()).rev() {
+        fs::rename(backup_path(journal_path, n), backup_path(journal_path, n + 1)).with_path("keeping undo backup", journal_path)?;
+        rename_trash_backup(journal_path, n, n + 1)?;
+    }
+    let backup = backup_path(journal_path, 1);
+    let kept = match fs::hard_link(journal_path, &backup) {
+        // Comment: undoing the change that created the journal leaves an empty journal
+        Err(err) if err.kind() == ErrorKind::NotFound => fs::write(&backup, b""),
+        // a file system without hard links gets a copy
+        Err(_) => fs::copy(journal_path, &backup).map(|_| ()),
+        Ok(()) => Ok(()),
+    };
+    kept.with_path("keeping undo backup", journal_path)?;
+    // a journal without a trash had an empty one
+    let trash_content = trash.content.as_deref().unwrap_or(b"[]");
+    fs::write(trash_backup_path(journal_path, 1), trash_content).with_path("keeping undo backup", journal_path)?;
+
+    let command = COMMAND.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    steps.insert(0, Step { command, at: now });
+    save_steps(journal_path, &steps)
+}
+
+/// Puts back the journal and its trash as they were before the given number of most recent changes, which are returned,
+/// most recent first. Called with the journal locked, on the file the journal resolves to and the trash of the journal
+pub(crate) fn undo(journal_path: &Path, trash_path: &Path, count: usize) -> ioResult<Vec<Step>> {
+    let mut steps = steps(journal_path)?;
+    if steps.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, tr("error-undo-nothing", &[])));
+    }
+    if count == 0 || count > steps.len() {
+        return Err(Error::new(ErrorKind::InvalidInput, tr("error-undo-steps", &[("count", &steps.len())])));
+    }
+
+    // Comment: the rename is atomic like any write of the journal, the backups in between go as they are newer than the journal now
+    fs::rename(backup_path(journal_path, count), journal_path).with_path("undoing", journal_path)?;
+    // Comment: backups kept before the trash was backed up too leave the trash as it is
+    match fs::rename(trash_backup_path(journal_path, count), paths::resolve(trash_path)?) {
+        Err(err) if err.kind() != ErrorKind::NotFound => return Err(err).with_path("undoing", trash_path),
+        _ => {}
+    }
+    for n in 1..count {
+        let _ = fs::remove_file(backup_path(journal_path, n));
+        let _ = fs::remove_file(trash_backup_path(journal_path, n));
+    }
+    for n in count + 1..=steps.len() {
+        fs::rename(backup_path(journal_path, n), backup_path(journal_path, n - count)).with_path("undoing", journal_path)?;
+        rename_trash_backup(journal_path, n, n - count)?;
+    }
+
+    let undone = steps.drain(..count).collect();
+    save_steps(journal_path, &steps)?;
+    Ok(undone)
+}
+
+fn rename_trash_backup(journal_path: &Path, from: usize, to: usize) -> ioResult<()> {
+    match fs::rename(trash_backup_path(journal_path, from), trash_backup_path(journal_path, to)) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err).with_path("keeping undo backup", journal_path),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,94 @@
+use std::io::{Error, ErrorKind, Result as ioResult};
+use std::process::{Command, Stdio};
+use crate::color::{self, ColorMode};
+use crate::i18n::tr;
+
+const SCHEMES: [&str; 2] = ["https://", "http://"];
+
+/// Finds the http(s) URLs in the text, in order. Punctuation ending a sentence right after
+/// a URL isn't part of it, nor is a closing parenthesis without an opening one inside the URL
+/// # Examples
+/// ```
+/// use rusty_journal_clap::url;
+/// assert_eq!(url::find("Read https://example.com/a?b=1, then http://x.org/(y)."), ["https://example.com/a?b=1", "http://x.org/(y)"]);
+/// assert_eq!(url::find("(see https://example.com)"), ["https://example.com"]);
+/// assert!(url::find("no link here").is_empty());
+/// ```
+pub fn find(text: &str) -> Vec<&str> {
+    let mut urls = Vec::new();
+    let mut rest = text;
+    while let Some(start) = SCHEMES.iter().filter_map(|scheme| rest.find(scheme)).min() {
+        let candidate = &rest[start..];
+        let end = candidate.find(|c: char| c.is_whitespace() || ['<', '>', '"'].contains(&c))
+                           .unwrap_or(candidate.len());
+        let mut url = &candidate[..end];
+        loop {
+            let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'']);
+            let trimmed = match trimmed.strip_suffix(')') {
+                Some(unbalanced) if trimmed.matches('(').count() < trimmed.matches(')').count() => unbalanced,
+                _ => trimmed,
+            };
+            if trimmed == url {
+                break;
+            }
+            url = trimmed;
+        }
+        // a bare scheme isn't a URL
+        if !SCHEMES.contains(&url) {
+            urls.push(url);
+        }
+        rest = &candidate[end..];
+    }
+    urls
+}
+
+/// Underlines the URLs in the text, leaving it as it is with `ColorMode::Never`
+/// # Examples
+/// ```
+/// use rusty_journal_clap::{color::ColorMode, url};
+/// assert_eq!(url::underline("see https://example.com", ColorMode::Ansi), "see \x1b[4mhttps://example.com\x1b[24m");
+/// ```
+pub fn underline(text: &str, mode: ColorMode) -> String {
+    if mode == ColorMode::Never {
+        return text.to_string();
+    }
+    let mut underlined = String::with_capacity(text.len());
+    let mut rest = text;
+    for url in find(text) {
+        // Comment: the URLs are found in order and don't overlap, so each one is in what is left of the text
+        let start = rest.find(url).unwrap();
+        underlined.push_str(&rest[..start]);
+        underlined.push_str(&color::underline(url, mode));
+        rest = &rest[start + url.len()..];
+    }
+    underlined.push_str(rest);
+    underlined
+}
+
+/// Opens the URL with the system opener, i.e. usually in the default browser
+pub fn open(url: &str) -> ioResult<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        // the empty argument is the window title `start` expects before the URL
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(unix) {
+        Command::new("xdg-open")
+    } else {
+        return Err(Error::new(ErrorKind::Unsupported, tr("error-open-unsupported", &[])));
+    };
+
+    let status = command.arg(url)
+                        .stdin(Stdio::null())
+                        .stdout(Stdio::null())
+                        .stderr(Stdio::null())
+                        .status()?;
+
+    if !status.success() {
+        return Err(Error::other(tr("error-opener-exited", &[("status", &status)])));
+    }
+
+    Ok(())
+}
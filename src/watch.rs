@@ -0,0 +1,73 @@
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, Result as ioResult, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use crate::output;
+
+// Comment: there is no file notification crate available, so the journal is polled. Checking the
+// metadata twice a second is cheap and quick enough for a list on a second monitor
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Tells whether a file changed since it was last looked at, by its modification time and size
+/// # Examples
+/// ```
+/// use rusty_journal_clap::watch::Watcher;
+/// use std::{env, fs};
+/// let path = env::temp_dir().join(format!("watched-{}.json", std::process::id()));
+/// fs::write(&path, "[]").unwrap();
+/// let mut watcher = Watcher::new(&path);
+/// assert!(!watcher.changed());
+/// fs::write(&path, "[ ]").unwrap();
+/// assert!(watcher.changed());
+/// assert!(!watcher.changed());
+/// fs::remove_file(&path).unwrap();
+/// ```
+pub struct Watcher {
+    path: PathBuf,
+    last_seen: Option<(SystemTime, u64)>,
+}
+
+impl Watcher {
+    pub fn new(path: &Path) -> Self {
+        let path = path.to_path_buf();
+        let last_seen = Self::stamp(&path);
+        Watcher { path, last_seen }
+    }
+
+    fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+        // a journal that is missing for a moment, e.g. while a sync tool replaces it, is a change as well
+        let metadata = fs::metadata(path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    /// Whether the file changed since the watcher was created or this was last called
+    pub fn changed(&mut self) -> bool {
+        let stamp = Self::stamp(&self.path);
+        let changed = stamp != self.last_seen;
+        self.last_seen = stamp;
+        changed
+    }
+}
+
+/// Renders the view, then renders it again on a cleared screen whenever the journal changes, until interrupted.
+/// A failing render, e.g. on a journal that is only half written, is shown in place of the view
+/// and the watch goes on
+pub fn watch<E: Display>(journal_path: &Path, mut render: impl FnMut() -> Result<(), E>) -> ioResult<()> {
+    let mut watcher = Watcher::new(journal_path);
+    loop {
+        // Comment: the deterministic mode doesn't clear the screen, so the renders can be told apart in captured output
+        if !output::is_deterministic() {
+            print!("\x1b[2J\x1b[H");
+        }
+        if let Err(err) = render() {
+            println!("{}", err);
+        }
+        io::stdout().flush()?;
+
+        while !watcher.changed() {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
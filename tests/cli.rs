@@ -0,0 +1,743 @@
+use std::fs;
+use std::path::Path;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use chrono::DateTime;
+use clap::CommandFactory;
+use rusty_journal_clap::cli::Cli;
+use rusty_journal_clap::clock::{self, FixedClock};
+use rusty_journal_clap::notify;
+use rusty_journal_clap::redact;
+use rusty_journal_clap::test_util::TempJournal;
+
+/// Runs the binary on the journal in deterministic mode, returning its stdout
+fn run(journal: &TempJournal, args: &[&str]) -> String {
+    let output = command(journal, &journal.path(), args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Runs the binary on the journal file expecting it to fail, returning its stderr
+fn run_failing(journal: &TempJournal, journal_file: &Path, args: &[&str]) -> String {
+    let output = command(journal, journal_file, args).output().unwrap();
+    assert!(!output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+/// Runs the binary on the journal file expecting it to fail, returning its exit status
+fn exit_code(journal: &TempJournal, journal_file: &Path, args: &[&str]) -> Option<i32> {
+    command(journal, journal_file, args).output().unwrap().status.code()
+}
+
+fn command(journal: &TempJournal, journal_file: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rusty-journal-clap"));
+    command.arg("--deterministic")
+           .arg("--journal_file")
+           .arg(journal_file)
+           .args(args)
+           // keeps the user's config, e.g. macros, out of the tests
+           .env("RUSTY_JOURNAL_CONFIG", journal.dir().join("config.json"))
+           .env("TZ", "Pacific/Kiritimati");
+    command
+}
+
+fn seeded() -> TempJournal {
+    let _guard = clock::install(FixedClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap()));
+    TempJournal::seeded().unwrap()
+}
+
+/// The names of the tasks in journal order
+fn names(journal: &TempJournal) -> Vec<String> {
+    journal.tasks().unwrap().iter().map(|task| task.name().to_string()).collect()
+}
+
+#[test]
+fn cli_definition_is_consistent() {
+    // Comment: clap checks the arg ids named in groups, requires and conflicts only when asked to, or on first use
+    Cli::command().debug_assert();
+}
+
+#[test]
+fn list_snapshot() {
+    let journal = seeded();
+    assert_eq!(run(&journal, &["list", "--tag", "work"]),
+"Task: Write the quarterly report                         Created at: 14/11/2023 22:13
+Task: Review pull requests                               Created at: 14/11/2023 22:13 Done at: 14/11/2023 22:13
+");
+}
+
+#[test]
+fn list_filters_by_state() {
+    let journal = seeded();
+    run(&journal, &["done", "1"]);
+    assert_eq!(run(&journal, &["list", "--state", "active"]),
+"Task: Write the quarterly report                         Created at: 14/11/2023 22:13
+Task: Book the dentist                                   Created at: 14/11/2023 22:13
+");
+    let complete = run(&journal, &["list", "--state", "complete"]);
+    assert_eq!(complete.lines().map(|line| line.split_whitespace().nth(1).unwrap()).collect::<Vec<_>>(), ["Buy", "File", "Review"]);
+    assert!(complete.lines().all(|line| line.contains(" Done at: ")));
+}
+
+#[test]
+fn report_snapshot() {
+    let journal = seeded();
+    let template = journal.dir().join("report.txt");
+    fs::write(&template, "{% for task in tasks %}{{ task.index }} {{ task.name }} {{ task.created_at | date(format=\"%H:%M\") }}\n{% endfor %}").unwrap();
+    assert_eq!(run(&journal, &["report", "--template", template.to_str().unwrap()]),
+"1 Buy milk 22:13
+2 Write the quarterly report 22:13
+3 File the taxes 22:13
+4 Book the dentist 22:13
+5 Review pull requests 22:13
+");
+}
+
+#[test]
+fn plain_report_lists_the_overdue_tasks_and_fails_on_them() {
+    let journal = seeded();
+    let report = run(&journal, &["report", "--plain"]);
+    assert!(report.contains("\nOverdue (0)\n\nPending (3)\n"));
+    assert_eq!(exit_code(&journal, &journal.path(), &["report", "--plain"]), Some(0));
+
+    run(&journal, &["add", "--task", "Renew the passport", "--due", "2023-11-01"]);
+    let output = command(&journal, &journal.path(), &["report", "--plain"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8(output.stdout).unwrap().contains("\nOverdue (1)\n     6. Renew the passport\n"));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "error in `report`: 1 task is overdue\n");
+}
+
+#[test]
+fn errors_name_the_command_and_the_file() {
+    let journal = seeded();
+    let missing = journal.dir().join("missing").join("todo.json");
+    assert_eq!(run_failing(&journal, &missing, &["list"]),
+               format!("error in `list`: while opening journal '{}': the directory '{}' doesn't exist\n",
+                       missing.display(), missing.parent().unwrap().display()));
+    assert_eq!(run_failing(&journal, &journal.path(), &["remove", "9"]), "error in `remove`: Invalid Task ID 9, the journal has 5 tasks\n");
+}
+
+#[test]
+fn exit_codes_tell_the_errors_apart() {
+    let journal = seeded();
+    let missing = journal.dir().join("missing").join("todo.json");
+    assert_eq!(exit_code(&journal, &missing, &["list"]), Some(1));
+    assert_eq!(exit_code(&journal, &journal.path(), &["done", "9"]), Some(3));
+
+    let damaged = journal.dir().join("damaged.json");
+    fs::write(&damaged, "[{\"name\": ").unwrap();
+    assert_eq!(exit_code(&journal, &damaged, &["list"]), Some(4));
+    assert!(run_failing(&journal, &damaged, &["list"]).ends_with("(the file is damaged or not in the expected format)\n"));
+
+    // a tool failing on the file isn't telling whether the journal could be parsed
+    let not_gzip = journal.dir().join("damaged.json.gz");
+    fs::write(&not_gzip, b"\x1f\x8b cut short").unwrap();
+    assert_eq!(exit_code(&journal, &not_gzip, &["list"]), Some(1));
+    let csv = journal.dir().join("tasks.csv");
+    fs::write(&csv, "name\n\"unterminated\n").unwrap();
+    assert_eq!(exit_code(&journal, &journal.path(), &["import", "--from", "csv", csv.to_str().unwrap()]), Some(4));
+}
+
+#[test]
+fn json_errors_carry_the_details() {
+    let journal = seeded();
+    let error = run_failing(&journal, &journal.path(), &["--error-format", "json", "remove", "2,9"]);
+    let error: serde_json::Value = serde_json::from_str(&error).unwrap();
+    assert_eq!(error["code"], "invalid_index");
+    assert_eq!(error["command"], "remove");
+    assert_eq!(error["index"], 9);
+
+    let missing = journal.dir().join("missing").join("todo.json");
+    let error: serde_json::Value = serde_json::from_str(&run_failing(&journal, &missing, &["--error-format", "json", "list"])).unwrap();
+    assert_eq!(error["code"], "not_found");
+    assert_eq!(error["path"], missing.to_str().unwrap());
+}
+
+#[test]
+fn prompt_segment_snapshot() {
+    let journal = seeded();
+    assert_eq!(run(&journal, &["prompt-segment", "--color", "zsh"]), "⚑3\n");
+    assert_eq!(run(&journal, &["prompt-segment", "--tag", "admin"]), "");
+
+    run(&journal, &["add", "--task", "Renew the passport", "--due", "2023-11-01", "--tag", "home"]);
+    assert_eq!(run(&journal, &["prompt-segment"]), "⚑4 ⚠1\n");
+    assert_eq!(run(&journal, &["prompt-segment", "--tag", "work"]), "⚑1\n");
+}
+
+#[test]
+fn messages_follow_the_configured_locale() {
+    let journal = seeded();
+    fs::write(journal.dir().join("config.json"), r#"{"locale": "de"}"#).unwrap();
+    assert_eq!(run(&journal, &["list", "--tag", "groceries"]),
+               "Aufgabe: Buy milk                                           Erstellt am: 14/11/2023 22:13\n");
+    assert_eq!(run_failing(&journal, &journal.path(), &["remove", "9"]), "Fehler in `remove`: Ungültige Aufgaben-ID 9, das Journal hat 5 Aufgaben\n");
+    assert!(run(&journal, &["done", "--tag", "work", "--dry-run"]).ends_with("\n1 Aufgabe würde erledigt\n"));
+    assert_eq!(run(&journal, &["remove", "--tag", "errands", "--yes"]), "Keine Aufgabe zu entfernen\n");
+    assert_eq!(run_failing(&journal, &journal.path(), &["move-to", "1", "--after", "1"]),
+               "Fehler in `move-to`: Eine Aufgabe kann nicht hinter sich selbst verschoben werden\n");
+    assert_eq!(run_failing(&journal, &journal.path(), &["move-to", "1", "9"]), "Fehler in `move-to`: Ungültige Position\n");
+
+    let missing = journal.dir().join("missing").join("todo.json");
+    assert!(run_failing(&journal, &missing, &["list"]).starts_with(&format!("Fehler in `list`: beim Öffnen des Journals '{}': ", missing.display())));
+}
+
+#[test]
+fn macros_run_their_steps_in_order() {
+    let journal = seeded();
+    fs::write(journal.dir().join("config.json"), r#"{"macros": {
+        "tidy": ["done 1", "move-to 1 --after 4", "list --state active"],
+        "nested": ["tidy"],
+        "broken": ["done 1", "remove 9", "done 3"],
+        "elsewhere": ["--journal_file other.json add --task Stray"],
+        "undoing": ["done 1", "undo"]
+    }}"#).unwrap();
+
+    let listed = run(&journal, &["tidy"]);
+    assert_eq!(listed.lines().map(|line| line.split_whitespace().nth(1).unwrap()).collect::<Vec<_>>(), ["Write", "Book"]);
+    assert_eq!(names(&journal), ["Write the quarterly report", "File the taxes", "Book the dentist", "Buy milk", "Review pull requests"]);
+    assert!(journal.tasks().unwrap()[3].is_complete());
+    // the journal is written once, so a single undo takes back every step
+    run(&journal, &["undo"]);
+    assert_eq!(names(&journal), ["Buy milk", "Write the quarterly report", "File the taxes", "Book the dentist", "Review pull requests"]);
+    assert!(!journal.tasks().unwrap()[0].is_complete());
+
+    assert!(run_failing(&journal, &journal.path(), &["nested"]).contains("Macro 'nested' cannot run macro 'tidy'"));
+    // a failing step leaves the journal as it was before the first one
+    assert!(run_failing(&journal, &journal.path(), &["broken"]).contains("Invalid Task ID 9"));
+    assert!(!journal.tasks().unwrap()[0].is_complete());
+    assert!(run_failing(&journal, &journal.path(), &["elsewhere"]).contains("Macro 'elsewhere' step '--journal_file other.json add --task Stray' can't set --journal_file"));
+    assert!(!journal.dir().join("other.json").exists());
+    assert!(run_failing(&journal, &journal.path(), &["undoing"]).contains("Macro 'undoing' cannot run `undo`"));
+    assert!(!journal.tasks().unwrap()[0].is_complete());
+    assert!(run_failing(&journal, &journal.path(), &["tidy-up"]).contains("Unrecognized subcommand 'tidy-up'"));
+}
+
+#[test]
+fn move_to_reorders_the_journal() {
+    let journal = seeded();
+    run(&journal, &["move-to", "1", "3"]);
+    assert_eq!(names(&journal), ["Write the quarterly report", "File the taxes", "Buy milk", "Book the dentist", "Review pull requests"]);
+    run(&journal, &["move-to", "5", "--after", "1"]);
+    assert_eq!(names(&journal), ["Write the quarterly report", "Review pull requests", "File the taxes", "Buy milk", "Book the dentist"]);
+    run(&journal, &["move-to", "1", "--after", "5"]);
+    assert_eq!(names(&journal), ["Review pull requests", "File the taxes", "Buy milk", "Book the dentist", "Write the quarterly report"]);
+
+    // a failed move leaves the order alone
+    assert!(run_failing(&journal, &journal.path(), &["move-to", "1", "6"]).contains("Invalid position"));
+    assert!(run_failing(&journal, &journal.path(), &["move-to", "6", "1"]).contains("Invalid Task ID 6"));
+    assert!(run_failing(&journal, &journal.path(), &["move-to", "2", "--after", "2"]).contains("Cannot move a task after itself"));
+    assert_eq!(exit_code(&journal, &journal.path(), &["move-to", "1", "2", "--after", "3"]), Some(2));
+    assert_eq!(names(&journal), ["Review pull requests", "File the taxes", "Buy milk", "Book the dentist", "Write the quarterly report"]);
+}
+
+#[test]
+fn swap_exchanges_two_tasks() {
+    let journal = seeded();
+    let ids = |journal: &TempJournal| journal.tasks().unwrap().iter().map(|task| task.id()).collect::<Vec<_>>();
+    let before = ids(&journal);
+    run(&journal, &["swap", "1", "4"]);
+    assert_eq!(names(&journal), ["Book the dentist", "Write the quarterly report", "File the taxes", "Buy milk", "Review pull requests"]);
+    // the tasks keep their ids, only their positions change
+    assert_eq!(ids(&journal), [before[3], before[1], before[2], before[0], before[4]]);
+    run(&journal, &["swap", "2", "2"]);
+    run(&journal, &["swap", "4", "1"]);
+    assert_eq!(ids(&journal), before);
+
+    assert!(run_failing(&journal, &journal.path(), &["swap", "1", "6"]).contains("Invalid Task ID 6"));
+    assert_eq!(ids(&journal), before);
+}
+
+#[test]
+fn csv_import_maps_the_columns_and_previews_with_dry_run() {
+    let journal = seeded();
+    let csv = journal.dir().join("tasks.csv");
+    fs::write(&csv, "Title,Labels,Status\n\"Paint the fence, white\",home;garden,open\nMow the lawn,garden,done\n,garden,open\n").unwrap();
+    let csv = csv.to_str().unwrap();
+    let map = "name=Title,tags=Labels,state=Status";
+
+    let preview = run(&journal, &["import", "--from", "csv", csv, "--map", map, "--dry-run"]);
+    assert!(preview.ends_with("2 tasks would be imported\n"), "{}", preview);
+    assert_eq!(journal.tasks().unwrap().len(), 5);
+
+    assert_eq!(run(&journal, &["import", "--from", "csv", csv, "--map", map]), "2 tasks imported\n");
+    let tasks = journal.tasks().unwrap();
+    assert_eq!((tasks[5].name(), tasks[5].tags()), ("Paint the fence, white", &["home".to_string(), "garden".to_string()][..]));
+    assert!(!tasks[5].is_complete());
+    assert_eq!(tasks[6].name(), "Mow the lawn");
+    assert!(tasks[6].is_complete());
+
+    assert!(run_failing(&journal, &journal.path(), &["import", "--from", "csv", csv, "--map", "name=Summary"]).contains("Summary"));
+    assert_eq!(journal.tasks().unwrap().len(), 7);
+}
+
+#[test]
+fn renumber_sorts_the_journal_and_fills_in_missing_ids() {
+    let journal = seeded();
+    // a task written by an older version, without an id
+    let mut tasks: serde_json::Value = serde_json::from_str(&fs::read_to_string(journal.path()).unwrap()).unwrap();
+    tasks[0].as_object_mut().unwrap().remove("id");
+    fs::write(journal.path(), tasks.to_string()).unwrap();
+    run(&journal, &["add", "--task", "Call the bank"]);
+    run(&journal, &["swap", "1", "6"]);
+
+    assert_eq!(run(&journal, &["renumber"]),
+"   2 -> 1    id 2    Write the quarterly report
+   4 -> 2    id 4    Book the dentist
+   6 -> 3    id 7    Buy milk
+   1 -> 4    id 6    Call the bank
+   3 -> 5    id 3    File the taxes
+   5 -> 6    id 5    Review pull requests
+");
+    assert_eq!(names(&journal), ["Write the quarterly report", "Book the dentist", "Buy milk", "Call the bank", "File the taxes", "Review pull requests"]);
+    assert!(journal.tasks().unwrap().iter().all(|task| task.id().is_some()));
+
+    // a journal in canonical order comes out the same
+    let before = fs::read_to_string(journal.path()).unwrap();
+    run(&journal, &["renumber"]);
+    assert_eq!(fs::read_to_string(journal.path()).unwrap(), before);
+}
+
+#[test]
+fn undo_puts_back_the_trash_with_the_journal() {
+    let journal = TempJournal::new().unwrap();
+    run(&journal, &["add", "--task", "a"]);
+    run(&journal, &["add", "--task", "b"]);
+    run(&journal, &["remove", "1"]);
+    run(&journal, &["undo"]);
+    assert_eq!(names(&journal), ["a", "b"]);
+    // the task is back in the journal and no longer in the trash, so it can't be restored a second time
+    assert_eq!(run(&journal, &["trash", "list"]), "The trash is empty\n");
+    assert!(run_failing(&journal, &journal.path(), &["restore", "1"]).contains("no task with id 1 in the trash"));
+    assert_eq!(names(&journal), ["a", "b"]);
+
+    // undoing a restore puts the task back in the trash
+    run(&journal, &["remove", "1"]);
+    run(&journal, &["restore", "1"]);
+    assert_eq!(names(&journal), ["b", "a"]);
+    run(&journal, &["undo"]);
+    assert_eq!(names(&journal), ["b"]);
+    assert!(run(&journal, &["trash", "list"]).starts_with("1: a "));
+}
+
+#[cfg(unix)]
+#[test]
+fn journals_may_have_non_utf8_paths() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let journal = seeded();
+    let dir = journal.dir().join(OsStr::from_bytes(b"caf\xe9"));
+    fs::create_dir(&dir).unwrap();
+    let journal_file = dir.join(OsStr::from_bytes(b"t\xe2che.json"));
+    let output = command(&journal, &journal_file, &["add", "--task", "Order beans"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(fs::read_to_string(&journal_file).unwrap().contains("Order beans"));
+
+    let missing = dir.join("missing").join("todo.json");
+    let error: serde_json::Value = serde_json::from_str(&run_failing(&journal, &missing, &["--error-format", "json", "list"])).unwrap();
+    assert_eq!(error["code"], "not_found");
+    assert_eq!(error["path"], missing.to_string_lossy().as_ref());
+}
+
+#[test]
+fn read_only_refuses_changes_and_never_creates_the_journal() {
+    let journal = seeded();
+    let before = fs::read(journal.path()).unwrap();
+    assert_eq!(run_failing(&journal, &journal.path(), &["--read-only", "remove", "1"]),
+               "error in `remove`: `remove` would change the journal, which is read-only\n");
+    run(&journal, &["--read-only", "remove", "--where", "tag:work", "--dry-run"]);
+    assert_eq!(fs::read(journal.path()).unwrap(), before);
+
+    let missing = journal.dir().join("missing.json");
+    assert_eq!(command(&journal, &missing, &["--read-only", "list"]).output().unwrap().stdout, b"Empty to-do list\n");
+    assert!(!missing.exists());
+
+    fs::write(journal.dir().join("config.json"), r#"{"read_only": true}"#).unwrap();
+    let error = run_failing(&journal, &journal.path(), &["--error-format", "json", "add", "--task", "Sneak in"]);
+    let error: serde_json::Value = serde_json::from_str(&error).unwrap();
+    assert_eq!(error["code"], "permission_denied");
+}
+
+#[test]
+fn only_listing_commands_check_for_overdue_tasks() {
+    let journal = seeded();
+    fs::write(journal.dir().join("config.json"), r#"{"notify": true}"#).unwrap();
+    let stamp = notify::stamp_path(&journal.path());
+
+    let missing = journal.dir().join("missing.json");
+    assert_eq!(command(&journal, &missing, &["count"]).output().unwrap().stdout, b"0\n");
+    assert!(!missing.exists());
+    assert_eq!(command(&journal, &missing, &["--read-only", "list"]).output().unwrap().stdout, b"Empty to-do list\n");
+    assert!(!missing.exists() && !notify::stamp_path(&missing).exists());
+
+    run(&journal, &["add", "--task", "Renew the passport"]);
+    run(&journal, &["count"]);
+    assert!(!stamp.exists());
+    run(&journal, &["--read-only", "list"]);
+    assert!(!stamp.exists());
+    run(&journal, &["list"]);
+    assert!(stamp.exists());
+}
+
+#[test]
+fn tasks_are_owned_by_the_current_user_and_listed_by_assignee() {
+    let journal = seeded();
+    fs::write(journal.dir().join("config.json"), r#"{"user": "alice"}"#).unwrap();
+    run(&journal, &["add", "--task", "Fix the printer", "--assign", "bob"]);
+    run(&journal, &["add", "--task", "Order toner", "--assign", "alice"]);
+
+    assert!(run(&journal, &["list", "--assignee", "bob"]).starts_with("Task: Fix the printer"));
+    assert!(run(&journal, &["list", "--assignee"]).starts_with("Task: Order toner"));
+    assert_eq!(run(&journal, &["list", "--assignee", "carol"]), "");
+    assert!(run(&journal, &["show", "6"]).contains("  Owner:       alice\n  Assignee:    bob\n"));
+}
+
+#[test]
+fn list_sorts_by_configured_urgency() {
+    let journal = seeded();
+    fs::write(journal.dir().join("config.json"), r#"{"urgency": {"tag": {"work": 4.0}}}"#).unwrap();
+    let listed = run(&journal, &["list", "--sort", "urgency", "--show-urgency"]);
+    let names = listed.lines()
+                      .map(|line| line.split("Created at").next().unwrap().trim_end())
+                      .collect::<Vec<_>>();
+    // the seeded tasks are more than a year old, so they all have the full age part
+    assert_eq!(names, ["  6.80  Task: Write the quarterly report",
+                       "  2.80  Task: Buy milk",
+                       "  2.00  Task: Book the dentist",
+                       "  6.80  Task: Review pull requests",
+                       "  2.90  Task: File the taxes"]);
+}
+
+#[test]
+fn list_sorts_by_due_date_and_priority() {
+    let journal = seeded();
+    run(&journal, &["add", "--task", "Renew the passport", "--due", "2024-07-01", "--priority", "low"]);
+    run(&journal, &["add", "--task", "Pay the rent", "--due", "2024-06-01", "--priority", "high"]);
+    run(&journal, &["add", "--task", "Water the plants", "--priority", "medium"]);
+    let names = |sort: &str| run(&journal, &["list", "--state", "active", "--sort", sort]).lines()
+                                                                                        .map(|line| line.split("Created at").next().unwrap().trim_end().to_string())
+                                                                                        .collect::<Vec<_>>();
+    assert_eq!(names("due"), ["Task: Pay the rent", "Task: Renew the passport", "Task: Buy milk",
+                              "Task: Write the quarterly report", "Task: Book the dentist", "Task: Water the plants"]);
+    assert_eq!(names("priority"), ["Task: Pay the rent", "Task: Water the plants", "Task: Renew the passport",
+                                   "Task: Buy milk", "Task: Write the quarterly report", "Task: Book the dentist"]);
+}
+
+#[test]
+fn waiting_tasks_only_show_up_in_the_waiting_list() {
+    let journal = seeded();
+    run(&journal, &["wait", "1", "--until", "2w", "--for", "Bob's reply"]);
+    assert!(!run(&journal, &["list"]).contains("Buy milk"));
+    let waiting = run(&journal, &["list", "--waiting"]);
+    assert!(waiting.starts_with("Task: Buy milk"), "{}", waiting);
+    assert!(waiting.trim_end().ends_with("(Bob's reply)"), "{}", waiting);
+    assert_eq!(waiting.lines().count(), 1);
+    assert_eq!(run(&journal, &["count"]), "2\n");
+}
+
+#[test]
+fn delegated_tasks_are_listed_by_person() {
+    let journal = seeded();
+    run(&journal, &["delegate", "2", "carol"]);
+    run(&journal, &["delegate", "4", "dave"]);
+    assert!(run(&journal, &["list", "--delegated", "carol"]).starts_with("Task: Write the quarterly report"));
+    assert_eq!(run(&journal, &["list", "--delegated"]).lines().count(), 2);
+    assert_eq!(run(&journal, &["list", "--delegated", "erin"]), "");
+    assert!(run(&journal, &["show", "4"]).contains("  Delegated:   dave\n"));
+}
+
+#[test]
+fn commands_on_tasks_take_several_indices() {
+    let journal = seeded();
+    run(&journal, &["wait", "1-2", "--until", "2w"]);
+    assert_eq!(run(&journal, &["list", "--waiting"]).lines().count(), 2);
+    run(&journal, &["estimate", "2,4", "1h"]);
+    run(&journal, &["annotate", "2,4", "ask Bob"]);
+    let shown = run(&journal, &["show", "2,4"]);
+    assert!(shown.starts_with("Task 2: Write the quarterly report\n"), "{}", shown);
+    assert!(shown.contains("\n\nTask 4: Book the dentist\n"), "{}", shown);
+    assert_eq!(shown.matches("  Estimate:    1h\n").count(), 2);
+    assert_eq!(shown.matches("ask Bob").count(), 2);
+
+    // a single index out of range leaves every task alone
+    assert!(run_failing(&journal, &journal.path(), &["delegate", "4,9", "dave"]).contains("Invalid Task ID 9"));
+    assert!(!run(&journal, &["show", "4"]).contains("Delegated"));
+    run(&journal, &["goal", "Get healthy", "--task", "1,4"]);
+    assert!(run(&journal, &["goals"]).starts_with("Get healthy                    0/2 tasks done"));
+}
+
+#[test]
+fn goals_show_the_progress_of_their_tasks() {
+    let journal = seeded();
+    assert_eq!(run(&journal, &["goals"]), "No goals\n");
+    run(&journal, &["goal", "Ship the release", "--by", "2w", "--task", "2", "--task", "5"]);
+    run(&journal, &["add", "--task", "Write the changelog", "--goal", "Ship the release"]);
+    run(&journal, &["goal", "Tidy up"]);
+    assert_eq!(run(&journal, &["goals"]),
+"Ship the release               1/3 tasks done, 13 days left
+Tidy up                        0/0 tasks done
+");
+    assert!(run_failing(&journal, &journal.path(), &["goal", "Tidy up", "--task", "9"]).contains("Invalid Task ID"));
+    assert!(journal.dir().join("todo.goals.json").exists());
+}
+
+#[test]
+fn stale_tasks_are_listed_and_counted() {
+    let journal = seeded();
+    run(&journal, &["annotate", "1", "still need milk"]);
+    let stale = run(&journal, &["list", "--stale", "30d"]);
+    assert_eq!(stale.lines().map(|line| line.split_whitespace().nth(1).unwrap()).collect::<Vec<_>>(), ["Write", "Book"]);
+
+    let stats = run(&journal, &["stats"]);
+    let lines = stats.lines().collect::<Vec<_>>();
+    assert_eq!(lines[..4], ["3 active, 0 waiting, 2 complete", "2 stale, untouched for 30 days or more", "", "Oldest open tasks"]);
+    assert!(lines[4].starts_with("     2. Write the quarterly report (untouched for "));
+    assert_eq!(lines[6], "     1. Buy milk (untouched for less than a day)");
+}
+
+#[test]
+fn journal_piped_through_stdin_comes_out_changed_on_stdout() {
+    let journal = seeded();
+    let mut child = command(&journal, Path::new("-"), &["done", "--tag", "groceries", "--yes"]).stdin(Stdio::piped())
+                                                                     .stdout(Stdio::piped())
+                                                                     .stderr(Stdio::piped())
+                                                                     .spawn()
+                                                                     .unwrap();
+    child.stdin.take().unwrap().write_all(&fs::read(journal.path()).unwrap()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let tasks = serde_json::from_slice::<Vec<serde_json::Value>>(&output.stdout).unwrap();
+    assert_eq!(tasks.len(), 5);
+    assert_eq!(tasks[0]["state"]["type"], "Complete");
+    assert!(String::from_utf8(output.stderr).unwrap().ends_with("1 task completed\n"));
+    // the journal file itself is left alone
+    assert!(run(&journal, &["show", "1"]).contains("active"));
+}
+
+#[test]
+fn json_export_is_a_journal_of_the_matching_tasks() {
+    let journal = seeded();
+    let out = journal.dir().join("work.json");
+    run(&journal, &["export", "--where", "tag:work", "--format", "json", out.to_str().unwrap()]);
+
+    let list = command(&journal, &out, &["list"]).output().unwrap();
+    assert_eq!(String::from_utf8(list.stdout).unwrap(),
+"Task: Write the quarterly report                         Created at: 14/11/2023 22:13
+Task: Review pull requests                               Created at: 14/11/2023 22:13 Done at: 14/11/2023 22:13
+");
+}
+
+#[test]
+fn toml_journals_are_kept_in_toml() {
+    let journal = seeded();
+    let toml_journal = journal.dir().join("todo.toml");
+    let append = command(&journal, &toml_journal, &["append", journal.path().to_str().unwrap()]).output().unwrap();
+    assert!(append.status.success(), "{}", String::from_utf8_lossy(&append.stderr));
+    command(&journal, &toml_journal, &["done", "1"]).output().unwrap();
+
+    let content = fs::read_to_string(&toml_journal).unwrap();
+    assert!(content.starts_with("[[task]]\n") && content.contains("\nname = \"Buy milk\"\n"), "{}", content);
+    let list = command(&journal, &toml_journal, &["list", "--state", "active"]).output().unwrap();
+    assert_eq!(String::from_utf8(list.stdout).unwrap().lines().count(), 2);
+}
+
+#[test]
+fn csv_and_markdown_exports_are_tables() {
+    let journal = seeded();
+    let csv = journal.dir().join("tasks.csv");
+    let markdown = journal.dir().join("tasks.md");
+    run(&journal, &["export", "--where", "tag:work", "--format", "csv", csv.to_str().unwrap()]);
+    run(&journal, &["export", "--where", "tag:work", "--format", "markdown", markdown.to_str().unwrap()]);
+
+    assert_eq!(fs::read_to_string(&csv).unwrap(),
+"index,name,state,tags,created_at,due,completed_at\r
+1,Write the quarterly report,active,work,2023-11-14 22:13,,\r
+2,Review pull requests,complete,work,2023-11-14 22:13,,2023-11-14 22:13\r
+");
+    assert_eq!(fs::read_to_string(&markdown).unwrap().lines().nth(2).unwrap(), "| 1 | Write the quarterly report | active | work | 2023-11-14 22:13 |  |");
+}
+
+#[cfg(unix)]
+#[test]
+fn commands_go_through_a_running_daemon() {
+    let journal = seeded();
+    // killed when the test ends, passing or not, since a daemon left running would keep the test output open
+    struct Daemon(std::process::Child);
+    impl Drop for Daemon {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+    let daemon = Daemon(command(&journal, &journal.path(), &["daemon"]).stdout(Stdio::null()).stderr(Stdio::null()).spawn().unwrap());
+    let socket = rusty_journal_clap::daemon::socket_path(&journal.path());
+    while std::os::unix::net::UnixStream::connect(&socket).is_err() {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    run(&journal, &["add", "--task", "Water the plants", "--tag", "home"]);
+    run(&journal, &["done", "1"]);
+    assert_eq!(run(&journal, &["count"]), "3\n");
+    drop(daemon);
+
+    // the daemon wrote the journal, which reads the same without it
+    assert_eq!(run(&journal, &["count"]), "3\n");
+    assert!(run(&journal, &["list", "--tag", "home"]).contains("Water the plants"));
+}
+
+#[test]
+fn search_ranks_the_closest_names_first() {
+    let journal = seeded();
+    assert_eq!(run(&journal, &["search", "re"]),
+"5: Task: Review pull requests                               Created at: 14/11/2023 22:13 Done at: 14/11/2023 22:13
+2: Task: Write the quarterly report                         Created at: 14/11/2023 22:13
+");
+}
+
+#[test]
+fn search_combines_name_tag_and_date_filters() {
+    let journal = seeded();
+    let indices = |args: &[&str]| run(&journal, args).lines()
+                                                     .map(|line| line.split(':').next().unwrap().to_string())
+                                                     .collect::<Vec<_>>();
+    assert_eq!(indices(&["search", "--exact", "THE"]), ["2", "3", "4"]);
+    assert_eq!(indices(&["search", "--regex", "^(?i)b"]), ["1", "4"]);
+    assert_eq!(indices(&["search", "--tag", "home", "--tag", "admin"]), ["3"]);
+    assert_eq!(indices(&["search", "--tag", "groceries", "--tag", "admin", "--any-tag"]), ["1", "3"]);
+    assert_eq!(indices(&["search", "re", "--tag", "work", "--created-before", "2023-11-16"]), ["5", "2"]);
+    assert!(indices(&["search", "--created-after", "2023-11-16"]).is_empty());
+    assert_eq!(run(&journal, &["list", "--where", "regex:milk$ created-before:2023-11-16"]),
+               "Task: Buy milk                                           Created at: 14/11/2023 22:13\n");
+}
+
+#[test]
+fn list_and_search_print_json_and_porcelain() {
+    let journal = seeded();
+    let listed: serde_json::Value = serde_json::from_str(&run(&journal, &["--output", "json", "list", "--tag", "work"])).unwrap();
+    assert_eq!(listed, serde_json::json!([
+        {"index": 2, "id": 2, "name": "Write the quarterly report", "state": "active", "tags": ["work"], "priority": null,
+         "created_at": "2023-11-14T22:13:20Z", "due": null, "completed_at": null, "waiting_until": null, "parent": null, "repeat": null},
+        {"index": 5, "id": 5, "name": "Review pull requests", "state": "complete", "tags": ["work"], "priority": null,
+         "created_at": "2023-11-14T22:13:20Z", "due": null, "completed_at": "2023-11-14T22:13:20Z", "waiting_until": null, "parent": null, "repeat": null},
+    ]));
+
+    assert_eq!(run(&journal, &["--output", "porcelain", "search", "--tag", "admin"]),
+               "3\t3\tcomplete\t\t2023-11-14T22:13:20Z\t\t2023-11-14T22:13:20Z\thome,admin\tFile the taxes\n");
+    let empty = TempJournal::new().unwrap();
+    assert_eq!(run(&empty, &["--output", "json", "list"]), "[]\n");
+}
+
+#[test]
+fn related_tasks_refer_to_each_other() {
+    let journal = seeded();
+    run(&journal, &["relate", "2", "--to", "5"]);
+    assert!(run(&journal, &["show", "2"]).contains("Related:     5\n"));
+    assert_eq!(run(&journal, &["list", "--related-to", "2"]),
+"Task: Review pull requests                               Created at: 14/11/2023 22:13 Done at: 14/11/2023 22:13
+");
+}
+
+#[test]
+fn redacted_list_hides_the_task_names() {
+    let journal = seeded();
+    let listed = run(&journal, &["list", "--tag", "work", "--redact"]);
+    assert!(!listed.contains("quarterly report"));
+    assert!(listed.starts_with(&format!("Task: {} [work]", redact::placeholder("Write the quarterly report"))));
+    assert!(listed.contains("Created at: 14/11/2023 22:13"));
+}
+
+#[test]
+fn info_gives_an_overview_of_the_journal() {
+    let journal = seeded();
+    let info = run(&journal, &["info"]);
+    let lines = info.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], format!("Journal:     {}", journal.path().canonicalize().unwrap().display()));
+    assert_eq!(lines[1..3], ["Format:      json", "Schema:      urn:rusty-journal-clap:journal:1"]);
+    assert!(lines[3].starts_with("Size:        ") && lines[3].ends_with(" bytes"));
+    assert_eq!(lines[4..], ["Tasks:       5 (3 active, 0 waiting, 2 complete)",
+                            "Oldest:      14/11/2023 22:13",
+                            "Newest:      14/11/2023 22:13",
+                            "Lock:        none"]);
+}
+
+#[test]
+fn subtasks_list_under_their_parent_and_follow_it() {
+    let journal = seeded();
+    run(&journal, &["add", "--task", "Plan the trip", "--tag", "trip"]);
+    run(&journal, &["add", "--task", "Pack", "--tag", "trip", "--parent", "6"]);
+    run(&journal, &["add", "--task", "Book the flights", "--tag", "trip", "--parent", "6"]);
+    run(&journal, &["add", "--task", "Find the passports", "--tag", "trip", "--parent", "7"]);
+    assert!(run_failing(&journal, &journal.path(), &["add", "--task", "Rent a car", "--parent", "10"]).contains("Invalid Task ID 10"));
+
+    // the creation times are the clock's, so only the names are compared
+    let tree = |args: &[&str]| run(&journal, args).lines()
+                                                 .map(|line| line.split("Created at").next().unwrap().trim_end().to_string())
+                                                 .collect::<Vec<_>>();
+    assert_eq!(tree(&["list", "--tag", "trip", "--tree"]),
+               ["Task: Plan the trip", "  Task: Pack", "    Task: Find the passports", "  Task: Book the flights"]);
+    assert!(run(&journal, &["show", "9"]).contains("Parent:      7\n"));
+
+    run(&journal, &["done", "7"]);
+    let completed = tree(&["list", "--tag", "trip", "--state", "complete"]);
+    assert_eq!(completed, ["Task: Pack", "Task: Find the passports"]);
+
+    run(&journal, &["remove", "6"]);
+    assert_eq!(run(&journal, &["list", "--tag", "trip"]), "");
+    assert_eq!(journal.tasks().unwrap().len(), 5);
+}
+
+#[test]
+fn journal_comes_from_the_flag_the_environment_or_the_config() {
+    let journal = seeded();
+    let other = journal.dir().join("other.json");
+    fs::copy(journal.path(), &other).unwrap();
+    run(&journal, &["add", "--task", "Only in todo.json"]);
+    let count = |dir: &Path, env: Option<&Path>| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_rusty-journal-clap"));
+        command.arg("count")
+               .current_dir(dir)
+               .env("RUSTY_JOURNAL_CONFIG", journal.dir().join("config.json"))
+               .env_remove("RUSTY_JOURNAL_FILE");
+        if let Some(env) = env {
+            command.env("RUSTY_JOURNAL_FILE", env);
+        }
+        let output = command.output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(journal.path().file_name().unwrap(), "todo.json");
+    assert_eq!(count(journal.dir(), None), "4\n");
+    assert_eq!(count(journal.dir(), Some(&other)), "3\n");
+    fs::write(journal.dir().join("config.json"), r#"{"journal": "other.json"}"#).unwrap();
+    assert_eq!(count(journal.dir(), None), "3\n");
+    assert_eq!(count(journal.dir(), Some(&journal.path())), "4\n");
+
+    // a relative journal in the config is next to the config, wherever the command runs from
+    let elsewhere = journal.dir().join("elsewhere");
+    fs::create_dir(&elsewhere).unwrap();
+    fs::copy(journal.path(), elsewhere.join("other.json")).unwrap();
+    assert_eq!(count(&elsewhere, None), "3\n");
+}
+
+#[test]
+fn completion_scripts_cover_the_subcommands_and_their_options() {
+    let journal = seeded();
+    let bash = run(&journal, &["completions", "bash"]);
+    assert!(bash.contains("complete -o default -F _rusty_journal_clap rusty-journal-clap"));
+    assert!(bash.contains("'/trash/list') path=\"/trash/list\" ;;"));
+    assert!(bash.contains("--state) COMPREPLY=($(compgen -W 'active complete all' -- \"$cur\")); return ;;"));
+
+    let zsh = run(&journal, &["completions", "zsh"]);
+    assert!(zsh.starts_with("#compdef rusty-journal-clap\n"));
+    assert!(zsh.contains("'--journal_file=[The journal"));
+    assert!(zsh.contains("                add) _rusty_journal_clap__add ;;"));
+
+    let fish = run(&journal, &["completions", "fish"]);
+    assert!(fish.contains("complete -c rusty-journal-clap -n '__fish_use_subcommand' -f -a 'tick'"));
+    assert!(fish.contains("complete -c rusty-journal-clap -n '__fish_use_subcommand' -f -a 'refresh'"));
+    assert!(fish.contains("complete -c rusty-journal-clap -n '__fish_seen_subcommand_from add' -l from-file -r -F"));
+
+    assert!(run_failing(&journal, &journal.path(), &["completions", "tcsh"]).contains("unknown shell 'tcsh', expected bash, zsh or fish"));
+}
@@ -0,0 +1,735 @@
+use std::fs;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde_json::Value;
+use rusty_journal_clap::clock::{self, FixedClock};
+use rusty_journal_clap::compress::Compression;
+use rusty_journal_clap::export::{self, Format, GroupBy};
+use rusty_journal_clap::filter::TaskFilter;
+use rusty_journal_clap::ics;
+use rusty_journal_clap::journal::Journal;
+use rusty_journal_clap::normalize::{Normalizer, Rules};
+use rusty_journal_clap::notify::{self, Notifier};
+use rusty_journal_clap::output;
+use rusty_journal_clap::plan;
+use rusty_journal_clap::recur::Recurrence;
+use rusty_journal_clap::reminders;
+use rusty_journal_clap::rpc::RpcServer;
+use rusty_journal_clap::score;
+use rusty_journal_clap::summary::{self, Period};
+use rusty_journal_clap::task::{Priority, Task, TaskEdit, TaskOptions};
+use rusty_journal_clap::test_util::TempJournal;
+use rusty_journal_clap::trash;
+use rusty_journal_clap::undo;
+
+fn names(journal: &TempJournal) -> Vec<String> {
+    journal.tasks()
+           .unwrap()
+           .iter()
+           .map(|task| task.name().to_string())
+           .collect()
+}
+
+#[test]
+fn add_assigns_increasing_ids() {
+    let journal = TempJournal::new().unwrap();
+    Task::add(journal.path(), "first".to_string(), None).unwrap();
+    Task::add(journal.path(), "second".to_string(), Some(vec!["work".to_string()])).unwrap();
+
+    let tasks = journal.tasks().unwrap();
+    assert_eq!(tasks.iter().map(|task| task.id()).collect::<Vec<_>>(), vec![Some(1), Some(2)]);
+    assert_eq!(tasks[1].tags(), ["work"]);
+}
+
+#[test]
+fn seeded_journal_holds_the_fixture() {
+    let journal = TempJournal::seeded().unwrap();
+    let tasks = journal.tasks().unwrap();
+    assert_eq!(tasks.len(), TempJournal::FIXTURE.len());
+    for (task, (name, tags, complete)) in tasks.iter().zip(TempJournal::FIXTURE) {
+        assert_eq!(task.name(), *name);
+        assert_eq!(task.tags(), *tags);
+        assert_eq!(task.is_complete(), *complete);
+    }
+}
+
+#[test]
+fn remove_many_keeps_the_other_tasks_in_order() {
+    let journal = TempJournal::seeded().unwrap();
+    Task::remove_many(journal.path(), &[1, 3]).unwrap();
+    assert_eq!(names(&journal), ["Write the quarterly report", "Book the dentist", "Review pull requests"]);
+}
+
+#[test]
+fn invalid_index_leaves_the_journal_untouched() {
+    let journal = TempJournal::seeded().unwrap();
+    assert!(Task::remove_many(journal.path(), &[1, 99]).is_err());
+    assert_eq!(journal.tasks().unwrap().len(), TempJournal::FIXTURE.len());
+}
+
+#[test]
+fn bump_and_sink_keep_relative_order() {
+    let journal = TempJournal::seeded().unwrap();
+    Task::bump(journal.path(), &[4, 5]).unwrap();
+    assert_eq!(names(&journal)[..2], ["Book the dentist", "Review pull requests"]);
+    Task::sink(journal.path(), &[1, 2]).unwrap();
+    assert_eq!(names(&journal)[3..], ["Book the dentist", "Review pull requests"]);
+}
+
+#[test]
+fn complete_many_marks_only_the_selected_tasks() {
+    let journal = TempJournal::seeded().unwrap();
+    Task::complete_many(journal.path(), &[1, 2]).unwrap();
+    let complete = journal.tasks()
+                          .unwrap()
+                          .iter()
+                          .map(|task| task.is_complete())
+                          .collect::<Vec<_>>();
+    assert_eq!(complete, [true, true, true, false, true]);
+}
+
+#[test]
+fn edit_where_tags_the_matching_tasks() {
+    let journal = TempJournal::seeded().unwrap();
+    let filter = TaskFilter::parse("tag:work state:active").unwrap();
+    Task::edit_where(journal.path(), &filter, &["urgent".to_string()], &[], false).unwrap();
+    let tasks = journal.tasks().unwrap();
+    assert_eq!(tasks[1].tags(), ["work", "urgent"]);
+    assert_eq!(tasks[4].tags(), ["work"]);
+}
+
+#[test]
+fn edit_changes_a_task_in_place() {
+    let journal = TempJournal::seeded().unwrap();
+    let created_at = journal.tasks().unwrap()[0].created_at();
+    let due = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let edit = TaskEdit { name: Some("Buy oat milk".to_string()), add_tags: vec!["errands".to_string()], due: Some(Some(due)), ..TaskEdit::default() };
+    Task::edit(journal.path(), &[1], &edit).unwrap();
+    let tasks = journal.tasks().unwrap();
+    assert_eq!((tasks[0].name(), tasks[0].tags(), tasks[0].due()), ("Buy oat milk", &["groceries".to_string(), "errands".to_string()][..], Some(due)));
+    assert_eq!(tasks[0].created_at(), created_at);
+
+    let clear_due = TaskEdit { due: Some(None), ..TaskEdit::default() };
+    Task::edit(journal.path(), &[1], &clear_due).unwrap();
+    assert_eq!(journal.tasks().unwrap()[0].due(), None);
+    assert!(Task::edit(journal.path(), &[1], &TaskEdit { name: Some(" ".to_string()), ..TaskEdit::default() }).is_err());
+    assert!(Task::edit(journal.path(), &[9], &clear_due).is_err());
+
+    let urgent = TaskEdit { add_tags: vec!["urgent".to_string()], ..TaskEdit::default() };
+    Task::edit(journal.path(), &[2, 4], &urgent).unwrap();
+    let tasks = journal.tasks().unwrap();
+    assert_eq!((tasks[1].tags(), tasks[3].tags()), (&["work".to_string(), "urgent".to_string()][..], &["urgent".to_string()][..]));
+    let rename = TaskEdit { name: Some("Call mum".to_string()), ..TaskEdit::default() };
+    assert!(Task::edit(journal.path(), &[2, 4], &rename).is_err());
+}
+
+#[test]
+fn append_gives_the_appended_tasks_fresh_ids() {
+    let journal = TempJournal::seeded().unwrap();
+    let other = TempJournal::seeded().unwrap();
+    Task::append(journal.path(), other.path()).unwrap();
+    let ids = journal.tasks()
+                     .unwrap()
+                     .iter()
+                     .filter_map(|task| task.id())
+                     .collect::<Vec<_>>();
+    assert_eq!(ids, (1..=10).collect::<Vec<_>>());
+}
+
+#[test]
+fn timestamps_come_from_the_installed_clock() {
+    let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let fixed = FixedClock::new(start);
+    let _guard = clock::install(fixed.clone());
+
+    let journal = TempJournal::new().unwrap();
+    Task::add(journal.path(), "play".to_string(), None).unwrap();
+    fixed.advance(Duration::hours(2));
+    Task::complete_many(journal.path(), &[1]).unwrap();
+
+    let tasks = journal.tasks().unwrap();
+    assert_eq!(tasks[0].created_at(), start);
+    assert_eq!(tasks[0].completed_at(), Some(start + Duration::hours(2)));
+}
+
+/// Collects the notifications instead of showing them
+#[derive(Default)]
+struct StubNotifier(Vec<(String, String)>);
+
+impl Notifier for StubNotifier {
+    fn notify(&mut self, summary: &str, body: &str) -> std::io::Result<()> {
+        self.0.push((summary.to_string(), body.to_string()));
+        Ok(())
+    }
+}
+
+#[test]
+fn notifications_tell_of_tasks_falling_due_and_high_priority_completions() {
+    let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let fixed = FixedClock::new(start);
+    let _guard = clock::install(fixed.clone());
+    let journal = TempJournal::new().unwrap();
+    let due = |hours| TaskOptions { due: Some(start + Duration::hours(hours)), ..TaskOptions::default() };
+    Task::add_with(journal.path(), "Pay the rent".to_string(), None, &due(-1)).unwrap();
+    Task::add_with(journal.path(), "Renew the passport".to_string(), None, &due(1)).unwrap();
+    Task::add_with(journal.path(), "Water the plants".to_string(), None, &due(3)).unwrap();
+
+    // the first check only takes note of the time, the rent was overdue already
+    let mut notifier = StubNotifier::default();
+    notify::overdue(&mut notifier, &journal.path(), clock::now()).unwrap();
+    assert!(notifier.0.is_empty());
+
+    // the passport fell due since, the plants aren't due yet
+    fixed.advance(Duration::hours(2));
+    notify::overdue(&mut notifier, &journal.path(), clock::now()).unwrap();
+    assert_eq!(notifier.0, [("Task overdue".to_string(), "Renew the passport".to_string())]);
+    notify::overdue(&mut notifier, &journal.path(), clock::now()).unwrap();
+    assert_eq!(notifier.0.len(), 1);
+
+    let urgent = TaskOptions { priority: Some(Priority::High), ..TaskOptions::default() };
+    Task::add_with(journal.path(), "Call the plumber".to_string(), None, &urgent).unwrap();
+    let mut notifier = StubNotifier::default();
+    notify::completing(&mut notifier, &journal.path(), || Task::complete_many(journal.path(), &[1, 4])).unwrap();
+    assert_eq!(notifier.0, [("High priority task completed".to_string(), "Call the plumber".to_string())]);
+}
+
+#[test]
+fn count_only_counts_active_tasks() {
+    let journal = TempJournal::seeded().unwrap();
+    assert_eq!(Task::count(journal.path(), None).unwrap(), 3);
+    assert_eq!(Task::count(journal.path(), Some("work")).unwrap(), 1);
+    assert_eq!(Task::count(journal.dir().join("missing.json"), None).unwrap(), 0);
+    assert!(!journal.dir().join("missing.json").exists());
+}
+
+#[test]
+fn plain_summary_snapshot() {
+    let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let fixed = FixedClock::new(start);
+    let _guard = clock::install(fixed.clone());
+    let journal = TempJournal::seeded().unwrap();
+
+    fixed.advance(Duration::days(3));
+    Task::add(journal.path(), "Water the plants".to_string(), Some(vec!["home".to_string()])).unwrap();
+    Task::complete_many(journal.path(), &[1]).unwrap();
+
+    output::set_deterministic(true);
+    assert_eq!(summary::plain(&journal.tasks().unwrap(), Period::Daily, clock::now()),
+"Daily report, 2023-11-16 22:13 to 2023-11-17 22:13
+
+Added (1)
+     6. Water the plants [home]
+
+Completed (1)
+     1. Buy milk [groceries]
+
+Overdue (0)
+
+Pending (3)
+     2. Write the quarterly report [work]
+     4. Book the dentist
+     6. Water the plants [home]
+");
+}
+
+#[test]
+fn html_export_groups_and_escapes_tasks() {
+    let journal = TempJournal::with_tasks(vec![("Fix <script> & quotes".to_string(), vec!["work".to_string(), "home".to_string()], false),
+                                               ("Book the dentist".to_string(), Vec::new(), true)]).unwrap();
+    let out = journal.dir().join("report.html");
+    Task::export(journal.path(), Format::Html, GroupBy::Tag, &TaskFilter::default(), out.clone()).unwrap();
+
+    let page = std::fs::read_to_string(&out).unwrap();
+    assert!(page.contains("Fix &lt;script&gt; &amp; quotes"));
+    assert!(!page.contains("<script> &"));
+    assert_eq!(page.matches("Fix &lt;script&gt;").count(), 2);
+    let sections = ["<h2>home (1)</h2>", "<h2>work (1)</h2>", "<h2>Untagged (1)</h2>"];
+    let positions = sections.iter().map(|section| page.find(section).unwrap()).collect::<Vec<_>>();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let by_state = export::html(&journal.tasks().unwrap(), GroupBy::State);
+    assert!(by_state.contains("<h2>Active (1)</h2>"));
+    assert!(by_state.contains("<tr class=\"complete\"><td class=\"index\">2</td><td class=\"name\">Book the dentist</td>"));
+}
+
+const CALENDAR: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+UID:standup@example.com\r
+DTSTART:20231120T090000Z\r
+SUMMARY:Prepare the standup\\, notes\r
+CATEGORIES:work,meetings\r
+BEGIN:VALARM\r
+SUMMARY:Reminder\r
+END:VALARM\r
+END:VEVENT\r
+BEGIN:VTODO\r
+UID:taxes@example.com\r
+SUMMARY:File the tax\r
+  return\r
+DUE;VALUE=DATE:20240415\r
+STATUS:COMPLETED\r
+COMPLETED:20240410T120000Z\r
+END:VTODO\r
+END:VCALENDAR\r
+";
+
+#[test]
+fn ics_import_reads_due_dates_and_skips_known_uids() {
+    output::set_deterministic(true);
+    let normalizer = Normalizer::new(&Rules::default()).unwrap();
+    let tasks = ics::from_ics(CALENDAR, &normalizer).unwrap();
+
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].name(), "Prepare the standup, notes");
+    assert_eq!(tasks[0].tags(), ["work", "meetings"]);
+    assert_eq!(tasks[0].due(), DateTime::from_timestamp(1_700_470_800, 0));
+    assert!(!tasks[0].is_complete());
+    assert_eq!(tasks[1].name(), "File the tax return");
+    assert_eq!(tasks[1].due(), DateTime::from_timestamp(1_713_139_200, 0));
+    assert_eq!(tasks[1].completed_at(), DateTime::from_timestamp(1_712_750_400, 0));
+    assert_eq!(tasks[1].extra("uid"), Some("taxes@example.com"));
+
+    let journal = TempJournal::new().unwrap();
+    Task::import(journal.path(), tasks, false).unwrap();
+    Task::import(journal.path(), ics::from_ics(CALENDAR, &normalizer).unwrap(), false).unwrap();
+    assert_eq!(names(&journal), ["Prepare the standup, notes", "File the tax return"]);
+
+    assert!(ics::from_ics("BEGIN:VTODO\r\nSUMMARY:Cut short\r\n", &normalizer).is_err());
+    assert!(ics::from_ics("BEGIN:VTODO\r\nSUMMARY:Bad\r\nDUE:tomorrow\r\nEND:VTODO\r\n", &normalizer).is_err());
+}
+
+#[test]
+fn reminder_apps_import_lists_as_tags() {
+    let normalizer = Normalizer::new(&Rules::default()).unwrap();
+    let apple = r#"[{"title": "Buy milk", "list": "Groceries", "isCompleted": false, "externalId": "A1", "priority": 0},
+                    {"title": "File the taxes", "list": "Home", "isCompleted": true, "externalId": "A2", "completionDate": "2024-04-10T12:00:00Z", "priority": 5}]"#;
+    let tasks = reminders::from_apple_reminders(apple, &normalizer).unwrap();
+    assert_eq!(tasks[0].tags(), ["Groceries"]);
+    assert_eq!(tasks[0].priority(), None);
+    assert_eq!(tasks[1].priority(), Some(Priority::Medium));
+    assert_eq!(tasks[1].completed_at(), DateTime::from_timestamp(1_712_750_400, 0));
+
+    let journal = TempJournal::new().unwrap();
+    Task::import(journal.path(), tasks, false).unwrap();
+    Task::import(journal.path(), reminders::from_apple_reminders(apple, &normalizer).unwrap(), false).unwrap();
+    assert_eq!(names(&journal), ["Buy milk", "File the taxes"]);
+
+    let ms_todo = r#"[{"displayName": "Work", "tasks": [{"id": "T1", "title": "Review pull requests", "status": "notStarted",
+                       "importance": "high", "categories": ["Red category"],
+                       "dueDateTime": {"dateTime": "2024-04-15T00:00:00.0000000", "timeZone": "UTC"}}]}]"#;
+    let tasks = reminders::from_ms_todo(ms_todo, &normalizer).unwrap();
+    assert_eq!(tasks[0].tags(), ["Work", "Red category"]);
+    assert_eq!(tasks[0].priority(), Some(Priority::High));
+    assert_eq!(tasks[0].due(), DateTime::from_timestamp(1_713_139_200, 0));
+    assert!(!tasks[0].is_complete());
+
+    assert!(reminders::from_ms_todo(r#"[{"displayName": "Work"}]"#, &normalizer).is_err());
+}
+
+#[test]
+fn url_comes_from_the_name_or_the_link() {
+    let normalizer = Normalizer::new(&Rules::default()).unwrap();
+    let calendar = "BEGIN:VEVENT\r\nSUMMARY:Team sync\r\nURL:https://meet.example.com/sync\r\nEND:VEVENT\r\n";
+    let journal = TempJournal::with_tasks(vec![("Review https://example.com/pr/7 (and https://example.com/pr/8).".to_string(), Vec::new(), false),
+                                               ("No link".to_string(), Vec::new(), false)]).unwrap();
+    Task::import(journal.path(), ics::from_ics(calendar, &normalizer).unwrap(), false).unwrap();
+
+    let tasks = journal.tasks().unwrap();
+    assert_eq!(tasks[0].url(), Some("https://example.com/pr/7"));
+    assert_eq!(tasks[1].url(), None);
+    assert_eq!(tasks[2].url(), Some("https://meet.example.com/sync"));
+    assert!(Task::open(journal.path(), &[2]).is_err());
+    assert!(Task::open(journal.path(), &[4]).is_err());
+}
+
+#[test]
+fn score_puts_overdue_before_priority_before_age() {
+    let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let fixed = FixedClock::new(start);
+    let _guard = clock::install(fixed.clone());
+    let normalizer = Normalizer::new(&Rules::default()).unwrap();
+    let journal = TempJournal::new().unwrap();
+
+    Task::add(journal.path(), "Old chore".to_string(), None).unwrap();
+    fixed.advance(Duration::days(200));
+    Task::add_with(journal.path(), "Urgent fix".to_string(), None, &TaskOptions { priority: Some(Priority::High), ..TaskOptions::default() }).unwrap();
+    let calendar = "BEGIN:VTODO\r\nSUMMARY:Renew passport\r\nDUE:20240530T000000Z\r\nEND:VTODO\r\n";
+    Task::import(journal.path(), ics::from_ics(calendar, &normalizer).unwrap(), false).unwrap();
+
+    let tasks = journal.tasks().unwrap();
+    let scores = tasks.iter().map(|task| score::score(task, clock::now(), &score::Coefficients::default())).collect::<Vec<_>>();
+    assert!(scores[2].total() > scores[1].total());
+    assert!(scores[1].total() > scores[0].total());
+    assert_eq!(scores[2].components[0].reason, "overdue by 2 days");
+    assert_eq!(scores[1].to_string(), "Score 6.00\n  priority     6.00  (high)\n  age          0.00  (less than a day old)\n");
+}
+
+#[test]
+fn history_records_changes_and_notes() {
+    let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let fixed = FixedClock::new(start);
+    let _guard = clock::install(fixed.clone());
+    let journal = TempJournal::seeded().unwrap();
+
+    fixed.advance(Duration::hours(1));
+    Task::annotate(journal.path(), &[2], "asked for the sales figures".to_string()).unwrap();
+    fixed.advance(Duration::hours(1));
+    Task::edit_where(journal.path(), &TaskFilter::parse("tag:work").unwrap(), &["urgent".to_string()], &["work".to_string()], false).unwrap();
+    Task::complete_many(journal.path(), &[2]).unwrap();
+    // completing again isn't a change
+    Task::complete_many(journal.path(), &[2]).unwrap();
+    assert!(Task::annotate(journal.path(), &[9], "nowhere".to_string()).is_err());
+
+    output::set_deterministic(true);
+    let tasks = journal.tasks().unwrap();
+    assert_eq!(tasks[1].details(2),
+"Task 2: Write the quarterly report
+  Id:          2
+  State:       complete since 15/11/2023 00:13
+  Tags:        urgent
+  Created at:  14/11/2023 22:13
+  Updated at:  15/11/2023 00:13
+History
+  14/11/2023 23:13  note   asked for the sales figures
+  15/11/2023 00:13  edit   tagged urgent
+  15/11/2023 00:13  edit   untagged work
+  15/11/2023 00:13  state  completed
+");
+}
+
+#[test]
+fn plan_flags_overcommitted_days() {
+    let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let _guard = clock::install(FixedClock::new(start));
+    output::set_deterministic(true);
+    let journal = TempJournal::seeded().unwrap();
+
+    Task::estimate_at(journal.path(), &[1], Duration::minutes(30)).unwrap();
+    let calendar = "BEGIN:VTODO\r\nSUMMARY:Renew passport\r\nDUE:20231115T090000Z\r\nEND:VTODO\r\n\
+                    BEGIN:VTODO\r\nSUMMARY:Prepare the talk\r\nDUE:20231115T170000Z\r\nEND:VTODO\r\n\
+                    BEGIN:VTODO\r\nSUMMARY:Pay the fine\r\nDUE:20231101T000000Z\r\nEND:VTODO\r\n";
+    let normalizer = Normalizer::new(&Rules::default()).unwrap();
+    Task::import(journal.path(), ics::from_ics(calendar, &normalizer).unwrap(), false).unwrap();
+    Task::estimate_at(journal.path(), &[6], Duration::hours(2)).unwrap();
+    Task::estimate_at(journal.path(), &[7], Duration::minutes(270)).unwrap();
+    assert!(Task::estimate_at(journal.path(), &[9], Duration::hours(1)).is_err());
+
+    let tasks = journal.tasks().unwrap();
+    assert_eq!(tasks[0].estimate(), Some(Duration::minutes(30)));
+    assert_eq!(plan::plan(&tasks, 3, Duration::hours(6), clock::now()),
+"Plan for 3 days, 6h a day
+2023-11-14  0m / 6h
+     8. Pay the fine (no estimate)
+2023-11-15  6h30m / 6h  overcommitted by 30m
+     6. Renew passport (2h)
+     7. Prepare the talk (4h30m)
+2023-11-16  0m / 6h
+Unscheduled: 3 tasks, 30m
+");
+}
+
+#[test]
+fn waiting_tasks_are_hidden_until_they_wake_up() {
+    let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let fixed = FixedClock::new(start);
+    let _guard = clock::install(fixed.clone());
+    output::set_deterministic(true);
+    let journal = TempJournal::seeded().unwrap();
+
+    Task::wait(journal.path(), &[1], start + Duration::days(2), Some("the shop to open".to_string())).unwrap();
+    assert_eq!(Task::count(journal.path(), None).unwrap(), 2);
+    let listed = |journal: &TempJournal| {
+        let mut out = Vec::new();
+        Task::list_to(journal.path(), None, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert!(!listed(&journal).contains("Buy milk"));
+    let waiting = TaskFilter::parse("state:waiting").unwrap();
+    assert_eq!(journal.tasks().unwrap().iter().filter(|task| waiting.matches(task)).count(), 1);
+
+    fixed.advance(Duration::days(3));
+    assert_eq!(Task::count(journal.path(), None).unwrap(), 3);
+    assert!(listed(&journal).contains("Buy milk"));
+    let milk = &journal.tasks().unwrap()[0];
+    assert!(!milk.is_waiting());
+    assert_eq!(milk.details(1).lines().skip(5).collect::<Vec<_>>(),
+               ["  Updated at:  16/11/2023 22:13",
+                "History",
+                "  14/11/2023 22:13  state  waiting until 16/11/2023 22:13 for the shop to open",
+                "  16/11/2023 22:13  state  woke up"]);
+}
+
+#[test]
+fn compressed_journals_stay_compressed() {
+    let journal = TempJournal::new().unwrap();
+    let archive = journal.dir().join("archive.json.gz");
+    Task::add(archive.clone(), "first".to_string(), None).unwrap();
+    Task::add(archive.clone(), "second".to_string(), None).unwrap();
+    assert_eq!(Compression::from_magic(&fs::read(&archive).unwrap()), Some(Compression::Gzip));
+
+    // a compressed journal is recognized by its content as well, whatever its name
+    let renamed = journal.dir().join("archive.json");
+    fs::rename(&archive, &renamed).unwrap();
+    Task::complete(renamed.clone(), 1).unwrap();
+    assert_eq!(Compression::from_magic(&fs::read(&renamed).unwrap()), Some(Compression::Gzip));
+    assert_eq!(Task::count(renamed, None).unwrap(), 1);
+}
+
+#[test]
+fn removed_tasks_can_be_restored_from_the_trash() {
+    let _guard = clock::install(FixedClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap()));
+    let journal = TempJournal::seeded().unwrap();
+    let milk_id = journal.tasks().unwrap()[0].id().unwrap();
+    Task::remove_many(journal.path(), &[1, 3]).unwrap();
+    assert_eq!(trash::load(&journal.path(), clock::now()).unwrap().len(), 2);
+
+    Task::restore(journal.path(), milk_id).unwrap();
+    assert_eq!(names(&journal).last().unwrap(), "Buy milk");
+    assert_eq!(journal.tasks().unwrap().last().unwrap().id(), Some(milk_id));
+    assert!(Task::restore(journal.path(), milk_id).is_err());
+
+    // the trash is emptied of the tasks removed longer ago than the retention
+    let later = clock::now() + trash::retention() + Duration::seconds(1);
+    assert!(trash::load(&journal.path(), later).unwrap().is_empty());
+}
+
+#[test]
+fn concurrent_writers_keep_every_task() {
+    let journal = TempJournal::new().unwrap();
+    let writers = (0..8).map(|writer| {
+                            let path = journal.path();
+                            std::thread::spawn(move || {
+                                for i in 0..5 {
+                                    Task::add(path.clone(), format!("task {} of writer {}", i, writer), None).unwrap();
+                                }
+                            })
+                        })
+                        .collect::<Vec<_>>();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    let mut ids = journal.tasks().unwrap().iter().map(|task| task.id()).collect::<Vec<_>>();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 40);
+    // each write renames its temporary file over the journal, none is left behind
+    assert!(fs::read_dir(journal.dir()).unwrap().all(|entry| !entry.unwrap().file_name().to_string_lossy().ends_with(".tmp")));
+}
+
+#[test]
+fn journal_batches_changes_in_memory() {
+    let journal = TempJournal::seeded().unwrap();
+    let mut loaded = Journal::load(journal.path()).unwrap();
+    let index = loaded.add_task("Call the plumber".to_string(), Some(vec!["home".to_string()]));
+    loaded.complete_task(1).unwrap();
+    assert_eq!(loaded.remove_task(4).unwrap(), 1);
+    // nothing is written until the journal is saved
+    assert_eq!(names(&journal).len(), TempJournal::FIXTURE.len());
+    assert!(trash::load(&journal.path(), clock::now()).unwrap().is_empty());
+
+    loaded.save().unwrap();
+    assert_eq!(trash::load(&journal.path(), clock::now()).unwrap()[0].task.name(), "Book the dentist");
+    let tasks = journal.tasks().unwrap();
+    assert_eq!(tasks.len(), index - 1);
+    assert!(tasks[0].is_complete());
+    assert_eq!(tasks.last().unwrap().name(), "Call the plumber");
+    assert_eq!(tasks.last().unwrap().id(), Some(TempJournal::FIXTURE.len() as u64 + 1));
+
+    let copy = journal.dir().join("copy.toml");
+    loaded.save_as(copy.clone()).unwrap();
+    assert_eq!(loaded.path(), copy);
+    assert_eq!(Journal::load(copy).unwrap().iter().map(|task| task.name()).collect::<Vec<_>>(),
+               loaded.into_iter().map(|task| task.name().to_string()).collect::<Vec<_>>());
+}
+
+#[test]
+fn journal_changes_are_the_ones_of_the_commands() {
+    let _guard = clock::install(FixedClock::new(DateTime::from_timestamp(1_700_000_000, 0).unwrap()));
+    let weekly = TaskOptions { repeat: Some(Recurrence::Weeks(1)), ..TaskOptions::default() };
+    let subtask = TaskOptions { parent: Some(6), ..TaskOptions::default() };
+    let (with_commands, in_memory) = (TempJournal::seeded().unwrap(), TempJournal::seeded().unwrap());
+    for journal in [&with_commands, &in_memory] {
+        Task::add_with(journal.path(), "Water the plants".to_string(), None, &weekly).unwrap();
+        Task::add_with(journal.path(), "Fill the can".to_string(), None, &subtask).unwrap();
+        Task::add_with(journal.path(), "Move out".to_string(), None, &TaskOptions::default()).unwrap();
+        Task::add_with(journal.path(), "Pack".to_string(), None, &TaskOptions { parent: Some(8), ..TaskOptions::default() }).unwrap();
+    }
+
+    Task::complete(with_commands.path(), 6).unwrap();
+    Task::remove(with_commands.path(), 8).unwrap();
+    let mut loaded = Journal::load(in_memory.path()).unwrap();
+    loaded.complete_task(6).unwrap();
+    assert_eq!(loaded.remove_task(8).unwrap(), 2);
+    loaded.save().unwrap();
+
+    let summary = |journal: &TempJournal| journal.tasks().unwrap()
+                                                 .iter()
+                                                 .map(|task| (task.name().to_string(), task.is_complete(), task.parent(), task.repeat()))
+                                                 .collect::<Vec<_>>();
+    assert_eq!(summary(&in_memory), summary(&with_commands));
+    // the subtask is completed along with its parent, and the next occurrence of the recurring task is added
+    assert_eq!(summary(&in_memory)[5..], [("Water the plants".to_string(), true, None, None),
+                                           ("Fill the can".to_string(), true, Some(6), None),
+                                           ("Water the plants".to_string(), false, None, Some(Recurrence::Weeks(1)))]);
+    let trashed = |journal: &TempJournal| trash::load(&journal.path(), clock::now()).unwrap()
+                                                                                    .into_iter()
+                                                                                    .map(|trashed| trashed.task.name().to_string())
+                                                                                    .collect::<Vec<_>>();
+    assert_eq!(trashed(&in_memory), ["Move out", "Pack"]);
+    assert_eq!(trashed(&in_memory), trashed(&with_commands));
+}
+
+#[test]
+fn undo_puts_back_the_journal_before_the_last_changes() {
+    let journal = TempJournal::seeded().unwrap();
+    Task::remove(journal.path(), 1).unwrap();
+    Task::complete(journal.path(), 1).unwrap();
+    Task::add(journal.path(), "Call the plumber".to_string(), None).unwrap();
+    // the seeding, the removal, the completion and the addition
+    assert_eq!(undo::steps(&journal.path()).unwrap().len(), 4);
+
+    Task::undo(journal.path(), 1).unwrap();
+    assert!(journal.tasks().unwrap()[0].is_complete());
+    Task::undo(journal.path(), 2).unwrap();
+    assert_eq!(names(&journal), TempJournal::FIXTURE.iter().map(|(name, _, _)| name.to_string()).collect::<Vec<_>>());
+
+    // undoing the change that created the journal leaves it empty, and then there is nothing left to undo
+    assert!(Task::undo(journal.path(), 2).is_err());
+    Task::undo(journal.path(), 1).unwrap();
+    assert!(journal.tasks().unwrap().is_empty());
+    assert!(Task::undo(journal.path(), 1).is_err());
+}
+
+#[test]
+fn completing_no_task_leaves_the_journal_and_undo_alone() {
+    let journal = TempJournal::seeded().unwrap();
+    Task::complete_where(journal.path(), &TaskFilter::tagged("errands"), false, true).unwrap();
+    // the completed tasks aren't completed again either
+    Task::complete_where(journal.path(), &TaskFilter::parse("state:complete").unwrap(), false, true).unwrap();
+    assert_eq!(undo::steps(&journal.path()).unwrap().len(), 1);
+
+    Task::complete_where(journal.path(), &TaskFilter::tagged("work"), false, true).unwrap();
+    assert_eq!(undo::steps(&journal.path()).unwrap().len(), 2);
+}
+
+#[test]
+fn removing_no_task_leaves_the_journal_and_undo_alone() {
+    let journal = TempJournal::seeded().unwrap();
+    Task::remove_where(journal.path(), &TaskFilter::tagged("errands"), false, true).unwrap();
+    assert_eq!(undo::steps(&journal.path()).unwrap().len(), 1);
+    assert!(trash::load(&journal.path(), clock::now()).unwrap().is_empty());
+
+    Task::remove_where(journal.path(), &TaskFilter::tagged("work"), false, true).unwrap();
+    assert_eq!(undo::steps(&journal.path()).unwrap().len(), 2);
+}
+
+#[test]
+fn rpc_requests_see_the_changes_of_other_processes() {
+    let journal = TempJournal::seeded().unwrap();
+    let mut server = RpcServer::new(journal.path()).unwrap();
+    let mut call = |request: &str| -> Value { serde_json::from_str(&server.handle(request).unwrap()).unwrap() };
+
+    // the command line adds a task and removes the first one while the session is open
+    Task::add(journal.path(), "Call the bank".to_string(), None).unwrap();
+    Task::remove(journal.path(), 1).unwrap();
+
+    let listed = call(r#"{"jsonrpc": "2.0", "id": 1, "method": "list"}"#);
+    assert_eq!(listed["result"][0]["name"], "Write the quarterly report");
+    assert_eq!(listed["result"][4]["name"], "Call the bank");
+
+    // the session's own change keeps those of the command line
+    let added = call(r#"{"jsonrpc": "2.0", "id": 2, "method": "add", "params": {"name": "Water the plants"}}"#);
+    assert_eq!(added["result"]["index"], 6);
+    assert_eq!(names(&journal), ["Write the quarterly report", "File the taxes", "Book the dentist", "Review pull requests",
+                                 "Call the bank", "Water the plants"]);
+
+    let completed = call(r#"{"jsonrpc": "2.0", "id": 3, "method": "complete", "params": {"index": 7}}"#);
+    assert_eq!(completed["error"]["code"], -32602);
+}
+
+#[test]
+fn a_resident_server_changes_the_journal_as_it_is_on_disk() {
+    let journal = TempJournal::seeded().unwrap();
+    let mut server = RpcServer::resident(journal.path()).unwrap();
+    server.handle(r#"{"jsonrpc": "2.0", "id": 1, "method": "list"}"#).unwrap();
+
+    // the command line completes a task behind the daemon's back
+    Task::complete(journal.path(), 1).unwrap();
+    server.handle(r#"{"jsonrpc": "2.0", "id": 2, "method": "remove", "params": {"index": 4}}"#).unwrap();
+
+    let tasks = journal.tasks().unwrap();
+    assert_eq!(tasks.len(), 4);
+    assert!(tasks[0].is_complete());
+    assert!(!tasks.iter().any(|task| task.name() == "Book the dentist"));
+}
+
+#[test]
+fn a_resident_server_keeps_its_own_changes_without_reading_them_back() {
+    let journal = TempJournal::seeded().unwrap();
+    let mut server = RpcServer::resident(journal.path()).unwrap();
+    server.handle(r#"{"jsonrpc": "2.0", "id": 1, "method": "add", "params": {"name": "Water the plants"}}"#).unwrap();
+    assert_eq!(journal.tasks().unwrap()[5].name(), "Water the plants");
+
+    // a change the watcher can't tell from the server's own write, same size and time, isn't read
+    let path = journal.path();
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+    let content = std::fs::read_to_string(&path).unwrap().replace("Buy milk", "Buy mint");
+    std::fs::write(&path, content).unwrap();
+    std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    let listed = server.handle(r#"{"jsonrpc": "2.0", "id": 2, "method": "list"}"#).unwrap();
+    assert!(listed.contains("Buy milk") && listed.contains("Water the plants"));
+}
+
+#[test]
+fn completing_a_recurring_task_adds_its_next_occurrence() {
+    let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let fixed = FixedClock::new(start);
+    let _guard = clock::install(fixed.clone());
+
+    let journal = TempJournal::new().unwrap();
+    let weekly = TaskOptions { repeat: Some(Recurrence::Weeks(1)), due: Some(start + Duration::days(1)), ..TaskOptions::default() };
+    Task::add_with(journal.path(), "Take out the bins".to_string(), Some(vec!["home".to_string()]), &weekly).unwrap();
+    fixed.advance(Duration::days(3));
+    Task::complete(journal.path(), 1).unwrap();
+
+    let tasks = journal.tasks().unwrap();
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].repeat(), None);
+    assert_eq!((tasks[1].name(), tasks[1].tags()), ("Take out the bins", &["home".to_string()][..]));
+    assert_eq!((tasks[1].due(), tasks[1].repeat()), (Some(start + Duration::days(8)), Some(Recurrence::Weeks(1))));
+
+    // the weeks missed entirely are skipped
+    fixed.advance(Duration::days(20));
+    let mut loaded = Journal::load(journal.path()).unwrap();
+    loaded.complete_task(2).unwrap();
+    assert_eq!(loaded.get(3).unwrap().due(), Some(start + Duration::days(29)));
+    loaded.save().unwrap();
+
+    // a task without due date comes back one period after it was completed
+    Task::add_with(journal.path(), "Pay rent".to_string(), None, &TaskOptions { repeat: Some(Recurrence::Days(30)), ..TaskOptions::default() }).unwrap();
+    Task::complete(journal.path(), 4).unwrap();
+    assert_eq!(journal.tasks().unwrap()[4].due(), Some(start + Duration::days(53)));
+    Task::tick(journal.path()).unwrap();
+    assert_eq!(journal.tasks().unwrap().len(), 5);
+}
+
+#[test]
+fn a_monthly_task_due_at_the_end_of_the_month_stays_there() {
+    let start = Utc.with_ymd_and_hms(2024, 1, 20, 9, 0, 0).unwrap();
+    let fixed = FixedClock::new(start);
+    let _guard = clock::install(fixed.clone());
+
+    let journal = TempJournal::new().unwrap();
+    let monthly = TaskOptions { repeat: Some(Recurrence::Months(1)), due: Some(Utc.with_ymd_and_hms(2024, 1, 31, 9, 0, 0).unwrap()), ..TaskOptions::default() };
+    Task::add_with(journal.path(), "Send the invoices".to_string(), None, &monthly).unwrap();
+    let mut dues = Vec::new();
+    for index in 1..=4 {
+        Task::complete(journal.path(), index).unwrap();
+        dues.push(journal.tasks().unwrap()[index].due().unwrap());
+    }
+
+    // February is too short for the 31st, the months after it are not
+    assert_eq!(dues, [Utc.with_ymd_and_hms(2024, 2, 29, 9, 0, 0).unwrap(), Utc.with_ymd_and_hms(2024, 3, 31, 9, 0, 0).unwrap(),
+                      Utc.with_ymd_and_hms(2024, 4, 30, 9, 0, 0).unwrap(), Utc.with_ymd_and_hms(2024, 5, 31, 9, 0, 0).unwrap()]);
+}
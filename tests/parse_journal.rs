@@ -0,0 +1,70 @@
+use rusty_journal_clap::task::{parse_journal, Task};
+use rusty_journal_clap::test_util::{Arbitrary, Rng};
+
+// Comment: the cases the fuzz target turned up or that it is meant to cover, so they stay fixed
+// without a nightly toolchain
+
+fn task_with_timestamp(timestamp: &str) -> String {
+    format!(r#"[{{"name": "x", "state": {{"type": "Active"}}, "tags": null, "creted_at": {}}}]"#, timestamp)
+}
+
+#[test]
+fn huge_numbers_are_errors() {
+    for timestamp in ["9223372036854775807", "-9223372036854775808", "18446744073709551616", "1e400", "1.5"] {
+        assert!(parse_journal(task_with_timestamp(timestamp).as_bytes()).is_err(), "{}", timestamp);
+    }
+}
+
+#[test]
+fn extreme_timestamps_can_be_printed() {
+    for timestamp in ["8210266876799", "-8334601228800"] {
+        let tasks = parse_journal(task_with_timestamp(timestamp).as_bytes()).unwrap();
+        assert!(!tasks[0].to_string().is_empty());
+    }
+}
+
+#[test]
+fn deep_nesting_is_an_error() {
+    let nested = format!(r#"[{{"name": {}"#, "[".repeat(100_000));
+    assert!(parse_journal(nested.as_bytes()).is_err());
+    assert!(parse_journal("[".repeat(100_000).as_bytes()).is_err());
+}
+
+#[test]
+fn invalid_utf8_is_an_error() {
+    assert!(parse_journal(b"[{\"name\": \"\xc3\x28\"}]").is_err());
+    assert!(parse_journal(b"\xff\xfe[]").is_err());
+}
+
+#[test]
+fn truncated_journal_is_an_error() {
+    assert!(parse_journal(b"").unwrap().is_empty());
+    assert!(parse_journal(b" \n").unwrap().is_empty());
+    assert!(parse_journal(b"[").is_err());
+    assert!(parse_journal(&task_with_timestamp("0").as_bytes()[..30]).is_err());
+}
+
+#[test]
+fn mutated_journals_never_panic() {
+    let mut rng = Rng::new(714);
+    for _ in 0..2_000 {
+        let tasks = Vec::<Task>::arbitrary(&mut rng);
+        let mut content = serde_json::to_vec(&tasks).unwrap();
+        for _ in 0..=rng.below(4) {
+            if content.is_empty() {
+                break;
+            }
+            let at = rng.below(content.len() as u64) as usize;
+            match rng.below(3) {
+                0 => content[at] = rng.next_u64() as u8,
+                1 => { content.remove(at); },
+                _ => content.insert(at, *rng.pick(b"[]{}\",:0-e\\")),
+            }
+        }
+        if let Ok(tasks) = parse_journal(&content) {
+            for task in tasks {
+                let _ = task.to_string();
+            }
+        }
+    }
+}
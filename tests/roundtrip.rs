@@ -0,0 +1,68 @@
+use rusty_journal_clap::storage::Backend;
+use rusty_journal_clap::task::Task;
+use rusty_journal_clap::test_util::{for_all, TempJournal};
+
+// Comment: every storage format gets a round trip here, so a format change can't silently drop
+// tags, mangle unicode names or shift timestamps
+
+#[test]
+fn task_round_trips_through_json() {
+    for_all(500, |task: &Task| {
+        let json = serde_json::to_string(task).unwrap();
+        assert_eq!(&serde_json::from_str::<Task>(&json).unwrap(), task);
+    });
+}
+
+#[test]
+fn tasks_round_trip_through_toml() {
+    for_all(300, |tasks: &Vec<Task>| {
+        // Comment: TOML integers are signed, so the ids are brought into the range of i64 first,
+        // past it the encoding fails rather than writing what other TOML readers would reject
+        let mut value = serde_json::to_value(tasks).unwrap();
+        for task in value.as_array_mut().unwrap() {
+            if let Some(id) = task["id"].as_u64() {
+                task["id"] = (id & i64::MAX as u64).into();
+            }
+            if let Some(parent) = task["parent"].as_u64() {
+                task["parent"] = (parent & i64::MAX as u64).into();
+            }
+            if let Some(related) = task.get_mut("related").and_then(|related| related.as_array_mut()) {
+                for id in related {
+                    *id = (id.as_u64().unwrap() & i64::MAX as u64).into();
+                }
+            }
+        }
+        let tasks: Vec<Task> = serde_json::from_value(value).unwrap();
+
+        let toml = Backend::Toml.encode(&tasks).unwrap();
+        assert_eq!(Backend::Toml.decode::<Vec<Task>>(&toml).unwrap(), tasks, "{}", String::from_utf8_lossy(&toml));
+    });
+}
+
+#[test]
+fn tasks_round_trip_through_the_journal_file() {
+    for_all(200, |tasks: &Vec<Task>| {
+        let journal = TempJournal::new().unwrap();
+        journal.write(tasks).unwrap();
+        assert_eq!(&journal.tasks().unwrap(), tasks);
+    });
+}
+
+#[test]
+fn appending_preserves_everything_but_the_ids() {
+    for_all(100, |tasks: &Vec<Task>| {
+        let journal = TempJournal::new().unwrap();
+        let other = TempJournal::new().unwrap();
+        other.write(tasks).unwrap();
+        Task::append(journal.path(), other.path()).unwrap();
+
+        let appended = journal.tasks().unwrap();
+        assert_eq!(appended.len(), tasks.len());
+        for (appended, task) in appended.iter().zip(tasks) {
+            assert_eq!(appended.name(), task.name());
+            assert_eq!(appended.tags(), task.tags());
+            assert_eq!(appended.created_at(), task.created_at());
+            assert_eq!(appended.completed_at(), task.completed_at());
+        }
+    });
+}
@@ -0,0 +1,43 @@
+use serde_json::Value;
+use rusty_journal_clap::schema;
+use rusty_journal_clap::task::Task;
+use rusty_journal_clap::test_util::for_all;
+
+// Comment: there is no validator available, so this checks the parts of the schema that drift
+// when a field is added to Task without updating the schema
+
+fn object_keys(value: &Value) -> Vec<String> {
+    value.as_object().unwrap().keys().cloned().collect()
+}
+
+#[test]
+fn schema_covers_every_serialized_field() {
+    let schema = schema::journal();
+    let task_schema = &schema["$defs"]["task"];
+    let properties = object_keys(&task_schema["properties"]);
+    let required = task_schema["required"].as_array().unwrap();
+
+    for_all(200, |task: &Task| {
+        let task = serde_json::to_value(task).unwrap();
+        for key in object_keys(&task) {
+            assert!(properties.contains(&key), "{} is missing from the schema", key);
+        }
+        for key in required {
+            assert!(task.get(key.as_str().unwrap()).is_some(), "{} is required but not always written", key);
+        }
+
+        let state = &task["state"];
+        let state_schema = schema["$defs"]["state"]["oneOf"].as_array()
+                                                              .unwrap()
+                                                              .iter()
+                                                              .find(|variant| variant["properties"]["type"]["const"] == state["type"])
+                                                              .unwrap();
+        let state_properties = object_keys(&state_schema["properties"]);
+        for key in object_keys(state) {
+            assert!(state_properties.contains(&key), "{} of the {} state is missing from the schema", key, state["type"]);
+        }
+        for key in state_schema["required"].as_array().unwrap() {
+            assert!(state.get(key.as_str().unwrap()).is_some(), "{} is required but not always written", key);
+        }
+    });
+}